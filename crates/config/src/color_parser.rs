@@ -20,8 +20,14 @@ impl TryFrom<String> for Color {
     type Error = ColorParserError;
 
     fn try_from(value: String) -> Result<Self, Self::Error> {
-        if let Some(hex) = value.strip_prefix('#') {
-            if hex.len() == 6 {
+        // `0xRRGGBB` is accepted as well, as colors are often copied from code
+        let hex = value
+            .strip_prefix('#')
+            .or_else(|| value.strip_prefix("0x"))
+            .or_else(|| value.strip_prefix("0X"));
+        if let Some(hex) = hex {
+            // `from_str_radix` would also take a leading sign
+            if hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
                 let color = u32::from_str_radix(hex, 16).map_err(|_| {
                     ColorParserError::InvalidFormat(format!("color {value} is not a valid format"))
                 })?;
@@ -140,3 +146,20 @@ impl Default for Color {
         Self(0x252525)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_colors_accept_both_prefixes() {
+        let parse = |value: &str| Color::try_from(value.to_string()).map(|color| color.0);
+
+        assert_eq!(parse("#ff0000").unwrap(), 0xff0000);
+        assert_eq!(parse("0xff0000").unwrap(), 0xff0000);
+        assert_eq!(parse("0X00FF00").unwrap(), 0x00ff00);
+        assert!(parse("0xZZ").is_err());
+        assert!(parse("0xZZZZZZ").is_err());
+        assert!(parse("0x+f0000").is_err());
+    }
+}
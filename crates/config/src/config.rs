@@ -1,7 +1,80 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 use xcb::x::KeyButMask;
 
+use crate::config_loader::MAX_WORKSPACES;
 use crate::keysyms::Keysym;
 
+/// places the windows matching `class` on a workspace, or changes whether they float
+#[derive(Debug, Clone)]
+pub struct Rule {
+    /// either the instance or the class name from `WM_CLASS`
+    pub(crate) class: String,
+    /// 0-indexed workspace the window is placed on, instead of the active one
+    pub(crate) workspace: Option<u8>,
+    pub(crate) floating: Option<bool>,
+    /// overrides `focus_new_clients` for the windows matching this rule
+    pub(crate) focus: Option<bool>,
+    /// 0-indexed monitor the window is placed on, instead of the active one
+    pub(crate) monitor: Option<usize>,
+}
+
+impl Rule {
+    pub fn class(&self) -> &str {
+        &self.class
+    }
+
+    pub fn workspace(&self) -> Option<u8> {
+        self.workspace
+    }
+
+    pub fn floating(&self) -> Option<bool> {
+        self.floating
+    }
+
+    pub fn focus(&self) -> Option<bool> {
+        self.focus
+    }
+
+    pub fn monitor(&self) -> Option<usize> {
+        self.monitor
+    }
+}
+
+/// overrides for a single workspace, written as `[[workspace]]` tables
+#[derive(Debug, Clone)]
+pub struct WorkspaceSettings {
+    /// 0-indexed workspace the settings apply to
+    pub(crate) index: u8,
+    pub(crate) inner_gap: Option<u32>,
+    pub(crate) outer_gap: Option<u32>,
+    pub(crate) gapless_monocle: Option<bool>,
+    /// overrides whether the layout of the workspace leaves the space reserved by docks uncovered
+    pub(crate) respect_struts: Option<bool>,
+}
+
+impl WorkspaceSettings {
+    pub fn index(&self) -> u8 {
+        self.index
+    }
+
+    pub fn inner_gap(&self) -> Option<u32> {
+        self.inner_gap
+    }
+
+    pub fn outer_gap(&self) -> Option<u32> {
+        self.outer_gap
+    }
+
+    pub fn gapless_monocle(&self) -> Option<bool> {
+        self.gapless_monocle
+    }
+
+    pub fn respect_struts(&self) -> Option<bool> {
+        self.respect_struts
+    }
+}
+
 #[derive(Debug)]
 pub struct Config {
     /// Leader key is an alias to make easy to switch the key used to execute commands, instead of
@@ -14,7 +87,10 @@ pub struct Config {
     pub(crate) actions: Vec<Action>,
     /// List of all `commands` defined in the configuration file
     pub(crate) commands: Vec<Command>,
-    /// total workspaces to display, this is constrained to >= 1 and <= 10.
+    /// alternate tables of actions by name, only one of them is active at a time through
+    /// `EnterMode`, replacing `actions` and `commands` until the mode is left
+    pub(crate) modes: HashMap<String, Vec<Action>>,
+    /// total workspaces to display, this is constrained to >= 1, and counts above 20 are clamped
     pub(crate) workspaces: u8,
     /// the size of the border to be used by the frames
     pub(crate) border_width: u16,
@@ -30,6 +106,136 @@ pub struct Config {
     pub(crate) focus_follow_mouse: bool,
     /// commands to be executed during window manager startup
     pub(crate) startup_commands: Vec<AutoCommand>,
+    /// space between adjacent tiled clients
+    /// default: 0
+    pub(crate) inner_gap: u32,
+    /// space between the tiled clients and the edges of the screen, measured from the edge of
+    /// the area left by bars, so clients sit `outer_gap` away from a bar
+    /// default: 0
+    pub(crate) outer_gap: u32,
+    /// whether the outer gap should also be applied to edges that have space reserved by a bar,
+    /// when false, the bar already separates the clients from that edge and the gap is skipped,
+    /// leaving the clients right against the bar
+    /// default: true
+    pub(crate) outer_gap_on_reserved_edges: bool,
+    /// portion of the screen width taken by the master area of the tall layout,
+    /// ranging from `0.1` to `0.9`
+    /// default: 0.5
+    pub(crate) master_ratio: f32,
+    /// side of the screen where the master area of the tall layout is placed, the
+    /// stack takes the opposite side
+    /// default: left
+    pub(crate) layout_orientation: LayoutOrientation,
+    /// names of the workspaces in order, workspaces without a name are called
+    /// `Workspace <number>`
+    pub(crate) workspace_names: Vec<String>,
+    /// `WM_CLASS` of the window used as the scratchpad, it is shown and hidden through the
+    /// `ToggleScratchpad` action instead of being tiled
+    /// default: none
+    pub(crate) scratchpad_class: Option<String>,
+    /// rules applied to windows when they are mapped, matched by `WM_CLASS` in order, the first
+    /// matching rule wins
+    pub(crate) rules: Vec<Rule>,
+    /// hides the border of a tiled client when it is the only tiled client on its workspace
+    /// default: false
+    pub(crate) smart_borders: bool,
+    /// drops the inner and outer gaps when there is a single tiled client on the workspace
+    /// default: false
+    pub(crate) smart_gaps: bool,
+    /// whether moving a client to another workspace also switches to that workspace
+    /// default: false
+    pub(crate) follow: bool,
+    /// color to be used by the border of clients demanding attention
+    /// default: #B33A3A
+    pub(crate) urgent_border_color: u32,
+    /// whether a window launched from a terminal listed on `terminal_classes` takes the place of
+    /// that terminal until it is closed
+    /// default: false
+    pub(crate) swallowing: bool,
+    /// `WM_CLASS` of the terminals that can be swallowed by the windows launched from them
+    /// default: []
+    pub(crate) terminal_classes: Vec<String>,
+    /// where new floating clients are placed, `center` of the screen, under the `cursor`,
+    /// or `smart` to avoid covering other floating clients
+    /// default: center
+    pub(crate) float_placement: FloatPlacement,
+    /// radius of the rounded corners of every frame, needs the SHAPE extension
+    /// default: 0
+    pub(crate) border_radius: u32,
+    /// settings that apply to a single workspace, overriding the global ones
+    pub(crate) workspace_settings: Vec<WorkspaceSettings>,
+    /// most verbose level written to the log, read once at startup
+    /// default: info
+    pub(crate) log_level: tracing::Level,
+    /// file the log is appended to, `~` and environment variables are expanded. The log
+    /// goes to stderr when unset, read once at startup
+    pub(crate) log_file: Option<PathBuf>,
+    /// name of the X cursor shown over the root window, from the standard cursor font. Read
+    /// once at startup
+    /// default: left_ptr
+    pub(crate) cursor: String,
+    /// move the pointer to the middle of a client whenever it gets focused
+    /// default: false
+    pub(crate) warp_cursor: bool,
+    /// lay monocle clients over the whole monitor without borders or gaps, can be set for a
+    /// single workspace through `[[workspace]]`
+    /// default: false
+    pub(crate) gapless_monocle: bool,
+    /// whether monocle clients leave the space reserved by docks, like bars, uncovered, can be
+    /// set for a single workspace through `[[workspace]]`
+    /// default: true
+    pub(crate) monocle_respect_struts: bool,
+    /// fade new windows in through `_NET_WM_WINDOW_OPACITY`, which needs a compositor like picom
+    /// default: false
+    pub(crate) animations: bool,
+    /// `WM_CLASS` of windows that are managed but never focused, like on-screen keyboards
+    pub(crate) no_focus_classes: Vec<String>,
+    /// whether the only tiled client of a workspace on the tall layout keeps its border. Unlike
+    /// `smart_borders` this only applies to the tall layout
+    /// default: true
+    pub(crate) single_window_border: bool,
+    /// how much of the space shared with its neighbour a tiled client gains or loses on each
+    /// resize action
+    /// default: 0.05
+    pub(crate) resize_step: f32,
+    /// whether every monitor has its own workspaces, or all of them share a single set where
+    /// each workspace is shown on one monitor at a time
+    /// default: per-monitor
+    pub(crate) workspace_scope: WorkspaceScope,
+    /// what happens to the focus when a new client shows up on a monitor other than the
+    /// active one
+    pub(crate) new_client_monitor_focus: NewClientMonitorFocus,
+    /// reserve the edge a `_NET_WM_WINDOW_TYPE_DOCK` window is flush against when it sets no
+    /// struts, so bars that only set their type are not covered
+    /// default: false
+    pub(crate) dock_auto_reserve: bool,
+    /// which client gets the focus after switching to a workspace
+    pub(crate) workspace_switch_focus: WorkspaceSwitchFocus,
+    /// draws a darker window behind every tiled and floating frame as a drop shadow, for setups
+    /// without a compositor
+    /// default: false
+    pub(crate) fake_shadow: bool,
+    /// color of the shadow drawn with `fake_shadow`
+    /// default: #000000
+    pub(crate) shadow_color: u32,
+    /// how far the shadow drawn with `fake_shadow` reaches past the right and bottom edges of
+    /// the frame
+    /// default: 4
+    pub(crate) shadow_offset: u32,
+    /// whether tall clients leave the space reserved by docks, like bars, uncovered, can be set
+    /// for a single workspace through `[[workspace]]`
+    /// default: true
+    pub(crate) tall_respect_struts: bool,
+    /// command the file written by `ShowKeybinds` is opened with, like `alacritty -e less`, the
+    /// path of the file is added as its last argument. Without one the bindings go to the log
+    /// default: none
+    pub(crate) keybinds_viewer: Option<String>,
+    /// command spawned once the window manager is done starting up, every time it starts
+    /// default: none
+    pub(crate) on_ready: Option<AutoCommand>,
+    /// create workspaces past `workspaces` when they are switched or moved to, dropping them
+    /// again once they are empty and not shown
+    pub(crate) dynamic_workspaces: bool,
 }
 
 impl Config {
@@ -41,10 +247,81 @@ impl Config {
         &self.commands
     }
 
+    pub fn mode_actions(&self, mode: &str) -> Option<&[Action]> {
+        self.modes.get(mode).map(Vec::as_slice)
+    }
+
+    /// every key binding written out as a table, one section per set of modifiers, followed
+    /// by one section per mode
+    pub fn keybinds(&self) -> String {
+        let mut groups: BTreeMap<String, Vec<(String, String)>> = BTreeMap::new();
+        for action in self.actions.iter() {
+            groups
+                .entry(action.modifier.to_string())
+                .or_default()
+                .push((action.key.to_string(), format!("{:?}", action.action)));
+        }
+        for command in self.commands.iter() {
+            let exec = std::iter::once(command.command.as_str())
+                .chain(command.args.iter().map(String::as_str))
+                .collect::<Vec<_>>()
+                .join(" ");
+            groups
+                .entry(command.modifier.to_string())
+                .or_default()
+                .push((command.key.to_string(), format!("exec {exec}")));
+        }
+
+        let mut modes = self.modes.iter().collect::<Vec<_>>();
+        modes.sort_by_key(|(name, _)| name.as_str());
+
+        let mut sections = groups
+            .into_iter()
+            .map(|(modifier, binds)| match modifier.is_empty() {
+                true => (String::from("no modifiers"), binds),
+                false => (modifier, binds),
+            })
+            .collect::<Vec<_>>();
+        sections.extend(modes.into_iter().map(|(name, actions)| {
+            let binds = actions
+                .iter()
+                .map(|action| {
+                    let key = match action.modifier.inner() {
+                        0 => action.key.to_string(),
+                        _ => format!("{}+{}", action.modifier, action.key),
+                    };
+                    (key, format!("{:?}", action.action))
+                })
+                .collect();
+            (format!("mode {name}"), binds)
+        }));
+
+        sections
+            .into_iter()
+            .map(|(title, binds)| {
+                let width = binds.iter().map(|(key, _)| key.len()).max().unwrap_or(0);
+                let lines = binds
+                    .iter()
+                    .map(|(key, description)| format!("  {key:width$}  {description}\n"))
+                    .collect::<String>();
+                format!("{title}\n{lines}")
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
     pub fn workspaces(&self) -> u8 {
         self.workspaces
     }
 
+    /// most workspaces a screen can reach, dynamic workspaces can grow up to the hard limit
+    pub fn workspace_limit(&self) -> u8 {
+        match self.dynamic_workspaces {
+            true => MAX_WORKSPACES,
+            false => self.workspaces,
+        }
+    }
+
     pub fn border_width(&self) -> u16 {
         self.border_width
     }
@@ -69,16 +346,207 @@ impl Config {
         &self.startup_commands
     }
 
+    pub fn inner_gap(&self) -> u32 {
+        self.inner_gap
+    }
+
+    pub fn outer_gap(&self) -> u32 {
+        self.outer_gap
+    }
+
+    pub fn outer_gap_on_reserved_edges(&self) -> bool {
+        self.outer_gap_on_reserved_edges
+    }
+
+    pub fn master_ratio(&self) -> f32 {
+        self.master_ratio
+    }
+
+    pub fn layout_orientation(&self) -> LayoutOrientation {
+        self.layout_orientation.clone()
+    }
+
+    pub fn workspace_names(&self) -> &[String] {
+        &self.workspace_names
+    }
+
+    pub fn scratchpad_class(&self) -> Option<&str> {
+        self.scratchpad_class.as_deref()
+    }
+
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    pub fn smart_borders(&self) -> bool {
+        self.smart_borders
+    }
+
+    pub fn smart_gaps(&self) -> bool {
+        self.smart_gaps
+    }
+
+    pub fn follow(&self) -> bool {
+        self.follow
+    }
+
+    pub fn urgent_border_color(&self) -> u32 {
+        self.urgent_border_color
+    }
+
+    pub fn swallowing(&self) -> bool {
+        self.swallowing
+    }
+
+    pub fn terminal_classes(&self) -> &[String] {
+        &self.terminal_classes
+    }
+
+    pub fn float_placement(&self) -> FloatPlacement {
+        self.float_placement.clone()
+    }
+
+    pub fn border_radius(&self) -> u32 {
+        self.border_radius
+    }
+
+    pub fn workspace_settings(&self) -> &[WorkspaceSettings] {
+        &self.workspace_settings
+    }
+
+    pub fn log_level(&self) -> tracing::Level {
+        self.log_level
+    }
+
+    pub fn log_file(&self) -> Option<&Path> {
+        self.log_file.as_deref()
+    }
+
+    pub fn cursor(&self) -> &str {
+        &self.cursor
+    }
+
+    pub fn warp_cursor(&self) -> bool {
+        self.warp_cursor
+    }
+
+    pub fn gapless_monocle(&self) -> bool {
+        self.gapless_monocle
+    }
+
+    pub fn monocle_respect_struts(&self) -> bool {
+        self.monocle_respect_struts
+    }
+
+    pub fn animations(&self) -> bool {
+        self.animations
+    }
+
+    pub fn no_focus_classes(&self) -> &[String] {
+        &self.no_focus_classes
+    }
+
+    pub fn single_window_border(&self) -> bool {
+        self.single_window_border
+    }
+
+    pub fn resize_step(&self) -> f32 {
+        self.resize_step
+    }
+
+    pub fn workspace_scope(&self) -> WorkspaceScope {
+        self.workspace_scope.clone()
+    }
+
+    pub fn new_client_monitor_focus(&self) -> NewClientMonitorFocus {
+        self.new_client_monitor_focus.clone()
+    }
+
+    pub fn dock_auto_reserve(&self) -> bool {
+        self.dock_auto_reserve
+    }
+
+    pub fn workspace_switch_focus(&self) -> WorkspaceSwitchFocus {
+        self.workspace_switch_focus.clone()
+    }
+
+    pub fn fake_shadow(&self) -> bool {
+        self.fake_shadow
+    }
+
+    pub fn shadow_color(&self) -> u32 {
+        self.shadow_color
+    }
+
+    pub fn shadow_offset(&self) -> u32 {
+        self.shadow_offset
+    }
+
+    pub fn tall_respect_struts(&self) -> bool {
+        self.tall_respect_struts
+    }
+
+    pub fn keybinds_viewer(&self) -> Option<&str> {
+        self.keybinds_viewer.as_deref()
+    }
+
+    pub fn on_ready(&self) -> Option<&AutoCommand> {
+        self.on_ready.as_ref()
+    }
+
+    pub fn dynamic_workspaces(&self) -> bool {
+        self.dynamic_workspaces
+    }
+
     pub fn update(&mut self, other: Config) {
         self.leader = other.leader;
         self.actions = other.actions;
         self.commands = other.commands;
+        self.modes = other.modes;
         self.workspaces = other.workspaces;
         self.border_width = other.border_width;
         self.border_color = other.border_color;
         self.active_border_color = other.active_border_color;
         self.focus_new_clients = other.focus_new_clients;
         self.focus_follow_mouse = other.focus_follow_mouse;
+        self.inner_gap = other.inner_gap;
+        self.outer_gap = other.outer_gap;
+        self.outer_gap_on_reserved_edges = other.outer_gap_on_reserved_edges;
+        self.master_ratio = other.master_ratio;
+        self.layout_orientation = other.layout_orientation;
+        self.workspace_names = other.workspace_names;
+        self.scratchpad_class = other.scratchpad_class;
+        self.rules = other.rules;
+        self.smart_borders = other.smart_borders;
+        self.smart_gaps = other.smart_gaps;
+        self.follow = other.follow;
+        self.urgent_border_color = other.urgent_border_color;
+        self.swallowing = other.swallowing;
+        self.terminal_classes = other.terminal_classes;
+        self.float_placement = other.float_placement;
+        self.border_radius = other.border_radius;
+        self.workspace_settings = other.workspace_settings;
+        self.log_level = other.log_level;
+        self.log_file = other.log_file;
+        self.cursor = other.cursor;
+        self.warp_cursor = other.warp_cursor;
+        self.gapless_monocle = other.gapless_monocle;
+        self.monocle_respect_struts = other.monocle_respect_struts;
+        self.animations = other.animations;
+        self.no_focus_classes = other.no_focus_classes;
+        self.single_window_border = other.single_window_border;
+        self.resize_step = other.resize_step;
+        self.workspace_scope = other.workspace_scope;
+        self.new_client_monitor_focus = other.new_client_monitor_focus;
+        self.dock_auto_reserve = other.dock_auto_reserve;
+        self.workspace_switch_focus = other.workspace_switch_focus;
+        self.fake_shadow = other.fake_shadow;
+        self.shadow_color = other.shadow_color;
+        self.shadow_offset = other.shadow_offset;
+        self.tall_respect_struts = other.tall_respect_struts;
+        self.keybinds_viewer = other.keybinds_viewer;
+        self.on_ready = other.on_ready;
+        self.dynamic_workspaces = other.dynamic_workspaces;
     }
 }
 
@@ -94,19 +562,140 @@ impl Default for Config {
             leader: AvailableLeaderKeys::Mod1,
             actions: vec![],
             commands: vec![],
+            modes: HashMap::default(),
             startup_commands: vec![],
+            inner_gap: 0,
+            outer_gap: 0,
+            outer_gap_on_reserved_edges: true,
+            master_ratio: 0.5,
+            layout_orientation: LayoutOrientation::Left,
+            workspace_names: vec![],
+            scratchpad_class: None,
+            rules: vec![],
+            smart_borders: false,
+            smart_gaps: false,
+            follow: false,
+            urgent_border_color: 0xB33A3A,
+            swallowing: false,
+            terminal_classes: vec![],
+            float_placement: FloatPlacement::Center,
+            border_radius: 0,
+            workspace_settings: Vec::default(),
+            log_level: tracing::Level::INFO,
+            log_file: None,
+            cursor: String::from("left_ptr"),
+            warp_cursor: false,
+            gapless_monocle: false,
+            monocle_respect_struts: true,
+            animations: false,
+            no_focus_classes: vec![],
+            single_window_border: true,
+            resize_step: 0.05,
+            workspace_scope: WorkspaceScope::PerMonitor,
+            new_client_monitor_focus: NewClientMonitorFocus::Focus,
+            dock_auto_reserve: false,
+            workspace_switch_focus: WorkspaceSwitchFocus::Last,
+            fake_shadow: false,
+            shadow_color: 0x000000,
+            shadow_offset: 4,
+            tall_respect_struts: true,
+            keybinds_viewer: None,
+            on_ready: None,
+            dynamic_workspaces: false,
         }
     }
 }
 
 #[derive(Default, Debug, Clone)]
 pub enum AvailableLeaderKeys {
+    /// usually alt
     #[default]
     Mod1,
+    /// usually super, the windows key
+    Mod4,
     Shift,
     Control,
 }
 
+/// where the master area of the tall layout is placed
+#[derive(Default, Debug, Clone, PartialEq)]
+pub enum LayoutOrientation {
+    #[default]
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+/// how workspaces are shared between monitors
+#[derive(Default, Debug, Clone, PartialEq)]
+pub enum WorkspaceScope {
+    /// a single set of workspaces, each of them shown on one monitor at a time
+    Global,
+    /// every monitor has its own workspaces
+    #[default]
+    PerMonitor,
+}
+
+/// how the focus is handled for new clients placed on a monitor other than the active one
+#[derive(Default, Debug, Clone, PartialEq)]
+pub enum NewClientMonitorFocus {
+    /// focus the client and make its monitor the active one
+    Switch,
+    /// focus the client on its monitor, keeping the active monitor
+    #[default]
+    Focus,
+    /// leave the focus on that monitor untouched
+    Keep,
+}
+
+/// which edge of a key stroke fires a binding
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub enum KeyTrigger {
+    #[default]
+    Press,
+    /// fires once the key is let go, the modifiers must still be held at that point
+    Release,
+}
+
+/// which client is focused after switching to a workspace, every policy falls back to the
+/// next one when it finds no client
+#[derive(Default, Debug, Clone, PartialEq)]
+pub enum WorkspaceSwitchFocus {
+    /// the client focused when the workspace was left
+    #[default]
+    Last,
+    /// the first client of the workspace, usually the master
+    First,
+    /// the client under the cursor
+    Cursor,
+}
+
+/// where new floating clients are placed on the active screen
+#[derive(Default, Debug, Clone, PartialEq)]
+pub enum FloatPlacement {
+    #[default]
+    Center,
+    Cursor,
+    Smart,
+}
+
+/// layouts that can be set on a workspace
+#[derive(Debug, Clone, PartialEq)]
+pub enum AvailableLayouts {
+    Tall,
+    Monocle,
+}
+
+/// directions used by the actions that reach for a neighbouring monitor
+#[derive(Debug, Clone, PartialEq)]
+pub enum AvailableDirections {
+    Left,
+    Down,
+    Up,
+    Right,
+}
+
 /// All the actions available for any given key combination
 #[derive(Debug, Clone)]
 pub enum AvailableActions {
@@ -126,12 +715,23 @@ pub enum AvailableActions {
     MoveUp,
     /// moves a client one position to the right, shifting other clients as needed
     MoveRight,
+    /// moves the border of the focused tiled client to the left
+    ResizeLeft,
+    /// moves the border of the focused tiled client to the bottom
+    ResizeDown,
+    /// moves the border of the focused tiled client to the top
+    ResizeUp,
+    /// moves the border of the focused tiled client to the right
+    ResizeRight,
     /// closes the focused client
     Close,
     /// Exits lucky
     Quit,
     /// Reloads the configuration file
     Reload,
+    /// replaces the running window manager with the `lucky` binary on disk, keeping the
+    /// arrangement of the windows
+    Restart,
     /// switches to workspace 1
     Workspace1,
     /// switches to workspace 2
@@ -150,8 +750,8 @@ pub enum AvailableActions {
     Workspace8,
     /// switches to workspace 9
     Workspace9,
-    /// set focused client to be fullscreen
-    Fullscreen,
+    /// toggles the focused client between covering the whole monitor and its previous place
+    ToggleFullscreen,
     /// move the focused client to workspace 1
     MoveToWorkspace1,
     /// move the focused client to workspace 2
@@ -170,28 +770,84 @@ pub enum AvailableActions {
     MoveToWorkspace8,
     /// move the focused client to workspace 9
     MoveToWorkspace9,
+    /// toggles the border of the focused client on and off
+    ToggleBorder,
+    /// move the focused client to the next workspace and follow it, wrapping at the last one
+    CarouselWindow,
+    /// switches the focused client between tiled and floating
+    ToggleFloating,
+    /// switch to the workspace with the most clients on the active screen
+    FocusBusiest,
+    /// grow the master area of the active workspace
+    IncreaseMaster,
+    /// shrink the master area of the active workspace
+    DecreaseMaster,
+    /// add one more client to the master area of the active workspace
+    IncreaseMasterCount,
+    /// remove one client from the master area of the active workspace
+    DecreaseMasterCount,
+    /// puts half of the clients of the active workspace on the master area
+    DistributeEven,
+    /// switch the active workspace to the next layout
+    CycleLayout,
+    /// set the layout of the active workspace, written as `{ SetLayout = "Monocle" }`
+    SetLayout(AvailableLayouts),
+    /// shows the scratchpad on the active workspace, or hides it when it is shown
+    ToggleScratchpad,
+    /// swaps the focused client with the master client
+    SwapWithMaster,
+    /// focuses the master client of the active workspace
+    FocusMaster,
+    /// moves every client of the active workspace to the monitor in the given direction, written
+    /// as `{ MoveWorkspaceToMonitor = "Right" }`
+    MoveWorkspaceToMonitor(AvailableDirections),
+    /// focuses the next client across every workspace and monitor, wrapping around at the last one
+    CycleFocusNext,
+    /// focuses the previous client across every workspace and monitor, wrapping around at the
+    /// first one
+    CycleFocusPrev,
+    /// focuses the client that was focused before the current one, wherever it is
+    FocusLastWindow,
+    /// writes every key binding to a file opened with `keybinds_viewer`, or to the log
+    ShowKeybinds,
+    /// shows the focused client on every workspace of its monitor, or back on a single one
+    ToggleSticky,
+    /// splits the stack of the tall layout on the active workspace the other way around
+    ToggleStackDirection,
+    /// focuses the monitor in the given direction, no matter where the focused client is on
+    /// the active one, written as `{ FocusMonitor = "Right" }`
+    FocusMonitor(AvailableDirections),
+    /// replaces the bindings with the ones of the named mode until it is left, written as
+    /// `{ EnterMode = "resize" }`
+    EnterMode(String),
+    /// goes back to the regular bindings, so does pressing `Escape` while in a mode
+    ExitMode,
 }
 
 #[derive(Debug)]
 pub struct Action {
     /// Bitflag modifiers required to execute this action, example: `0x0008` maps to `Mod1`
-    pub(crate) modifier: ActionModifier,
+    pub(crate) modifier: Modifiers,
     /// The keysym used to describe this action, example: `XK_Return` matches with `Enter`
     pub(crate) key: Keysym,
     /// One of the possible actions to be performed by a key combination
     pub(crate) action: AvailableActions,
+    /// Whether the action fires when the key is pressed or released
+    pub(crate) on: KeyTrigger,
 }
 
 #[derive(Debug)]
 pub struct Command {
     /// Bitflag modifiers required to execute this command, example: `0x0008` maps to `Mod1`
-    pub(crate) modifier: u32,
+    pub(crate) modifier: Modifiers,
     /// The keysym used to describe this command, example: `XK_Return` matches with `Enter`
     pub(crate) key: Keysym,
     /// The string to be spawned when this command is called
     pub(crate) command: String,
     /// the arguments to be passed to the program that will be spawned
     pub(crate) args: Vec<String>,
+    /// Whether the command is spawned when the key is pressed or released
+    pub(crate) on: KeyTrigger,
 }
 
 #[derive(Debug)]
@@ -202,10 +858,29 @@ pub struct AutoCommand {
     pub(crate) args: Vec<String>,
 }
 
-#[derive(Debug, Clone, Copy)]
-pub struct ActionModifier(u32);
+/// Bitflag of the modifiers that must be held for a binding to fire, the bits match the X11
+/// modifier masks, so `Modifiers::MOD1 | Modifiers::SHIFT` is `0x0009`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Modifiers(u32);
+
+impl Modifiers {
+    pub const SHIFT: Modifiers = Modifiers(0x0001);
+    /// caps lock
+    pub const LOCK: Modifiers = Modifiers(0x0002);
+    pub const CONTROL: Modifiers = Modifiers(0x0004);
+    /// usually alt
+    pub const MOD1: Modifiers = Modifiers(0x0008);
+    /// usually num lock
+    pub const MOD2: Modifiers = Modifiers(0x0010);
+    pub const MOD3: Modifiers = Modifiers(0x0020);
+    /// usually super, the windows key
+    pub const MOD4: Modifiers = Modifiers(0x0040);
+    pub const MOD5: Modifiers = Modifiers(0x0080);
+
+    /// modifiers that toggle instead of being held, having them active should not change which
+    /// binding a key press triggers
+    pub const LOCKS: Modifiers = Modifiers(Self::LOCK.0 | Self::MOD2.0);
 
-impl ActionModifier {
     pub fn new(value: u32) -> Self {
         Self(value)
     }
@@ -215,18 +890,51 @@ impl ActionModifier {
     }
 }
 
+/// written like the chords on the config, as in `Control+Shift+Super`
+impl std::fmt::Display for Modifiers {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let names = [
+            (Self::CONTROL, "Control"),
+            (Self::SHIFT, "Shift"),
+            (Self::MOD1, "Alt"),
+            (Self::MOD3, "Mod3"),
+            (Self::MOD4, "Super"),
+            (Self::MOD5, "Mod5"),
+        ];
+        let held = names
+            .into_iter()
+            .filter(|(modifier, _)| self.0 & modifier.0 != 0)
+            .map(|(_, name)| name)
+            .collect::<Vec<_>>();
+
+        write!(f, "{}", held.join("+"))
+    }
+}
+
+impl std::ops::BitOr for Modifiers {
+    type Output = Modifiers;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Modifiers(self.0 | rhs.0)
+    }
+}
+
 impl Action {
     pub fn key(&self) -> Keysym {
         self.key.clone()
     }
 
-    pub fn modifiers(&self) -> ActionModifier {
+    pub fn modifiers(&self) -> Modifiers {
         self.modifier
     }
 
     pub fn action(&self) -> AvailableActions {
         self.action.clone()
     }
+
+    pub fn on(&self) -> KeyTrigger {
+        self.on
+    }
 }
 
 impl Command {
@@ -234,7 +942,7 @@ impl Command {
         self.key.clone()
     }
 
-    pub fn modifiers(&self) -> u32 {
+    pub fn modifiers(&self) -> Modifiers {
         self.modifier
     }
 
@@ -245,6 +953,10 @@ impl Command {
     pub fn args(&self) -> &[String] {
         &self.args
     }
+
+    pub fn on(&self) -> KeyTrigger {
+        self.on
+    }
 }
 
 impl AutoCommand {
@@ -257,8 +969,11 @@ impl AutoCommand {
     }
 }
 
-impl From<ActionModifier> for KeyButMask {
-    fn from(value: ActionModifier) -> Self {
-        KeyButMask::from_bits(value.0).expect("action modifiers from config file must be valid")
+/// strips the lock modifiers and the pointer buttons from the state of an event, leaving only
+/// the modifiers that are relevant for matching a binding
+impl From<KeyButMask> for Modifiers {
+    fn from(value: KeyButMask) -> Self {
+        let held = Self::SHIFT | Self::CONTROL | Self::MOD1 | Self::MOD3 | Self::MOD4 | Self::MOD5;
+        Modifiers(value.bits() & held.0)
     }
 }
@@ -1,12 +1,15 @@
-use std::ops::Add;
-
 use crate::{
     color_parser::Color,
     config::{
-        Action, ActionModifier, AutoCommand, AvailableActions, AvailableLeaderKeys, Command, Config,
+        Action, AutoCommand, AvailableActions, AvailableDirections, AvailableLayouts,
+        AvailableLeaderKeys, Command, Config, FloatPlacement, KeyTrigger, LayoutOrientation,
+        Modifiers, NewClientMonitorFocus, Rule, WorkspaceScope, WorkspaceSettings,
+        WorkspaceSwitchFocus,
     },
 };
 use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
 
 #[derive(Deserialize)]
 pub struct UnresolvedConfig {
@@ -16,39 +19,198 @@ pub struct UnresolvedConfig {
     focus_follow_mouse: Option<bool>,
     active_border_color: Option<String>,
     focus_new_clients: Option<bool>,
-    leader: UnresolvedLeader,
+    #[serde(alias = "mod_key")]
+    leader: Option<UnresolvedLeader>,
     actions: Vec<UnresolvedActionEntry>,
     commands: Vec<UnresolvedCommandEntry>,
+    modes: Option<HashMap<String, UnresolvedMode>>,
+    #[serde(alias = "autostart")]
     startup_commands: Option<Vec<String>>,
+    inner_gap: Option<u32>,
+    outer_gap: Option<u32>,
+    outer_gap_on_reserved_edges: Option<bool>,
+    master_ratio: Option<f32>,
+    layout_orientation: Option<UnresolvedLayoutOrientation>,
+    workspace_names: Option<Vec<String>>,
+    scratchpad_class: Option<String>,
+    rules: Option<Vec<UnresolvedRule>>,
+    smart_borders: Option<bool>,
+    smart_gaps: Option<bool>,
+    follow: Option<bool>,
+    urgent_border_color: Option<String>,
+    swallowing: Option<bool>,
+    terminal_classes: Option<Vec<String>>,
+    float_placement: Option<UnresolvedFloatPlacement>,
+    border_radius: Option<u32>,
+    #[serde(rename = "workspace")]
+    workspace_settings: Option<Vec<UnresolvedWorkspaceSettings>>,
+    log: Option<UnresolvedLog>,
+    cursor: Option<String>,
+    warp_cursor: Option<bool>,
+    gapless_monocle: Option<bool>,
+    monocle_respect_struts: Option<bool>,
+    animations: Option<bool>,
+    no_focus_classes: Option<Vec<String>>,
+    single_window_border: Option<bool>,
+    resize_step: Option<f32>,
+    workspace_scope: Option<UnresolvedWorkspaceScope>,
+    new_client_monitor_focus: Option<UnresolvedNewClientMonitorFocus>,
+    dock_auto_reserve: Option<bool>,
+    workspace_switch_focus: Option<UnresolvedWorkspaceSwitchFocus>,
+    fake_shadow: Option<bool>,
+    shadow_color: Option<String>,
+    shadow_offset: Option<u32>,
+    tall_respect_struts: Option<bool>,
+    keybinds_viewer: Option<String>,
+    on_ready: Option<String>,
+    dynamic_workspaces: Option<bool>,
+}
+
+#[derive(Deserialize)]
+struct UnresolvedRule {
+    class: String,
+    /// workspaces are written 1-indexed, like the workspace actions
+    workspace: Option<u8>,
+    floating: Option<bool>,
+    focus: Option<bool>,
+    /// monitors are written 1-indexed, like the workspaces
+    monitor: Option<usize>,
+}
+
+/// the `[log]` section
+#[derive(Deserialize, Default)]
+struct UnresolvedLog {
+    level: Option<UnresolvedLogLevel>,
+    file: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum UnresolvedLogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+#[derive(Deserialize)]
+struct UnresolvedWorkspaceSettings {
+    /// workspaces are written 1-indexed, like the workspace actions
+    index: u8,
+    inner_gap: Option<u32>,
+    outer_gap: Option<u32>,
+    gapless_monocle: Option<bool>,
+    respect_struts: Option<bool>,
 }
 
 #[derive(Deserialize)]
 enum UnresolvedLeader {
+    #[serde(alias = "Ctrl")]
     Control,
     Shift,
+    #[serde(alias = "Alt")]
     Mod1,
+    #[serde(alias = "Super")]
+    Mod4,
+}
+
+#[derive(Deserialize)]
+enum UnresolvedLayout {
+    Tall,
+    Monocle,
+}
+
+#[derive(Deserialize)]
+enum UnresolvedDirection {
+    Left,
+    Down,
+    Up,
+    Right,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum UnresolvedLayoutOrientation {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum UnresolvedWorkspaceScope {
+    Global,
+    PerMonitor,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum UnresolvedNewClientMonitorFocus {
+    Switch,
+    Focus,
+    Keep,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum UnresolvedWorkspaceSwitchFocus {
+    Last,
+    First,
+    Cursor,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum UnresolvedFloatPlacement {
+    Center,
+    Cursor,
+    Smart,
 }
 
 #[derive(Deserialize, Clone)]
 enum UnresolvedModifier {
+    #[serde(alias = "Mod")]
     Leader,
+    #[serde(alias = "Ctrl")]
     Control,
     Shift,
+    #[serde(alias = "Alt")]
     Mod1,
+    #[serde(alias = "Super")]
+    Mod4,
 }
 
 #[derive(Deserialize)]
 struct UnresolvedActionEntry {
+    #[serde(default)]
     modifiers: Vec<UnresolvedModifier>,
     key: String,
     action: UnresolvedAction,
+    on: Option<UnresolvedKeyTrigger>,
+}
+
+/// a `[modes.<name>]` table
+#[derive(Deserialize)]
+struct UnresolvedMode {
+    actions: Vec<UnresolvedActionEntry>,
 }
 
 #[derive(Deserialize)]
 struct UnresolvedCommandEntry {
+    #[serde(default)]
     modifiers: Vec<UnresolvedModifier>,
     key: String,
     command: String,
+    on: Option<UnresolvedKeyTrigger>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum UnresolvedKeyTrigger {
+    Press,
+    Release,
 }
 
 #[derive(Deserialize)]
@@ -61,8 +223,13 @@ enum UnresolvedAction {
     MoveDown,
     MoveUp,
     MoveRight,
+    ResizeLeft,
+    ResizeDown,
+    ResizeUp,
+    ResizeRight,
     Close,
     Reload,
+    Restart,
     Quit,
     Workspace1,
     Workspace2,
@@ -82,22 +249,374 @@ enum UnresolvedAction {
     MoveToWorkspace7,
     MoveToWorkspace8,
     MoveToWorkspace9,
-    Fullscreen,
+    #[serde(alias = "Fullscreen")]
+    ToggleFullscreen,
+    ToggleBorder,
+    CarouselWindow,
+    ToggleFloating,
+    FocusBusiest,
+    IncreaseMaster,
+    DecreaseMaster,
+    IncreaseMasterCount,
+    DecreaseMasterCount,
+    DistributeEven,
+    CycleLayout,
+    SetLayout(UnresolvedLayout),
+    ToggleScratchpad,
+    SwapWithMaster,
+    FocusMaster,
+    MoveWorkspaceToMonitor(UnresolvedDirection),
+    CycleFocusNext,
+    CycleFocusPrev,
+    FocusLastWindow,
+    ShowKeybinds,
+    ToggleSticky,
+    ToggleStackDirection,
+    FocusMonitor(UnresolvedDirection),
+    EnterMode(String),
+    ExitMode,
 }
 
+/// every error refers to the config field that caused it, like `actions[2].key`, so users can
+/// find what to fix
+#[derive(Debug)]
 pub enum ConfigError {
-    Key(String),
-    Workspaces(String),
-    BorderWidth(String),
-    BorderColor(String),
-    InvalidCommand(String),
-    Color(String),
+    /// the file is not valid toml, or a value does not have the expected type. `location` is
+    /// the line and column of the offending value, when known
+    Parse {
+        field: String,
+        location: Option<(usize, usize)>,
+        message: String,
+    },
+    Key {
+        field: String,
+        message: String,
+    },
+    Workspaces {
+        field: String,
+        message: String,
+    },
+    BorderWidth {
+        field: String,
+        message: String,
+    },
+    BorderColor {
+        field: String,
+        message: String,
+    },
+    InvalidCommand {
+        field: String,
+        message: String,
+    },
+    Color {
+        field: String,
+        message: String,
+    },
+    Mode {
+        field: String,
+        message: String,
+    },
+    MasterRatio {
+        field: String,
+        message: String,
+    },
+}
+
+impl ConfigError {
+    pub fn field(&self) -> &str {
+        match self {
+            ConfigError::Parse { field, .. }
+            | ConfigError::Key { field, .. }
+            | ConfigError::Workspaces { field, .. }
+            | ConfigError::BorderWidth { field, .. }
+            | ConfigError::BorderColor { field, .. }
+            | ConfigError::InvalidCommand { field, .. }
+            | ConfigError::Color { field, .. }
+            | ConfigError::Mode { field, .. }
+            | ConfigError::MasterRatio { field, .. } => field,
+        }
+    }
+
+    /// errors raised while converting a single value don't know which field it came from, so
+    /// the field is filled in by whoever is converting that field
+    fn in_field(mut self, name: impl Into<String>) -> Self {
+        match &mut self {
+            ConfigError::Parse { field, .. }
+            | ConfigError::Key { field, .. }
+            | ConfigError::Workspaces { field, .. }
+            | ConfigError::BorderWidth { field, .. }
+            | ConfigError::BorderColor { field, .. }
+            | ConfigError::InvalidCommand { field, .. }
+            | ConfigError::Color { field, .. }
+            | ConfigError::Mode { field, .. }
+            | ConfigError::MasterRatio { field, .. } => *field = name.into(),
+        }
+        self
+    }
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let ConfigError::Parse {
+            location: Some((line, column)),
+            ..
+        } = self
+        {
+            write!(f, "line {line}, column {column}: ")?;
+        }
+
+        if !self.field().is_empty() {
+            write!(f, "{}: ", self.field())?;
+        }
+
+        match self {
+            ConfigError::Parse { message, .. }
+            | ConfigError::Key { message, .. }
+            | ConfigError::Workspaces { message, .. }
+            | ConfigError::BorderWidth { message, .. }
+            | ConfigError::BorderColor { message, .. }
+            | ConfigError::InvalidCommand { message, .. }
+            | ConfigError::Color { message, .. }
+            | ConfigError::Mode { message, .. }
+            | ConfigError::MasterRatio { message, .. } => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// most workspaces a screen can have, larger counts are clamped. Only the first 9 can be reached
+/// through the workspace actions, the others through ipc
+pub(crate) const MAX_WORKSPACES: u8 = 20;
+
+/// key holding the list of files a config includes, like `include = ["keybinds.toml"]`. It is
+/// resolved while reading the files, so it never reaches `UnresolvedConfig`
+pub const INCLUDE_KEY: &str = "include";
+
+/// expands a leading `~` to `$HOME`, and every `$VAR` or `${VAR}` to the value of the variable.
+/// Variables that are not set are left as written
+pub fn expand_path(value: &str) -> PathBuf {
+    PathBuf::from(expand(value))
+}
+
+/// the expansion done by `expand_path`, for values that are not only paths, like commands
+fn expand(value: &str) -> String {
+    let value = match value.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => format!("$HOME{rest}"),
+        _ => value.to_string(),
+    };
+
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value.as_str();
+    while let Some(start) = rest.find('$') {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let (name, written_len) = match after.strip_prefix('{') {
+            Some(braced) => match braced.find('}') {
+                Some(end) => (&braced[..end], end + 2),
+                None => ("", 0),
+            },
+            None => {
+                let end = after
+                    .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                    .unwrap_or(after.len());
+                (&after[..end], end)
+            }
+        };
+
+        let written = &rest[start..start + 1 + written_len];
+        match std::env::var(name) {
+            Ok(variable) if !name.is_empty() => expanded.push_str(&variable),
+            _ => expanded.push_str(written),
+        }
+        rest = &rest[start + 1 + written_len..];
+    }
+    expanded.push_str(rest);
+
+    expanded
+}
+
+/// deserializes the config file, keeping track of the path to the value being deserialized so
+/// errors can point at the exact field and line that is wrong
+pub fn parse_config(source: &str) -> Result<UnresolvedConfig, ConfigError> {
+    serde_path_to_error::deserialize(toml::Deserializer::new(source))
+        .map_err(|e| into_parse_error(e, Some(source)))
+}
+
+/// deserializes a config that was merged from multiple files, which no longer has a location
+/// to point at, only the field
+pub fn config_from_table(table: toml::Table) -> Result<UnresolvedConfig, ConfigError> {
+    serde_path_to_error::deserialize(toml::Value::Table(table))
+        .map_err(|e| into_parse_error(e, None))
+}
+
+/// parses a file as a plain table, so that it can be merged with the files it includes
+pub fn parse_table(source: &str) -> Result<toml::Table, ConfigError> {
+    serde_path_to_error::deserialize(toml::Deserializer::new(source))
+        .map_err(|e| into_parse_error(e, Some(source)))
+}
+
+impl Config {
+    /// changes a single value in memory, like `border_width` to `4`, without touching the
+    /// config file. The value is written like it would be on the file, bare words are taken as
+    /// strings, and goes through the same validation. Only plain values can be set, the ones
+    /// that build state once, like the workspaces or the key bindings, need a reload
+    pub fn set(&mut self, key: &str, value: &str) -> anyhow::Result<()> {
+        let apply: fn(&mut Config, Config) = match key {
+            "border_width" => |config, other| config.border_width = other.border_width,
+            "border_color" => |config, other| config.border_color = other.border_color,
+            "active_border_color" => {
+                |config, other| config.active_border_color = other.active_border_color
+            }
+            "urgent_border_color" => {
+                |config, other| config.urgent_border_color = other.urgent_border_color
+            }
+            "focus_new_clients" => {
+                |config, other| config.focus_new_clients = other.focus_new_clients
+            }
+            "new_client_monitor_focus" => {
+                |config, other| config.new_client_monitor_focus = other.new_client_monitor_focus
+            }
+            "workspace_switch_focus" => {
+                |config, other| config.workspace_switch_focus = other.workspace_switch_focus
+            }
+            "workspace_scope" => |config, other| config.workspace_scope = other.workspace_scope,
+            "dynamic_workspaces" => {
+                |config, other| config.dynamic_workspaces = other.dynamic_workspaces
+            }
+            "focus_follow_mouse" => {
+                |config, other| config.focus_follow_mouse = other.focus_follow_mouse
+            }
+            "inner_gap" => |config, other| config.inner_gap = other.inner_gap,
+            "outer_gap" => |config, other| config.outer_gap = other.outer_gap,
+            "outer_gap_on_reserved_edges" => |config, other| {
+                config.outer_gap_on_reserved_edges = other.outer_gap_on_reserved_edges
+            },
+            "master_ratio" => |config, other| config.master_ratio = other.master_ratio,
+            "layout_orientation" => {
+                |config, other| config.layout_orientation = other.layout_orientation
+            }
+            "smart_borders" => |config, other| config.smart_borders = other.smart_borders,
+            "smart_gaps" => |config, other| config.smart_gaps = other.smart_gaps,
+            "follow" => |config, other| config.follow = other.follow,
+            "swallowing" => |config, other| config.swallowing = other.swallowing,
+            "float_placement" => |config, other| config.float_placement = other.float_placement,
+            "border_radius" => |config, other| config.border_radius = other.border_radius,
+            "warp_cursor" => |config, other| config.warp_cursor = other.warp_cursor,
+            "gapless_monocle" => |config, other| config.gapless_monocle = other.gapless_monocle,
+            "tall_respect_struts" => {
+                |config, other| config.tall_respect_struts = other.tall_respect_struts
+            }
+            "monocle_respect_struts" => {
+                |config, other| config.monocle_respect_struts = other.monocle_respect_struts
+            }
+            "animations" => |config, other| config.animations = other.animations,
+            "fake_shadow" => |config, other| config.fake_shadow = other.fake_shadow,
+            "shadow_color" => |config, other| config.shadow_color = other.shadow_color,
+            "shadow_offset" => |config, other| config.shadow_offset = other.shadow_offset,
+            "dock_auto_reserve" => {
+                |config, other| config.dock_auto_reserve = other.dock_auto_reserve
+            }
+            "single_window_border" => {
+                |config, other| config.single_window_border = other.single_window_border
+            }
+            "resize_step" => |config, other| config.resize_step = other.resize_step,
+            _ => anyhow::bail!("{key} is not a value that can be set at runtime"),
+        };
+
+        let value = parse_table(&format!("value = {value}"))
+            .ok()
+            .and_then(|mut table| table.remove("value"))
+            .unwrap_or_else(|| toml::Value::String(value.to_string()));
+
+        // the fields every config needs are filled with the current ones, only `key` matters
+        let mut table = toml::Table::new();
+        table.insert("workspaces".into(), self.workspaces.into());
+        table.insert("actions".into(), toml::Value::Array(vec![]));
+        table.insert("commands".into(), toml::Value::Array(vec![]));
+        table.insert(
+            "border_color".into(),
+            format!("#{:06X}", self.border_color).into(),
+        );
+        table.insert(
+            "active_border_color".into(),
+            format!("#{:06X}", self.active_border_color).into(),
+        );
+        table.insert(key.into(), value);
+        let other = config_from_table(table).and_then(Config::try_from)?;
+
+        apply(self, other);
+
+        Ok(())
+    }
+}
+
+/// removes the include list from the table, returning the files to be included
+pub fn take_includes(table: &mut toml::Table) -> Result<Vec<String>, ConfigError> {
+    match table.remove(INCLUDE_KEY) {
+        Some(includes) => includes
+            .try_into::<Vec<String>>()
+            .map_err(|e| ConfigError::Parse {
+                field: INCLUDE_KEY.into(),
+                location: None,
+                message: e.message().to_string(),
+            }),
+        None => Ok(vec![]),
+    }
+}
+
+/// merges `other` into `base`, nested tables are merged recursively, any other value in `other`
+/// replaces the one in `base`
+pub fn merge_tables(base: &mut toml::Table, other: toml::Table) {
+    for (key, value) in other {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(other)) => {
+                merge_tables(base, other)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+fn into_parse_error(
+    e: serde_path_to_error::Error<toml::de::Error>,
+    source: Option<&str>,
+) -> ConfigError {
+    let field = match e.path().iter().next() {
+        Some(_) => e.path().to_string(),
+        None => String::default(),
+    };
+    let error = e.into_inner();
+
+    ConfigError::Parse {
+        field,
+        location: source
+            .zip(error.span())
+            .map(|(source, span)| line_and_column(source, span.start)),
+        message: error.message().to_string(),
+    }
+}
+
+/// translates a byte offset into a 1-based line and column
+fn line_and_column(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset.min(source.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before
+        .rsplit_once('\n')
+        .map_or(before, |(_, line)| line)
+        .chars()
+        .count()
+        + 1;
+    (line, column)
 }
 
 impl From<AvailableLeaderKeys> for UnresolvedModifier {
     fn from(value: AvailableLeaderKeys) -> Self {
         match value {
             AvailableLeaderKeys::Mod1 => UnresolvedModifier::Mod1,
+            AvailableLeaderKeys::Mod4 => UnresolvedModifier::Mod4,
             AvailableLeaderKeys::Shift => UnresolvedModifier::Shift,
             AvailableLeaderKeys::Control => UnresolvedModifier::Control,
         }
@@ -111,18 +630,18 @@ impl TryFrom<UnresolvedConfig> for Config {
         let mut value = value;
 
         let leader = match value.leader {
-            UnresolvedLeader::Shift => AvailableLeaderKeys::Shift,
-            UnresolvedLeader::Mod1 => AvailableLeaderKeys::Mod1,
-            UnresolvedLeader::Control => AvailableLeaderKeys::Control,
+            Some(UnresolvedLeader::Shift) => AvailableLeaderKeys::Shift,
+            Some(UnresolvedLeader::Mod1) => AvailableLeaderKeys::Mod1,
+            Some(UnresolvedLeader::Mod4) => AvailableLeaderKeys::Mod4,
+            Some(UnresolvedLeader::Control) => AvailableLeaderKeys::Control,
+            None => AvailableLeaderKeys::default(),
         };
 
-        value.actions.iter_mut().for_each(|action| {
-            action.modifiers.iter_mut().for_each(|modifier| {
-                if let UnresolvedModifier::Leader = modifier {
-                    *modifier = leader.clone().into();
-                }
-            })
-        });
+        for (idx, command) in value.commands.iter_mut().enumerate() {
+            command.key = split_chord(&command.key, &mut command.modifiers)
+                .map_err(|e| e.in_field(format!("commands[{idx}].key")))?;
+        }
+
         value.commands.iter_mut().for_each(|command| {
             command.modifiers.iter_mut().for_each(|modifier| {
                 if let UnresolvedModifier::Leader = modifier {
@@ -131,38 +650,190 @@ impl TryFrom<UnresolvedConfig> for Config {
             })
         });
 
-        let mut actions: Vec<Action> = vec![];
-        for action in value.actions.into_iter() {
-            actions.push(action.try_into()?);
+        let actions = resolve_actions(value.actions, &leader, "actions")?;
+
+        let mut modes = HashMap::new();
+        for (name, mode) in value.modes.unwrap_or_default() {
+            let actions = resolve_actions(mode.actions, &leader, &format!("modes.{name}.actions"))?;
+            modes.insert(name, actions);
+        }
+
+        // a mode that does not exist would leave every key ungrabbed with no way back
+        for (field, actions) in std::iter::once(("actions".to_string(), &actions)).chain(
+            modes
+                .iter()
+                .map(|(name, actions)| (format!("modes.{name}.actions"), actions)),
+        ) {
+            for (idx, action) in actions.iter().enumerate() {
+                if let AvailableActions::EnterMode(mode) = &action.action {
+                    if !modes.contains_key(mode) {
+                        return Err(ConfigError::Mode {
+                            field: format!("{field}[{idx}].action"),
+                            message: format!("mode {mode} is not defined under [modes]"),
+                        });
+                    }
+                }
+            }
         }
 
         let mut commands: Vec<Command> = vec![];
-        for command in value.commands.into_iter() {
-            commands.push(command.try_into()?);
+        for (idx, command) in value.commands.into_iter().enumerate() {
+            commands.push(
+                command
+                    .try_into()
+                    .map_err(|e: ConfigError| e.in_field(format!("commands[{idx}].key")))?,
+            );
         }
 
         let mut startup_commands: Vec<AutoCommand> = vec![];
-        for auto_command in value.startup_commands.unwrap_or_default().into_iter() {
-            startup_commands.push(auto_command.try_into()?);
+        for (idx, auto_command) in value
+            .startup_commands
+            .unwrap_or_default()
+            .into_iter()
+            .enumerate()
+        {
+            startup_commands.push(
+                auto_command
+                    .try_into()
+                    .map_err(|e: ConfigError| e.in_field(format!("startup_commands[{idx}]")))?,
+            );
+        }
+
+        let on_ready = value
+            .on_ready
+            .map(AutoCommand::try_from)
+            .transpose()
+            .map_err(|e| e.in_field("on_ready"))?;
+
+        if value.workspaces.eq(&0) {
+            return Err(ConfigError::Workspaces {
+                field: "workspaces".into(),
+                message: "0 is not valid, number of workspaces must be greater than 0".into(),
+            });
         }
 
-        if value.workspaces.gt(&9) || value.workspaces.eq(&0) {
-            return Err(ConfigError::Workspaces(format!(
-                "workspaces = {}: number of workspaces must be greater than 0, and up to 9",
+        let workspaces = if value.workspaces.gt(&MAX_WORKSPACES) {
+            tracing::warn!(
+                "{} workspaces were configured, only {MAX_WORKSPACES} are created",
                 value.workspaces
-            )));
+            );
+            MAX_WORKSPACES
+        } else {
+            value.workspaces
+        };
+        // rules and settings may target workspaces that dynamic workspaces create later on
+        let workspace_limit = match value.dynamic_workspaces.unwrap_or(false) {
+            true => MAX_WORKSPACES,
+            false => workspaces,
+        };
+
+        let mut rules: Vec<Rule> = vec![];
+        for (idx, rule) in value.rules.unwrap_or_default().into_iter().enumerate() {
+            if rule
+                .workspace
+                .is_some_and(|workspace| workspace.eq(&0) || workspace.gt(&workspace_limit))
+            {
+                return Err(ConfigError::Workspaces {
+                    field: format!("rules[{idx}].workspace"),
+                    message: format!(
+                        "{} is not valid, there are only {} workspaces",
+                        rule.workspace.unwrap_or_default(),
+                        workspace_limit
+                    ),
+                });
+            }
+
+            if rule.monitor.is_some_and(|monitor| monitor.eq(&0)) {
+                return Err(ConfigError::Parse {
+                    field: format!("rules[{idx}].monitor"),
+                    location: None,
+                    message: String::from("monitors are numbered starting from 1"),
+                });
+            }
+
+            rules.push(Rule {
+                class: rule.class,
+                workspace: rule.workspace.map(|workspace| workspace - 1),
+                floating: rule.floating,
+                focus: rule.focus,
+                monitor: rule.monitor.map(|monitor| monitor - 1),
+            });
+        }
+
+        let mut workspace_settings = vec![];
+        let settings = value.workspace_settings.unwrap_or_default();
+        for (idx, settings) in settings.into_iter().enumerate() {
+            if settings.index.eq(&0) || settings.index.gt(&workspace_limit) {
+                return Err(ConfigError::Workspaces {
+                    field: format!("workspace[{idx}].index"),
+                    message: format!(
+                        "{} is not valid, there are only {} workspaces",
+                        settings.index, workspace_limit
+                    ),
+                });
+            }
+
+            workspace_settings.push(WorkspaceSettings {
+                index: settings.index - 1,
+                inner_gap: settings.inner_gap,
+                outer_gap: settings.outer_gap,
+                gapless_monocle: settings.gapless_monocle,
+                respect_struts: settings.respect_struts,
+            });
         }
 
         let border_color = Color::try_from(value.border_color.unwrap_or_default())
-            .map_err(|e| ConfigError::BorderColor(e.to_string()))?
+            .map_err(|e| ConfigError::BorderColor {
+                field: "border_color".into(),
+                message: e.to_string(),
+            })?
             .0;
 
         let active_border_color = Color::try_from(value.active_border_color.unwrap_or_default())
-            .map_err(|e| ConfigError::BorderColor(e.to_string()))?
+            .map_err(|e| ConfigError::BorderColor {
+                field: "active_border_color".into(),
+                message: e.to_string(),
+            })?
             .0;
 
+        let urgent_border_color = value
+            .urgent_border_color
+            .map(|color| {
+                Color::try_from(color).map_err(|e| ConfigError::BorderColor {
+                    field: "urgent_border_color".into(),
+                    message: e.to_string(),
+                })
+            })
+            .transpose()?
+            .map_or(0xB33A3A, |color| color.0);
+
+        let shadow_color = value
+            .shadow_color
+            .map(|color| {
+                Color::try_from(color).map_err(|e| ConfigError::Color {
+                    field: "shadow_color".into(),
+                    message: e.to_string(),
+                })
+            })
+            .transpose()?
+            .map_or(0x000000, |color| color.0);
+
+        let log = value.log.unwrap_or_default();
+
+        // clamping keeps the ratio in range, but `nan` and `inf` are valid toml floats that
+        // would otherwise reach the layout
+        let master_ratio = match value.master_ratio {
+            Some(ratio) if !ratio.is_finite() => {
+                return Err(ConfigError::MasterRatio {
+                    field: "master_ratio".into(),
+                    message: format!("{ratio} is not a number between 0.1 and 0.9"),
+                })
+            }
+            ratio => ratio.unwrap_or(0.5).clamp(0.1, 0.9),
+        };
+
         Ok(Config {
-            workspaces: value.workspaces,
+            workspaces,
             border_width: value.border_width.unwrap_or(1),
             border_color,
             active_border_color,
@@ -171,24 +842,94 @@ impl TryFrom<UnresolvedConfig> for Config {
             actions,
             leader,
             commands,
+            modes,
             startup_commands,
+            inner_gap: value.inner_gap.unwrap_or(0),
+            outer_gap: value.outer_gap.unwrap_or(0),
+            outer_gap_on_reserved_edges: value.outer_gap_on_reserved_edges.unwrap_or(true),
+            master_ratio,
+            layout_orientation: value.layout_orientation.map(Into::into).unwrap_or_default(),
+            workspace_names: value.workspace_names.unwrap_or_default(),
+            scratchpad_class: value.scratchpad_class,
+            rules,
+            smart_borders: value.smart_borders.unwrap_or(false),
+            smart_gaps: value.smart_gaps.unwrap_or(false),
+            follow: value.follow.unwrap_or(false),
+            urgent_border_color,
+            swallowing: value.swallowing.unwrap_or(false),
+            terminal_classes: value.terminal_classes.unwrap_or_default(),
+            float_placement: value.float_placement.map(Into::into).unwrap_or_default(),
+            border_radius: value.border_radius.unwrap_or(0),
+            workspace_settings,
+            log_level: log.level.map(Into::into).unwrap_or(tracing::Level::INFO),
+            log_file: log.file.as_deref().map(expand_path),
+            cursor: value.cursor.unwrap_or_else(|| String::from("left_ptr")),
+            warp_cursor: value.warp_cursor.unwrap_or(false),
+            gapless_monocle: value.gapless_monocle.unwrap_or(false),
+            monocle_respect_struts: value.monocle_respect_struts.unwrap_or(true),
+            animations: value.animations.unwrap_or(false),
+            no_focus_classes: value.no_focus_classes.unwrap_or_default(),
+            single_window_border: value.single_window_border.unwrap_or(true),
+            resize_step: value.resize_step.unwrap_or(0.05).clamp(0.01, 0.5),
+            workspace_scope: value.workspace_scope.map(Into::into).unwrap_or_default(),
+            new_client_monitor_focus: value
+                .new_client_monitor_focus
+                .map(Into::into)
+                .unwrap_or_default(),
+            dock_auto_reserve: value.dock_auto_reserve.unwrap_or_default(),
+            workspace_switch_focus: value
+                .workspace_switch_focus
+                .map(Into::into)
+                .unwrap_or_default(),
+            fake_shadow: value.fake_shadow.unwrap_or_default(),
+            shadow_color,
+            shadow_offset: value.shadow_offset.unwrap_or(4),
+            tall_respect_struts: value.tall_respect_struts.unwrap_or(true),
+            keybinds_viewer: value.keybinds_viewer,
+            on_ready,
+            dynamic_workspaces: value.dynamic_workspaces.unwrap_or(false),
         })
     }
 }
 
+/// splits the chords of the given actions and replaces their `Leader` modifier, errors point at
+/// the offending entry of `field`
+fn resolve_actions(
+    entries: Vec<UnresolvedActionEntry>,
+    leader: &AvailableLeaderKeys,
+    field: &str,
+) -> Result<Vec<Action>, ConfigError> {
+    let mut actions: Vec<Action> = vec![];
+    for (idx, mut action) in entries.into_iter().enumerate() {
+        action.key = split_chord(&action.key, &mut action.modifiers)
+            .map_err(|e| e.in_field(format!("{field}[{idx}].key")))?;
+        action.modifiers.iter_mut().for_each(|modifier| {
+            if let UnresolvedModifier::Leader = modifier {
+                *modifier = leader.clone().into();
+            }
+        });
+        actions.push(
+            action
+                .try_into()
+                .map_err(|e: ConfigError| e.in_field(format!("{field}[{idx}].key")))?,
+        );
+    }
+
+    Ok(actions)
+}
+
 impl TryFrom<UnresolvedActionEntry> for Action {
     type Error = ConfigError;
 
     fn try_from(value: UnresolvedActionEntry) -> Result<Self, Self::Error> {
         Ok(Action {
             action: value.action.into(),
+            on: value.on.map(Into::into).unwrap_or_default(),
             key: value.key.as_str().try_into()?,
-            modifier: ActionModifier::new(
-                value
-                    .modifiers
-                    .into_iter()
-                    .fold(0, |acc, modifier| acc.add(u32::from(modifier))),
-            ),
+            modifier: value
+                .modifiers
+                .into_iter()
+                .fold(Modifiers::default(), |acc, modifier| acc | modifier.into()),
         })
     }
 }
@@ -203,14 +944,16 @@ impl TryFrom<UnresolvedCommandEntry> for Command {
             .map(|(left, right)| (left.to_string(), right.into()))
             .unwrap_or((value.command, Args(vec![])));
 
+        // commands are not run through a shell, so `~` and variables are expanded here
         Ok(Command {
-            command: command.to_string(),
+            command: expand(&command),
             key: value.key.as_str().try_into()?,
             modifier: value
                 .modifiers
                 .into_iter()
-                .fold(0, |acc, modifier| acc + u32::from(modifier)),
-            args: args.0,
+                .fold(Modifiers::default(), |acc, modifier| acc | modifier.into()),
+            args: args.0.iter().map(|arg| expand(arg)).collect(),
+            on: value.on.map(Into::into).unwrap_or_default(),
         })
     }
 }
@@ -220,9 +963,10 @@ impl TryFrom<String> for AutoCommand {
 
     fn try_from(value: String) -> Result<Self, Self::Error> {
         if value.is_empty() {
-            return Err(ConfigError::InvalidCommand(
-                "statup_command cannot be empty".to_string(),
-            ));
+            return Err(ConfigError::InvalidCommand {
+                field: "startup_commands".into(),
+                message: "command cannot be empty".into(),
+            });
         }
 
         let (command, args): (String, Args) = value
@@ -233,8 +977,8 @@ impl TryFrom<String> for AutoCommand {
             .unwrap_or((value, Args(vec![])));
 
         Ok(AutoCommand {
-            command,
-            args: args.0,
+            command: expand(&command),
+            args: args.0.iter().map(|arg| expand(arg)).collect(),
         })
     }
 }
@@ -250,10 +994,15 @@ impl From<UnresolvedAction> for AvailableActions {
             UnresolvedAction::MoveDown => AvailableActions::MoveDown,
             UnresolvedAction::MoveUp => AvailableActions::MoveUp,
             UnresolvedAction::MoveRight => AvailableActions::MoveRight,
+            UnresolvedAction::ResizeLeft => AvailableActions::ResizeLeft,
+            UnresolvedAction::ResizeDown => AvailableActions::ResizeDown,
+            UnresolvedAction::ResizeUp => AvailableActions::ResizeUp,
+            UnresolvedAction::ResizeRight => AvailableActions::ResizeRight,
             UnresolvedAction::Quit => AvailableActions::Quit,
             UnresolvedAction::Close => AvailableActions::Close,
             UnresolvedAction::Reload => AvailableActions::Reload,
-            UnresolvedAction::Fullscreen => AvailableActions::Fullscreen,
+            UnresolvedAction::Restart => AvailableActions::Restart,
+            UnresolvedAction::ToggleFullscreen => AvailableActions::ToggleFullscreen,
             UnresolvedAction::Workspace1 => AvailableActions::Workspace1,
             UnresolvedAction::Workspace2 => AvailableActions::Workspace2,
             UnresolvedAction::Workspace3 => AvailableActions::Workspace3,
@@ -272,21 +1021,190 @@ impl From<UnresolvedAction> for AvailableActions {
             UnresolvedAction::MoveToWorkspace7 => AvailableActions::MoveToWorkspace7,
             UnresolvedAction::MoveToWorkspace8 => AvailableActions::MoveToWorkspace8,
             UnresolvedAction::MoveToWorkspace9 => AvailableActions::MoveToWorkspace9,
+            UnresolvedAction::ToggleBorder => AvailableActions::ToggleBorder,
+            UnresolvedAction::CarouselWindow => AvailableActions::CarouselWindow,
+            UnresolvedAction::ToggleFloating => AvailableActions::ToggleFloating,
+            UnresolvedAction::FocusBusiest => AvailableActions::FocusBusiest,
+            UnresolvedAction::IncreaseMaster => AvailableActions::IncreaseMaster,
+            UnresolvedAction::DecreaseMaster => AvailableActions::DecreaseMaster,
+            UnresolvedAction::IncreaseMasterCount => AvailableActions::IncreaseMasterCount,
+            UnresolvedAction::DecreaseMasterCount => AvailableActions::DecreaseMasterCount,
+            UnresolvedAction::DistributeEven => AvailableActions::DistributeEven,
+            UnresolvedAction::CycleLayout => AvailableActions::CycleLayout,
+            UnresolvedAction::SetLayout(layout) => AvailableActions::SetLayout(layout.into()),
+            UnresolvedAction::ToggleScratchpad => AvailableActions::ToggleScratchpad,
+            UnresolvedAction::SwapWithMaster => AvailableActions::SwapWithMaster,
+            UnresolvedAction::FocusMaster => AvailableActions::FocusMaster,
+            UnresolvedAction::MoveWorkspaceToMonitor(direction) => {
+                AvailableActions::MoveWorkspaceToMonitor(direction.into())
+            }
+            UnresolvedAction::CycleFocusNext => AvailableActions::CycleFocusNext,
+            UnresolvedAction::CycleFocusPrev => AvailableActions::CycleFocusPrev,
+            UnresolvedAction::FocusLastWindow => AvailableActions::FocusLastWindow,
+            UnresolvedAction::ShowKeybinds => AvailableActions::ShowKeybinds,
+            UnresolvedAction::ToggleSticky => AvailableActions::ToggleSticky,
+            UnresolvedAction::ToggleStackDirection => AvailableActions::ToggleStackDirection,
+            UnresolvedAction::FocusMonitor(direction) => {
+                AvailableActions::FocusMonitor(direction.into())
+            }
+            UnresolvedAction::EnterMode(mode) => AvailableActions::EnterMode(mode),
+            UnresolvedAction::ExitMode => AvailableActions::ExitMode,
+        }
+    }
+}
+
+impl From<UnresolvedLayout> for AvailableLayouts {
+    fn from(value: UnresolvedLayout) -> Self {
+        match value {
+            UnresolvedLayout::Tall => AvailableLayouts::Tall,
+            UnresolvedLayout::Monocle => AvailableLayouts::Monocle,
+        }
+    }
+}
+
+impl From<UnresolvedDirection> for AvailableDirections {
+    fn from(value: UnresolvedDirection) -> Self {
+        match value {
+            UnresolvedDirection::Left => AvailableDirections::Left,
+            UnresolvedDirection::Down => AvailableDirections::Down,
+            UnresolvedDirection::Up => AvailableDirections::Up,
+            UnresolvedDirection::Right => AvailableDirections::Right,
+        }
+    }
+}
+
+impl From<UnresolvedLayoutOrientation> for LayoutOrientation {
+    fn from(value: UnresolvedLayoutOrientation) -> Self {
+        match value {
+            UnresolvedLayoutOrientation::Left => LayoutOrientation::Left,
+            UnresolvedLayoutOrientation::Right => LayoutOrientation::Right,
+            UnresolvedLayoutOrientation::Top => LayoutOrientation::Top,
+            UnresolvedLayoutOrientation::Bottom => LayoutOrientation::Bottom,
+        }
+    }
+}
+
+impl From<UnresolvedLogLevel> for tracing::Level {
+    fn from(value: UnresolvedLogLevel) -> Self {
+        match value {
+            UnresolvedLogLevel::Trace => tracing::Level::TRACE,
+            UnresolvedLogLevel::Debug => tracing::Level::DEBUG,
+            UnresolvedLogLevel::Info => tracing::Level::INFO,
+            UnresolvedLogLevel::Warn => tracing::Level::WARN,
+            UnresolvedLogLevel::Error => tracing::Level::ERROR,
+        }
+    }
+}
+
+impl From<UnresolvedNewClientMonitorFocus> for NewClientMonitorFocus {
+    fn from(value: UnresolvedNewClientMonitorFocus) -> Self {
+        match value {
+            UnresolvedNewClientMonitorFocus::Switch => NewClientMonitorFocus::Switch,
+            UnresolvedNewClientMonitorFocus::Focus => NewClientMonitorFocus::Focus,
+            UnresolvedNewClientMonitorFocus::Keep => NewClientMonitorFocus::Keep,
+        }
+    }
+}
+
+impl From<UnresolvedKeyTrigger> for KeyTrigger {
+    fn from(value: UnresolvedKeyTrigger) -> Self {
+        match value {
+            UnresolvedKeyTrigger::Press => KeyTrigger::Press,
+            UnresolvedKeyTrigger::Release => KeyTrigger::Release,
+        }
+    }
+}
+
+impl From<UnresolvedWorkspaceSwitchFocus> for WorkspaceSwitchFocus {
+    fn from(value: UnresolvedWorkspaceSwitchFocus) -> Self {
+        match value {
+            UnresolvedWorkspaceSwitchFocus::Last => WorkspaceSwitchFocus::Last,
+            UnresolvedWorkspaceSwitchFocus::First => WorkspaceSwitchFocus::First,
+            UnresolvedWorkspaceSwitchFocus::Cursor => WorkspaceSwitchFocus::Cursor,
+        }
+    }
+}
+
+impl From<UnresolvedFloatPlacement> for FloatPlacement {
+    fn from(value: UnresolvedFloatPlacement) -> Self {
+        match value {
+            UnresolvedFloatPlacement::Center => FloatPlacement::Center,
+            UnresolvedFloatPlacement::Cursor => FloatPlacement::Cursor,
+            UnresolvedFloatPlacement::Smart => FloatPlacement::Smart,
         }
     }
 }
 
-impl From<UnresolvedModifier> for u32 {
-    fn from(value: UnresolvedModifier) -> u32 {
+impl From<UnresolvedWorkspaceScope> for WorkspaceScope {
+    fn from(value: UnresolvedWorkspaceScope) -> Self {
         match value {
-            UnresolvedModifier::Shift => 0x00000001,
-            UnresolvedModifier::Control => 0x00000004,
-            UnresolvedModifier::Mod1 => 0x00000008,
-            _ => 0x00000000,
+            UnresolvedWorkspaceScope::Global => WorkspaceScope::Global,
+            UnresolvedWorkspaceScope::PerMonitor => WorkspaceScope::PerMonitor,
         }
     }
 }
 
+impl From<UnresolvedModifier> for Modifiers {
+    fn from(value: UnresolvedModifier) -> Modifiers {
+        match value {
+            UnresolvedModifier::Shift => Modifiers::SHIFT,
+            UnresolvedModifier::Control => Modifiers::CONTROL,
+            UnresolvedModifier::Mod1 => Modifiers::MOD1,
+            UnresolvedModifier::Mod4 => Modifiers::MOD4,
+            _ => Modifiers::default(),
+        }
+    }
+}
+
+impl TryFrom<&str> for UnresolvedModifier {
+    type Error = ConfigError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "Leader" | "Mod" | "mod" => Ok(UnresolvedModifier::Leader),
+            "Control" | "Ctrl" => Ok(UnresolvedModifier::Control),
+            "Shift" => Ok(UnresolvedModifier::Shift),
+            "Mod1" | "Alt" => Ok(UnresolvedModifier::Mod1),
+            "Mod4" | "Super" => Ok(UnresolvedModifier::Mod4),
+            _ => Err(ConfigError::Key {
+                field: "modifiers".into(),
+                message: format!("{value} is not a valid modifier"),
+            }),
+        }
+    }
+}
+
+/// keys can be written as chords, like `Super+Shift+q` or `mod+j`, every part but the last one
+/// is a modifier that gets added to the ones on the `modifiers` list, the last one is the key.
+/// `mod` refers to whatever modifier was configured as the leader
+///
+/// bindings made only of modifiers, like `Super` or `Super+Shift+`, are rejected, as there is
+/// no key to grab
+fn split_chord(key: &str, modifiers: &mut Vec<UnresolvedModifier>) -> Result<String, ConfigError> {
+    let binding = key;
+    let key = match key.rsplit_once('+') {
+        Some((chord, key)) => {
+            for modifier in chord.split('+') {
+                modifiers.push(modifier.trim().try_into()?);
+            }
+            key.trim()
+        }
+        None => key.trim(),
+    };
+
+    if key.is_empty() || UnresolvedModifier::try_from(key).is_ok() {
+        return Err(ConfigError::Key {
+            field: "key".into(),
+            message: format!(
+                "binding `{binding}` only has modifiers, it needs a key as well, like `{key}+q`",
+                key = binding.trim_end_matches('+')
+            ),
+        });
+    }
+
+    Ok(key.to_string())
+}
+
 pub struct Args(Vec<String>);
 
 impl From<&str> for Args {
@@ -299,3 +1217,183 @@ impl From<&str> for Args {
         Self(args)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chords_are_split_into_modifiers_and_key() {
+        let mut modifiers = vec![UnresolvedModifier::Leader];
+        let key = split_chord("Super+Shift+q", &mut modifiers).ok().unwrap();
+        let mask = modifiers
+            .into_iter()
+            .fold(Modifiers::default(), |acc, modifier| acc | modifier.into());
+
+        assert_eq!(key, "q");
+        assert_eq!(mask, Modifiers::MOD4 | Modifiers::SHIFT);
+        assert!(split_chord("Hyper+q", &mut vec![]).is_err());
+
+        let mut modifiers = vec![];
+        let key = split_chord("mod+j", &mut modifiers).ok().unwrap();
+        assert_eq!(key, "j");
+        assert!(matches!(modifiers[..], [UnresolvedModifier::Leader]));
+    }
+
+    #[test]
+    fn parse_errors_point_at_the_offending_field() {
+        let source = "workspaces = 9\nactions = [\n  { key = \"j\", action = \"FocusNowhere\" },\n]\ncommands = []\n";
+        let error = parse_config(source).err().unwrap();
+
+        assert_eq!(error.field(), "actions[0].action");
+        assert!(matches!(
+            error,
+            ConfigError::Parse {
+                location: Some((3, _)),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn home_and_variables_are_expanded() {
+        let home = std::env::var("HOME").unwrap();
+
+        assert_eq!(expand("~"), home);
+        assert_eq!(expand("~/.fehbg"), format!("{home}/.fehbg"));
+        assert_eq!(expand("$HOME/.fehbg"), format!("{home}/.fehbg"));
+        assert_eq!(expand("${HOME}.d"), format!("{home}.d"));
+        // only a leading `~` stands for the home directory
+        assert_eq!(expand("a~b"), "a~b");
+        assert_eq!(expand("~user/x"), "~user/x");
+        // unset variables are kept as written, so the failure names what was missing
+        assert_eq!(
+            expand("$LUCKY_UNSET_VARIABLE/x ${LUCKY_UNSET_VARIABLE}"),
+            "$LUCKY_UNSET_VARIABLE/x ${LUCKY_UNSET_VARIABLE}"
+        );
+        assert_eq!(expand("cost: 5$"), "cost: 5$");
+
+        let command = AutoCommand::try_from(String::from("~/bin/bar --config $HOME/bar")).unwrap();
+        assert_eq!(command.command, format!("{home}/bin/bar"));
+        assert_eq!(
+            command.args,
+            vec!["--config".to_string(), format!("{home}/bar")]
+        );
+    }
+
+    #[test]
+    fn included_tables_are_merged_in_order() {
+        let mut base = parse_table("workspaces = 9\ngaps = { inner = 1, outer = 2 }\n").unwrap();
+        let other = parse_table("workspaces = 3\ngaps = { outer = 4 }\n").unwrap();
+        merge_tables(&mut base, other);
+
+        assert_eq!(base["workspaces"].as_integer(), Some(3));
+        assert_eq!(base["gaps"]["inner"].as_integer(), Some(1));
+        assert_eq!(base["gaps"]["outer"].as_integer(), Some(4));
+    }
+
+    #[test]
+    fn workspace_count_is_validated_and_clamped() {
+        let resolve = |workspaces: u8| {
+            let source = format!(
+                "workspaces = {workspaces}\nborder_color = \"#000000\"\n\
+                 active_border_color = \"#ffffff\"\nactions = []\ncommands = []\n"
+            );
+            Config::try_from(parse_config(&source).ok().unwrap())
+        };
+
+        assert!(matches!(resolve(0), Err(ConfigError::Workspaces { .. })));
+        assert_eq!(resolve(12).ok().unwrap().workspaces(), 12);
+        assert_eq!(resolve(50).ok().unwrap().workspaces(), MAX_WORKSPACES);
+    }
+
+    #[test]
+    fn values_are_set_at_runtime() {
+        let mut config = Config::default();
+
+        config.set("border_width", "8").unwrap();
+        config.set("border_color", "#FF0000").unwrap();
+        config.set("layout_orientation", "top").unwrap();
+        assert_eq!(config.border_width(), 8);
+        assert_eq!(config.border_color(), 0xFF0000);
+        assert_eq!(config.layout_orientation(), LayoutOrientation::Top);
+
+        assert!(config.set("border_width", "wide").is_err());
+        assert!(config.set("workspaces", "3").is_err());
+        assert_eq!(config.border_width(), 8);
+
+        for ratio in ["nan", "inf", "-inf"] {
+            assert!(config.set("master_ratio", ratio).is_err(), "{ratio}");
+        }
+        assert_eq!(config.master_ratio(), 0.5);
+    }
+
+    /// a config with just the required keys and the given bindings, written as the contents
+    /// of the `actions` and `commands` arrays
+    fn bindings_source(actions: &str, commands: &str) -> String {
+        format!(
+            "workspaces = 9\nborder_color = \"#252525\"\nactive_border_color = \"#2D4F67\"\n\
+             actions = [{actions}]\ncommands = [{commands}]\n"
+        )
+    }
+
+    #[test]
+    fn modes_must_be_defined_to_be_entered() {
+        let source = bindings_source(
+            r#"{ key = "Leader+r", action = { EnterMode = "resize" } }"#,
+            "",
+        );
+        let error = parse_config(&source)
+            .and_then(Config::try_from)
+            .err()
+            .unwrap();
+        assert_eq!(error.field(), "actions[0].action");
+
+        let source = format!(
+            "{source}[modes.resize]\nactions = [{{ key = \"h\", action = \"ResizeLeft\" }}]\n"
+        );
+        let config = parse_config(&source).and_then(Config::try_from).unwrap();
+        assert_eq!(config.mode_actions("resize").map(<[_]>::len), Some(1));
+        assert!(config.mode_actions("move").is_none());
+    }
+
+    #[test]
+    fn modifier_only_bindings_are_rejected() {
+        let source = bindings_source("", r#"{ key = "Super+Shift", command = "rofi" }"#);
+        let error = parse_config(&source)
+            .and_then(Config::try_from)
+            .err()
+            .unwrap();
+
+        assert_eq!(error.field(), "commands[0].key");
+        assert!(split_chord("Super+", &mut vec![]).is_err());
+        assert!(split_chord("mod", &mut vec![]).is_err());
+    }
+
+    #[test]
+    fn keybinds_are_grouped_by_modifiers() {
+        let source = bindings_source(
+            r#"{ key = "Super+q", action = "Close" }, { key = "Super+Shift+r", action = "Reload" }"#,
+            r#"{ key = "Super+d", command = "rofi -show drun" }"#,
+        );
+        let config = parse_config(&source).and_then(Config::try_from).unwrap();
+
+        assert_eq!(
+            config.keybinds(),
+            "Shift+Super\n  r  Reload\n\nSuper\n  q  Close\n  d  exec rofi -show drun\n"
+        );
+    }
+
+    #[test]
+    fn bindings_fire_on_press_unless_asked_otherwise() {
+        let source = bindings_source(
+            r#"{ key = "Super+q", action = "Close" }, { key = "Super+r", action = "Reload", on = "release" }"#,
+            r#"{ key = "Super+d", command = "rofi", on = "release" }"#,
+        );
+        let config = parse_config(&source).and_then(Config::try_from).unwrap();
+
+        assert_eq!(config.actions()[0].on(), KeyTrigger::Press);
+        assert_eq!(config.actions()[1].on(), KeyTrigger::Release);
+        assert_eq!(config.commands()[0].on(), KeyTrigger::Release);
+    }
+}
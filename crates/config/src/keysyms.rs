@@ -11,6 +11,42 @@ use crate::config_loader::ConfigError;
 pub enum Keysym {
     XK_BackSpace = 0xff08,
     XK_Return = 0xff0d,
+    XK_Tab = 0xff09,
+    XK_Escape = 0xff1b,
+    XK_Home = 0xff50,
+    XK_Left = 0xff51,
+    XK_Up = 0xff52,
+    XK_Right = 0xff53,
+    XK_Down = 0xff54,
+    XK_Prior = 0xff55,
+    XK_Next = 0xff56,
+    XK_End = 0xff57,
+    XK_Insert = 0xff63,
+    XK_Delete = 0xffff,
+    XK_space = 0x020,
+    XK_F1 = 0xffbe,
+    XK_F2 = 0xffbf,
+    XK_F3 = 0xffc0,
+    XK_F4 = 0xffc1,
+    XK_F5 = 0xffc2,
+    XK_F6 = 0xffc3,
+    XK_F7 = 0xffc4,
+    XK_F8 = 0xffc5,
+    XK_F9 = 0xffc6,
+    XK_F10 = 0xffc7,
+    XK_F11 = 0xffc8,
+    XK_F12 = 0xffc9,
+    XK_KP_Enter = 0xff8d,
+    XK_KP_0 = 0xffb0,
+    XK_KP_1 = 0xffb1,
+    XK_KP_2 = 0xffb2,
+    XK_KP_3 = 0xffb3,
+    XK_KP_4 = 0xffb4,
+    XK_KP_5 = 0xffb5,
+    XK_KP_6 = 0xffb6,
+    XK_KP_7 = 0xffb7,
+    XK_KP_8 = 0xffb8,
+    XK_KP_9 = 0xffb9,
     XK_0 = 0x030,
     XK_1 = 0x031,
     XK_2 = 0x032,
@@ -82,6 +118,42 @@ impl Keysym {
         match self {
             Keysym::XK_BackSpace => "XK_BackSpace",
             Keysym::XK_Return => "XK_Return",
+            Keysym::XK_Tab => "XK_Tab",
+            Keysym::XK_Escape => "XK_Escape",
+            Keysym::XK_Home => "XK_Home",
+            Keysym::XK_Left => "XK_Left",
+            Keysym::XK_Up => "XK_Up",
+            Keysym::XK_Right => "XK_Right",
+            Keysym::XK_Down => "XK_Down",
+            Keysym::XK_Prior => "XK_Prior",
+            Keysym::XK_Next => "XK_Next",
+            Keysym::XK_End => "XK_End",
+            Keysym::XK_Insert => "XK_Insert",
+            Keysym::XK_Delete => "XK_Delete",
+            Keysym::XK_space => "XK_space",
+            Keysym::XK_F1 => "XK_F1",
+            Keysym::XK_F2 => "XK_F2",
+            Keysym::XK_F3 => "XK_F3",
+            Keysym::XK_F4 => "XK_F4",
+            Keysym::XK_F5 => "XK_F5",
+            Keysym::XK_F6 => "XK_F6",
+            Keysym::XK_F7 => "XK_F7",
+            Keysym::XK_F8 => "XK_F8",
+            Keysym::XK_F9 => "XK_F9",
+            Keysym::XK_F10 => "XK_F10",
+            Keysym::XK_F11 => "XK_F11",
+            Keysym::XK_F12 => "XK_F12",
+            Keysym::XK_KP_Enter => "XK_KP_Enter",
+            Keysym::XK_KP_0 => "XK_KP_0",
+            Keysym::XK_KP_1 => "XK_KP_1",
+            Keysym::XK_KP_2 => "XK_KP_2",
+            Keysym::XK_KP_3 => "XK_KP_3",
+            Keysym::XK_KP_4 => "XK_KP_4",
+            Keysym::XK_KP_5 => "XK_KP_5",
+            Keysym::XK_KP_6 => "XK_KP_6",
+            Keysym::XK_KP_7 => "XK_KP_7",
+            Keysym::XK_KP_8 => "XK_KP_8",
+            Keysym::XK_KP_9 => "XK_KP_9",
             Keysym::XK_0 => "XK_0",
             Keysym::XK_1 => "XK_1",
             Keysym::XK_2 => "XK_2",
@@ -155,6 +227,42 @@ impl TryFrom<xkbcommon::xkb::Keysym> for Keysym {
         match k {
             x if u32::from(x) == Keysym::XK_BackSpace as u32 => Ok(Keysym::XK_BackSpace),
             x if u32::from(x) == Keysym::XK_Return as u32 => Ok(Keysym::XK_Return),
+            x if u32::from(x) == Keysym::XK_Tab as u32 => Ok(Keysym::XK_Tab),
+            x if u32::from(x) == Keysym::XK_Escape as u32 => Ok(Keysym::XK_Escape),
+            x if u32::from(x) == Keysym::XK_Home as u32 => Ok(Keysym::XK_Home),
+            x if u32::from(x) == Keysym::XK_Left as u32 => Ok(Keysym::XK_Left),
+            x if u32::from(x) == Keysym::XK_Up as u32 => Ok(Keysym::XK_Up),
+            x if u32::from(x) == Keysym::XK_Right as u32 => Ok(Keysym::XK_Right),
+            x if u32::from(x) == Keysym::XK_Down as u32 => Ok(Keysym::XK_Down),
+            x if u32::from(x) == Keysym::XK_Prior as u32 => Ok(Keysym::XK_Prior),
+            x if u32::from(x) == Keysym::XK_Next as u32 => Ok(Keysym::XK_Next),
+            x if u32::from(x) == Keysym::XK_End as u32 => Ok(Keysym::XK_End),
+            x if u32::from(x) == Keysym::XK_Insert as u32 => Ok(Keysym::XK_Insert),
+            x if u32::from(x) == Keysym::XK_Delete as u32 => Ok(Keysym::XK_Delete),
+            x if u32::from(x) == Keysym::XK_space as u32 => Ok(Keysym::XK_space),
+            x if u32::from(x) == Keysym::XK_F1 as u32 => Ok(Keysym::XK_F1),
+            x if u32::from(x) == Keysym::XK_F2 as u32 => Ok(Keysym::XK_F2),
+            x if u32::from(x) == Keysym::XK_F3 as u32 => Ok(Keysym::XK_F3),
+            x if u32::from(x) == Keysym::XK_F4 as u32 => Ok(Keysym::XK_F4),
+            x if u32::from(x) == Keysym::XK_F5 as u32 => Ok(Keysym::XK_F5),
+            x if u32::from(x) == Keysym::XK_F6 as u32 => Ok(Keysym::XK_F6),
+            x if u32::from(x) == Keysym::XK_F7 as u32 => Ok(Keysym::XK_F7),
+            x if u32::from(x) == Keysym::XK_F8 as u32 => Ok(Keysym::XK_F8),
+            x if u32::from(x) == Keysym::XK_F9 as u32 => Ok(Keysym::XK_F9),
+            x if u32::from(x) == Keysym::XK_F10 as u32 => Ok(Keysym::XK_F10),
+            x if u32::from(x) == Keysym::XK_F11 as u32 => Ok(Keysym::XK_F11),
+            x if u32::from(x) == Keysym::XK_F12 as u32 => Ok(Keysym::XK_F12),
+            x if u32::from(x) == Keysym::XK_KP_Enter as u32 => Ok(Keysym::XK_KP_Enter),
+            x if u32::from(x) == Keysym::XK_KP_0 as u32 => Ok(Keysym::XK_KP_0),
+            x if u32::from(x) == Keysym::XK_KP_1 as u32 => Ok(Keysym::XK_KP_1),
+            x if u32::from(x) == Keysym::XK_KP_2 as u32 => Ok(Keysym::XK_KP_2),
+            x if u32::from(x) == Keysym::XK_KP_3 as u32 => Ok(Keysym::XK_KP_3),
+            x if u32::from(x) == Keysym::XK_KP_4 as u32 => Ok(Keysym::XK_KP_4),
+            x if u32::from(x) == Keysym::XK_KP_5 as u32 => Ok(Keysym::XK_KP_5),
+            x if u32::from(x) == Keysym::XK_KP_6 as u32 => Ok(Keysym::XK_KP_6),
+            x if u32::from(x) == Keysym::XK_KP_7 as u32 => Ok(Keysym::XK_KP_7),
+            x if u32::from(x) == Keysym::XK_KP_8 as u32 => Ok(Keysym::XK_KP_8),
+            x if u32::from(x) == Keysym::XK_KP_9 as u32 => Ok(Keysym::XK_KP_9),
             x if u32::from(x) == Keysym::XK_0 as u32 => Ok(Keysym::XK_0),
             x if u32::from(x) == Keysym::XK_1 as u32 => Ok(Keysym::XK_1),
             x if u32::from(x) == Keysym::XK_2 as u32 => Ok(Keysym::XK_2),
@@ -287,6 +395,42 @@ impl std::fmt::Display for Keysym {
             Keysym::XK_x => write!(f, "x"),
             Keysym::XK_y => write!(f, "y"),
             Keysym::XK_z => write!(f, "z"),
+            Keysym::XK_Tab => write!(f, "Tab"),
+            Keysym::XK_Escape => write!(f, "Escape"),
+            Keysym::XK_Home => write!(f, "Home"),
+            Keysym::XK_Left => write!(f, "Left"),
+            Keysym::XK_Up => write!(f, "Up"),
+            Keysym::XK_Right => write!(f, "Right"),
+            Keysym::XK_Down => write!(f, "Down"),
+            Keysym::XK_Prior => write!(f, "PageUp"),
+            Keysym::XK_Next => write!(f, "PageDown"),
+            Keysym::XK_End => write!(f, "End"),
+            Keysym::XK_Insert => write!(f, "Insert"),
+            Keysym::XK_Delete => write!(f, "Delete"),
+            Keysym::XK_space => write!(f, "Space"),
+            Keysym::XK_F1 => write!(f, "F1"),
+            Keysym::XK_F2 => write!(f, "F2"),
+            Keysym::XK_F3 => write!(f, "F3"),
+            Keysym::XK_F4 => write!(f, "F4"),
+            Keysym::XK_F5 => write!(f, "F5"),
+            Keysym::XK_F6 => write!(f, "F6"),
+            Keysym::XK_F7 => write!(f, "F7"),
+            Keysym::XK_F8 => write!(f, "F8"),
+            Keysym::XK_F9 => write!(f, "F9"),
+            Keysym::XK_F10 => write!(f, "F10"),
+            Keysym::XK_F11 => write!(f, "F11"),
+            Keysym::XK_F12 => write!(f, "F12"),
+            Keysym::XK_KP_Enter => write!(f, "KP_Enter"),
+            Keysym::XK_KP_0 => write!(f, "KP_0"),
+            Keysym::XK_KP_1 => write!(f, "KP_1"),
+            Keysym::XK_KP_2 => write!(f, "KP_2"),
+            Keysym::XK_KP_3 => write!(f, "KP_3"),
+            Keysym::XK_KP_4 => write!(f, "KP_4"),
+            Keysym::XK_KP_5 => write!(f, "KP_5"),
+            Keysym::XK_KP_6 => write!(f, "KP_6"),
+            Keysym::XK_KP_7 => write!(f, "KP_7"),
+            Keysym::XK_KP_8 => write!(f, "KP_8"),
+            Keysym::XK_KP_9 => write!(f, "KP_9"),
             _ => write!(f, ""),
         }
     }
@@ -334,7 +478,68 @@ impl TryFrom<&str> for Keysym {
             "y" => Ok(Keysym::XK_y),
             "z" => Ok(Keysym::XK_z),
             "Enter" => Ok(Keysym::XK_Return),
-            _ => Err(ConfigError::Key(format!("key {value} has invalid format"))),
+            "Tab" => Ok(Keysym::XK_Tab),
+            "Escape" | "Esc" => Ok(Keysym::XK_Escape),
+            "Home" => Ok(Keysym::XK_Home),
+            "Left" => Ok(Keysym::XK_Left),
+            "Up" => Ok(Keysym::XK_Up),
+            "Right" => Ok(Keysym::XK_Right),
+            "Down" => Ok(Keysym::XK_Down),
+            "PageUp" | "Page_Up" | "Prior" => Ok(Keysym::XK_Prior),
+            "PageDown" | "Page_Down" | "Next" => Ok(Keysym::XK_Next),
+            "End" => Ok(Keysym::XK_End),
+            "Insert" => Ok(Keysym::XK_Insert),
+            "Delete" => Ok(Keysym::XK_Delete),
+            "Space" => Ok(Keysym::XK_space),
+            "F1" => Ok(Keysym::XK_F1),
+            "F2" => Ok(Keysym::XK_F2),
+            "F3" => Ok(Keysym::XK_F3),
+            "F4" => Ok(Keysym::XK_F4),
+            "F5" => Ok(Keysym::XK_F5),
+            "F6" => Ok(Keysym::XK_F6),
+            "F7" => Ok(Keysym::XK_F7),
+            "F8" => Ok(Keysym::XK_F8),
+            "F9" => Ok(Keysym::XK_F9),
+            "F10" => Ok(Keysym::XK_F10),
+            "F11" => Ok(Keysym::XK_F11),
+            "F12" => Ok(Keysym::XK_F12),
+            "KP_Enter" => Ok(Keysym::XK_KP_Enter),
+            "KP_0" => Ok(Keysym::XK_KP_0),
+            "KP_1" => Ok(Keysym::XK_KP_1),
+            "KP_2" => Ok(Keysym::XK_KP_2),
+            "KP_3" => Ok(Keysym::XK_KP_3),
+            "KP_4" => Ok(Keysym::XK_KP_4),
+            "KP_5" => Ok(Keysym::XK_KP_5),
+            "KP_6" => Ok(Keysym::XK_KP_6),
+            "KP_7" => Ok(Keysym::XK_KP_7),
+            "KP_8" => Ok(Keysym::XK_KP_8),
+            "KP_9" => Ok(Keysym::XK_KP_9),
+            _ => Err(ConfigError::Key {
+                field: "key".into(),
+                message: format!("key {value} has invalid format"),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn function_keys_round_trip() {
+        for (name, raw, keysym) in [
+            ("F1", 0xffbe, Keysym::XK_F1),
+            ("F5", 0xffc2, Keysym::XK_F5),
+            ("F12", 0xffc9, Keysym::XK_F12),
+        ] {
+            let parsed = Keysym::try_from(name).ok().unwrap();
+            assert_eq!(parsed, keysym);
+            assert_eq!(parsed.to_string(), name);
+            assert_eq!(parsed.canonical_name(), format!("XK_{name}"));
+
+            let from_xkb = Keysym::try_from(xkbcommon::xkb::Keysym::new(raw)).unwrap();
+            assert_eq!(from_xkb, keysym);
         }
     }
 }
@@ -3,15 +3,18 @@ mod config;
 mod config_loader;
 pub mod keysyms;
 
-pub use config::{AutoCommand, AvailableActions, Config};
-use config_loader::{ConfigError, UnresolvedConfig};
+use anyhow::Context;
+pub use config::{
+    Action, AutoCommand, AvailableActions, AvailableDirections, AvailableLayouts, Config,
+    FloatPlacement, KeyTrigger, LayoutOrientation, Modifiers, NewClientMonitorFocus, Rule,
+    WorkspaceScope, WorkspaceSettings, WorkspaceSwitchFocus,
+};
 use std::path::{Path, PathBuf};
 
 static APP_NAME: &str = "lucky";
 static CONFIG_FILE: &str = "config.toml";
 static XDG_HOME: &str = "HOME";
 static XDG_CONFIG_HOME: &str = "XDG_CONFIG_HOME";
-static XDG_DATA_DIR: &str = "XDG_DATA_HOME";
 static LUCKY_CONF_ENV_VAR: &str = "LUCKY_CONFIG";
 
 /// Verify if `$HOME`/.config/lucky/config.toml exists
@@ -49,18 +52,70 @@ fn load_config_from_file<P>(path: P) -> anyhow::Result<Config>
 where
     P: AsRef<Path>,
 {
-    let config_file = std::fs::read_to_string(path.as_ref())?;
-    let config = toml::from_str::<UnresolvedConfig>(&config_file)?;
-    match Config::try_from(config) {
-        Ok(config) => Ok(config),
-        Err(e) => match e {
-            ConfigError::Key(msg) => anyhow::bail!(msg),
-            ConfigError::Workspaces(msg) => anyhow::bail!(msg),
-            ConfigError::BorderWidth(msg) => anyhow::bail!(msg),
-            ConfigError::BorderColor(msg) => anyhow::bail!(msg),
-            ConfigError::InvalidCommand(msg) => anyhow::bail!(msg),
-            ConfigError::Color(msg) => anyhow::bail!(msg),
-        },
+    let path = path.as_ref();
+    let config_file = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+
+    // a config without includes is deserialized straight from its source, which lets errors
+    // point at the line they happened
+    let includes = config_loader::parse_table(&config_file)
+        .with_context(|| format!("invalid config at {}", path.display()))?
+        .contains_key(config_loader::INCLUDE_KEY);
+    let config = match includes {
+        true => {
+            let table = read_config_table(path, &mut vec![])?;
+            config_loader::config_from_table(table)
+        }
+        false => config_loader::parse_config(&config_file),
+    }
+    .and_then(Config::try_from)
+    .with_context(|| format!("invalid config at {}", path.display()))?;
+
+    Ok(config)
+}
+
+/// reads the config at `path` merged with every file it includes. Includes are resolved
+/// relative to the file including them and merged in order, so later entries override earlier
+/// ones, and the including file overrides all of its includes
+fn read_config_table(path: &Path, visited: &mut Vec<PathBuf>) -> anyhow::Result<toml::Table> {
+    let canonical = path
+        .canonicalize()
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    if visited.contains(&canonical) {
+        anyhow::bail!("{} is included in a cycle", path.display());
+    }
+    visited.push(canonical);
+
+    let config_file = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    let mut table = config_loader::parse_table(&config_file)
+        .with_context(|| format!("invalid config at {}", path.display()))?;
+    let includes = config_loader::take_includes(&mut table)
+        .with_context(|| format!("invalid config at {}", path.display()))?;
+
+    let directory = path.parent().unwrap_or(Path::new("."));
+    let mut merged = toml::Table::new();
+    for include in includes {
+        let included = read_config_table(&directory.join(include), visited)?;
+        config_loader::merge_tables(&mut merged, included);
+    }
+    config_loader::merge_tables(&mut merged, table);
+
+    // only the files currently being included matter for cycles, the same file can still be
+    // included by two unrelated files
+    visited.pop();
+
+    Ok(merged)
+}
+
+/// resolves where the config file is, `LUCKY_CONFIG` takes priority over the XDG directories
+fn config_path() -> Option<PathBuf> {
+    match std::env::var(LUCKY_CONF_ENV_VAR) {
+        Ok(var) => {
+            tracing::debug!("loading config file from $LUCKY_CONFIG: {var:?}");
+            Some(PathBuf::from(&var).join(CONFIG_FILE))
+        }
+        Err(_) => get_config_dir_path(),
     }
 }
 
@@ -71,46 +126,32 @@ where
 /// * If not available, will attempt to load from `HOME`/.config/lucky/config.toml;
 /// * If not present on any of the directories above, will load the default configuration;
 pub fn load_config() -> Config {
-    let config_path = match std::env::var(LUCKY_CONF_ENV_VAR) {
-        Ok(var) => {
-            tracing::debug!("loading config file from $LUCKY_CONFIG: {var:?}");
-            Some(PathBuf::from(&var).join(CONFIG_FILE))
-        }
-        Err(_) => get_config_dir_path(),
-    };
-    match config_path
+    match config_path()
         .map(load_config_from_file)
         .unwrap_or(Ok(Config::default()))
     {
         Ok(config) => config,
         Err(e) => {
             tracing::error!("{e:?}");
+            // logs are not where most people look first, so the error also goes to stderr
+            eprintln!("lucky: {e:#}, falling back to the default config");
             Config::default()
         }
     }
 }
 
-fn data_dir() -> anyhow::Result<PathBuf> {
-    let data_path = match std::env::var(XDG_DATA_DIR) {
-        Ok(data_path) => PathBuf::from(data_path).join(APP_NAME),
-        Err(_) => match std::env::var(XDG_HOME) {
-            Ok(home_path) => {
-                let data_path = PathBuf::from(home_path)
-                    .join(".local")
-                    .join("share")
-                    .join(APP_NAME);
-                if !data_path.is_dir() {
-                    std::fs::create_dir(&data_path)?;
-                }
-                data_path
-            }
-            Err(_) => anyhow::bail!("failed to get $HOME environment variable"),
-        },
+/// validates the config file at `path`, or the one `load_config` would load when no path is
+/// given, returning the path that was checked. A directory is treated like `LUCKY_CONFIG`
+pub fn check_config(path: Option<PathBuf>) -> anyhow::Result<PathBuf> {
+    let path = match path {
+        Some(path) if path.is_dir() => path.join(CONFIG_FILE),
+        Some(path) => path,
+        None => config_path().context(
+            "could not find where the config is, set $LUCKY_CONFIG, $XDG_CONFIG_HOME or $HOME",
+        )?,
     };
 
-    Ok(data_path)
-}
+    load_config_from_file(&path)?;
 
-pub fn log_file() -> anyhow::Result<(PathBuf, String)> {
-    Ok((data_dir()?, format!("{}.log", APP_NAME)))
+    Ok(path)
 }
@@ -1,27 +1,259 @@
+use crate::atoms::Atoms;
 use crate::position::Position;
 use crate::xcb_utils::*;
 use config::Config;
-use std::{cell::RefCell, rc::Rc, sync::Arc};
+use std::{
+    cell::RefCell,
+    collections::{hash_map::Entry, HashMap},
+    rc::Rc,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use xcb::Xid;
 
 use crate::screen::Client;
 
+/// flag on `WM_HINTS` telling whether the input field is set
+const WM_HINTS_INPUT_HINT: u32 = 1;
+
+/// how many opacity changes a frame goes through until it is fully opaque
+const FADE_STEPS: u32 = 10;
+
+/// time between each opacity change of a fade
+const FADE_INTERVAL: Duration = Duration::from_millis(15);
+
+/// a frame being faded in, one step at a time
+struct Fade {
+    frame: xcb::x::Window,
+    step: u32,
+    next_step_at: Instant,
+}
+
 pub struct Decorator {
     config: Rc<RefCell<Config>>,
     conn: Arc<xcb::Connection>,
+    atoms: Atoms,
+    /// whether the server has the SHAPE extension, which rounds the corners of the frames
+    has_shape: bool,
+    /// frames still fading in with `animations`
+    fades: RefCell<Vec<Fade>>,
+    /// the window drawn behind each frame with `fake_shadow`, keyed by the frame
+    shadows: RefCell<HashMap<xcb::x::Window, xcb::x::Window>>,
 }
 
 impl Decorator {
-    pub fn new(conn: Arc<xcb::Connection>, config: Rc<RefCell<Config>>) -> Self {
-        Decorator { conn, config }
+    pub fn new(conn: Arc<xcb::Connection>, config: Rc<RefCell<Config>>, atoms: Atoms) -> Self {
+        let has_shape = conn
+            .wait_for_reply(conn.send_request(&xcb::x::QueryExtension {
+                name: xcb::shape::XNAME.as_bytes(),
+            }))
+            .is_ok_and(|reply| reply.present());
+        if !has_shape && config.borrow().border_radius() > 0 {
+            tracing::warn!("SHAPE is not available, windows will have square corners");
+        }
+
+        Decorator {
+            conn,
+            config,
+            atoms,
+            has_shape,
+            fades: RefCell::new(vec![]),
+            shadows: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// with `animations`, starts a frame out almost transparent so a compositor can fade it
+    /// in. This has to happen before the frame is mapped, or it would show up at full opacity
+    fn fade_in(&self, frame: xcb::x::Window) {
+        if !self.config.borrow().animations() {
+            return;
+        }
+
+        self.set_opacity(frame, 0);
+        self.fades.borrow_mut().push(Fade {
+            frame,
+            step: 0,
+            next_step_at: Instant::now() + FADE_INTERVAL,
+        });
+    }
+
+    /// moves every fade that is due one step closer to full opacity, returning whether any
+    /// request was sent. Fully opaque frames lose the property, which means the same thing
+    pub fn advance_fades(&self) -> bool {
+        let mut fades = self.fades.borrow_mut();
+        if fades.is_empty() {
+            return false;
+        }
+
+        let now = Instant::now();
+        let mut changed = false;
+        for fade in fades.iter_mut().filter(|fade| fade.next_step_at <= now) {
+            fade.step += 1;
+            fade.next_step_at = now + FADE_INTERVAL;
+            changed = true;
+
+            if fade.step >= FADE_STEPS {
+                self.conn.send_request(&xcb::x::DeleteProperty {
+                    window: fade.frame,
+                    property: self.atoms.net_wm_window_opacity,
+                });
+            } else {
+                self.set_opacity(fade.frame, fade.step);
+            }
+        }
+        fades.retain(|fade| fade.step < FADE_STEPS);
+
+        changed
+    }
+
+    /// the frame may be gone by the time a fade step runs, so the request is not checked
+    fn set_opacity(&self, frame: xcb::x::Window, step: u32) {
+        let opacity = (u32::MAX as u64 * step as u64 / FADE_STEPS as u64) as u32;
+        self.conn.send_request(&xcb::x::ChangeProperty {
+            mode: xcb::x::PropMode::Replace,
+            window: frame,
+            property: self.atoms.net_wm_window_opacity,
+            r#type: xcb::x::ATOM_CARDINAL,
+            data: &[opacity],
+        });
+    }
+
+    /// rounds the corners of a frame placed at the `outer` geometry, border included. A radius
+    /// of zero gives the frame back its rectangular shape
+    pub fn shape_frame(
+        &self,
+        frame: xcb::x::Window,
+        outer: &Position,
+        border_width: u32,
+        radius: u32,
+    ) {
+        if !self.has_shape {
+            return;
+        }
+
+        if radius.eq(&0) {
+            self.conn.send_request(&xcb::shape::Mask {
+                operation: xcb::shape::So::Set,
+                destination_kind: xcb::shape::Sk::Bounding,
+                destination_window: frame,
+                x_offset: 0,
+                y_offset: 0,
+                source_bitmap: xcb::x::Pixmap::none(),
+            });
+            return;
+        }
+
+        // the bounding shape is relative to the inside of the border
+        self.conn.send_request(&xcb::shape::Rectangles {
+            operation: xcb::shape::So::Set,
+            destination_kind: xcb::shape::Sk::Bounding,
+            ordering: xcb::x::ClipOrdering::Unsorted,
+            destination_window: frame,
+            x_offset: -(border_width as i16),
+            y_offset: -(border_width as i16),
+            rectangles: &rounded_rectangle(outer.width, outer.height, radius),
+        });
+    }
+
+    /// with `fake_shadow`, places a shadow right below every given frame, offset towards the
+    /// bottom right, creating the ones that are missing. Shadows of the frames `owned` accepts
+    /// that are not given are hidden, and every shadow is destroyed once `fake_shadow` is
+    /// turned off
+    pub fn place_shadows(
+        &self,
+        frames: &[(xcb::x::Window, Position)],
+        owned: impl Fn(xcb::x::Window) -> bool,
+    ) -> anyhow::Result<()> {
+        let config = self.config.borrow();
+        let mut shadows = self.shadows.borrow_mut();
+
+        if !config.fake_shadow() {
+            for (_, shadow) in shadows.drain() {
+                self.conn
+                    .send_request(&xcb::x::DestroyWindow { window: shadow });
+            }
+            return Ok(());
+        }
+
+        let offset = config.shadow_offset() as i32;
+        for (frame, geometry) in frames {
+            let shadow = match shadows.entry(*frame) {
+                Entry::Occupied(entry) => *entry.get(),
+                Entry::Vacant(entry) => *entry.insert(self.create_shadow()?),
+            };
+            self.conn.send_request(&xcb::x::ConfigureWindow {
+                window: shadow,
+                value_list: &[
+                    xcb::x::ConfigWindow::X(geometry.x + offset),
+                    xcb::x::ConfigWindow::Y(geometry.y + offset),
+                    xcb::x::ConfigWindow::Width(geometry.width.max(1)),
+                    xcb::x::ConfigWindow::Height(geometry.height.max(1)),
+                    xcb::x::ConfigWindow::Sibling(*frame),
+                    xcb::x::ConfigWindow::StackMode(xcb::x::StackMode::Below),
+                ],
+            });
+            xcb_map_win!(self.conn, shadow);
+        }
+
+        for (frame, shadow) in shadows.iter() {
+            if owned(*frame) && !frames.iter().any(|(other, _)| other.eq(frame)) {
+                xcb_unmap_win!(self.conn, *shadow);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// destroys the shadow of a frame that is going away
+    pub fn remove_shadow(&self, frame: xcb::x::Window) {
+        if let Some(shadow) = self.shadows.borrow_mut().remove(&frame) {
+            self.conn
+                .send_request(&xcb::x::DestroyWindow { window: shadow });
+        }
+    }
+
+    /// shadows are never managed, so they are created with override redirect
+    fn create_shadow(&self) -> anyhow::Result<xcb::x::Window> {
+        let root = self
+            .conn
+            .get_setup()
+            .roots()
+            .next()
+            .expect("should have at least one screen to manage")
+            .root();
+
+        let shadow = xcb_create_win!(
+            self.conn,
+            root,
+            Position::new(0, 0, 1, 1),
+            &[
+                xcb::x::Cw::BackPixel(self.config.borrow().shadow_color()),
+                xcb::x::Cw::OverrideRedirect(true),
+            ],
+        );
+
+        Ok(shadow)
     }
 
-    pub fn decorate_client(&self, client: xcb::x::Window) -> anyhow::Result<xcb::x::Window> {
-        let frame = self.create_frame()?;
+    /// frames of clients that asked not to be decorated are created without a border
+    pub fn decorate_client(
+        &self,
+        client: xcb::x::Window,
+        border: bool,
+    ) -> anyhow::Result<xcb::x::Window> {
+        let frame = self.create_frame(border)?;
+        self.fade_in(frame);
+        // clients on the save set are given back to the root window when we go away, instead
+        // of being destroyed along with their frames, so a new instance can adopt them
+        self.conn.send_request(&xcb::x::ChangeSaveSet {
+            mode: xcb::x::SetMode::Insert,
+            window: client,
+        });
         xcb_reparent_win!(self.conn, client, frame)?;
         Ok(frame)
     }
 
-    fn create_frame(&self) -> anyhow::Result<xcb::x::Window> {
+    fn create_frame(&self, border: bool) -> anyhow::Result<xcb::x::Window> {
         let root = self
             .conn
             .get_setup()
@@ -34,7 +266,11 @@ impl Decorator {
             self.conn,
             root,
             Position::new(0, 0, 1, 1),
-            self.config.borrow().border_width(),
+            if border {
+                self.config.borrow().border_width()
+            } else {
+                0
+            },
             &[
                 xcb::x::Cw::BackPixel(0),
                 xcb::x::Cw::BorderPixel(self.config.borrow().border_color()),
@@ -52,15 +288,61 @@ impl Decorator {
         Ok(frame)
     }
 
+    /// re-applies the configured border to every given client, used when the configuration
+    /// is reloaded, as frames only read the border configuration when they are created or
+    /// displayed, and clients on hidden workspaces would keep the old one
+    pub fn refresh_borders<'a>(&self, clients: impl Iterator<Item = &'a Client>) {
+        let config = self.config.borrow();
+        for shadow in self.shadows.borrow().values() {
+            xcb_change_attr!(
+                self.conn,
+                *shadow,
+                &[xcb::x::Cw::BackPixel(config.shadow_color())]
+            );
+            self.conn.send_request(&xcb::x::ClearArea {
+                exposures: false,
+                window: *shadow,
+                x: 0,
+                y: 0,
+                width: 0,
+                height: 0,
+            });
+        }
+        for client in clients {
+            xcb_change_attr!(
+                self.conn,
+                client.frame,
+                &[xcb::x::Cw::BorderPixel(self.border_color(client, &config))]
+            );
+            self.conn.send_request(&xcb::x::ConfigureWindow {
+                window: client.frame,
+                value_list: &[xcb::x::ConfigWindow::BorderWidth(
+                    client.border_width(&config),
+                )],
+            });
+        }
+    }
+
     pub fn unfocus_client(&self, client: &Client) -> anyhow::Result<()> {
         xcb_change_attr!(
             self.conn,
             client.frame,
-            &[xcb::x::Cw::BorderPixel(self.config.borrow().border_color())]
+            &[xcb::x::Cw::BorderPixel(
+                self.border_color(client, &self.config.borrow())
+            )]
         );
         Ok(())
     }
 
+    /// the color of the border of a client that is not focused
+    fn border_color(&self, client: &Client, config: &Config) -> u32 {
+        if client.urgent {
+            config.urgent_border_color()
+        } else {
+            config.border_color()
+        }
+    }
+
     pub fn focus_client(&self, client: &Client) -> anyhow::Result<()> {
         xcb_change_attr!(
             self.conn,
@@ -69,8 +351,114 @@ impl Decorator {
                 self.config.borrow().active_border_color()
             )]
         );
-        xcb_input_focus!(self.conn, client.window);
+        self.set_input_focus(client);
 
         Ok(())
     }
+
+    /// gives the keyboard focus to the client following the ICCCM input models. Clients that
+    /// accept input through `WM_HINTS`, which is assumed when they don't say otherwise, get the
+    /// focus set directly. Clients that list `WM_TAKE_FOCUS` on their `WM_PROTOCOLS` are asked
+    /// to take it themselves, and clients that do neither never get the focus
+    ///
+    /// both properties are requested before waiting for any reply, so focusing costs a single
+    /// round trip to the server
+    fn set_input_focus(&self, client: &Client) {
+        let wm_hints = self.conn.send_request(&xcb::x::GetProperty {
+            delete: false,
+            window: client.window,
+            property: xcb::x::ATOM_WM_HINTS,
+            r#type: xcb::x::ATOM_WM_HINTS,
+            long_offset: 0,
+            long_length: 9,
+        });
+        let wm_protocols = self.conn.send_request(&xcb::x::GetProperty {
+            delete: false,
+            window: client.window,
+            property: self.atoms.wm_protocols,
+            r#type: xcb::x::ATOM_ATOM,
+            long_offset: 0,
+            long_length: 1024,
+        });
+
+        let accepts_input = self
+            .conn
+            .wait_for_reply(wm_hints)
+            .ok()
+            .and_then(|reply| match reply.value::<u32>() {
+                [flags, input, ..] if flags & WM_HINTS_INPUT_HINT != 0 => Some(*input != 0),
+                _ => None,
+            })
+            .unwrap_or(true);
+
+        let takes_focus = self
+            .conn
+            .wait_for_reply(wm_protocols)
+            .map(|reply| {
+                reply
+                    .value::<xcb::x::Atom>()
+                    .contains(&self.atoms.wm_take_focus)
+            })
+            .unwrap_or(false);
+
+        if accepts_input {
+            xcb_input_focus!(self.conn, client.window);
+        }
+
+        if takes_focus {
+            let event = xcb::x::ClientMessageEvent::new(
+                client.window,
+                self.atoms.wm_protocols,
+                xcb::x::ClientMessageData::Data32([
+                    self.atoms.wm_take_focus.resource_id(),
+                    xcb::x::CURRENT_TIME,
+                    0,
+                    0,
+                    0,
+                ]),
+            );
+
+            xcb_send_event!(
+                self.conn,
+                xcb::x::SendEventDest::Window(client.window),
+                &event
+            );
+        }
+    }
+}
+
+/// covers a `width` by `height` rectangle with rounded corners using one rectangle per row of
+/// the corners and a single one for everything between them
+fn rounded_rectangle(width: u32, height: u32, radius: u32) -> Vec<xcb::x::Rectangle> {
+    let radius = radius.min(width / 2).min(height / 2);
+    let mut rectangles = Vec::with_capacity(radius as usize * 2 + 1);
+
+    for row in 0..radius {
+        let distance = radius as f64 - row as f64 - 0.5;
+        let inset = (radius as f64 - (radius.pow(2) as f64 - distance.powi(2)).sqrt()).round();
+        let inset = inset as u32;
+        let row_width = width.saturating_sub(inset * 2) as u16;
+
+        rectangles.push(xcb::x::Rectangle {
+            x: inset as i16,
+            y: row as i16,
+            width: row_width,
+            height: 1,
+        });
+        rectangles.push(xcb::x::Rectangle {
+            x: inset as i16,
+            y: (height - row - 1) as i16,
+            width: row_width,
+            height: 1,
+        });
+    }
+
+    rectangles.push(xcb::x::Rectangle {
+        x: 0,
+        y: radius as i16,
+        width: width as u16,
+        height: (height - radius * 2) as u16,
+    });
+
+    rectangles
 }
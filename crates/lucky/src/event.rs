@@ -1,6 +1,6 @@
 use crate::{
-    atoms::Atoms, decorator::Decorator, keyboard::Keyboard, layout_manager::LayoutManager,
-    screen_manager::ScreenManager,
+    atoms::Atoms, decorator::Decorator, ipc::IpcRequest, keyboard::Keyboard,
+    layout_manager::LayoutManager, screen_manager::ScreenManager,
 };
 use config::{AvailableActions, Config};
 use std::{
@@ -201,3 +201,41 @@ impl Clone for EventContext<'_, xcb::x::PropertyNotifyEvent> {
         }
     }
 }
+
+impl Clone for EventContext<'_, xcb::x::ClientMessageEvent> {
+    fn clone(&self) -> Self {
+        let event = xcb::x::ClientMessageEvent::new(
+            self.event.window(),
+            self.event.r#type(),
+            self.event.data(),
+        );
+
+        Self {
+            event,
+            conn: self.conn.clone(),
+            config: self.config.clone(),
+            keyboard: self.keyboard,
+            screen_manager: self.screen_manager.clone(),
+            atoms: self.atoms,
+            decorator: self.decorator,
+            layout_manager: self.layout_manager,
+            action_tx: self.action_tx.clone(),
+        }
+    }
+}
+
+impl Clone for EventContext<'_, IpcRequest> {
+    fn clone(&self) -> Self {
+        Self {
+            event: self.event.clone(),
+            conn: self.conn.clone(),
+            config: self.config.clone(),
+            keyboard: self.keyboard,
+            screen_manager: self.screen_manager.clone(),
+            atoms: self.atoms,
+            decorator: self.decorator,
+            layout_manager: self.layout_manager,
+            action_tx: self.action_tx.clone(),
+        }
+    }
+}
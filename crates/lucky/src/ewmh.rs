@@ -5,6 +5,7 @@ use crate::xcb_utils::*;
 
 use std::collections::HashMap;
 use std::sync::Arc;
+use xcb::Xid;
 
 /// updates _NET_CURRENT_DESKTOP atom with the number of workspaces
 /// on the active screen (monitor).
@@ -76,7 +77,7 @@ pub fn ewmh_set_desktop_names(
         conn,
         root,
         xcb::x::PropMode::Replace,
-        xcb::x::ATOM_STRING,
+        atoms.utf8_string,
         atoms.net_desktop_names,
         &screen
             .workspaces()
@@ -89,23 +90,33 @@ pub fn ewmh_set_desktop_names(
     )
 }
 
-/// updates _NET_WM_DESKTOP for all clients on all workspaces for the
-/// current screen
+/// updates _NET_WM_DESKTOP for all clients on all workspaces of every
+/// screen, so pagers know where windows on other monitors are as well.
+/// sticky clients are on every desktop, which is told by `0xFFFFFFFF`
 pub fn ewmh_set_wm_desktop(
     conn: &Arc<xcb::Connection>,
-    screen: &Screen,
+    screens: &[Screen],
     client_map: &HashMap<xcb::x::Window, crate::screen::Client>,
     atoms: &Atoms,
 ) -> anyhow::Result<(), xcb::ProtocolError> {
-    for workspace in screen.workspaces() {
-        for client in workspace.clients() {
+    for workspace in screens.iter().flat_map(|screen| screen.workspaces()) {
+        for client in workspace
+            .clients()
+            .iter()
+            .filter_map(|frame| client_map.get(frame))
+        {
+            let desktop = if client.sticky {
+                u32::MAX
+            } else {
+                workspace.id() as u32
+            };
             xcb_change_prop!(
                 conn,
-                client_map.get(client).unwrap().window,
+                client.window,
                 xcb::x::PropMode::Replace,
                 xcb::x::ATOM_CARDINAL,
                 atoms.net_wm_desktop,
-                &[workspace.id() as u32],
+                &[desktop],
             )?;
         }
     }
@@ -168,6 +179,29 @@ pub fn ewmh_set_focus(
     }
 }
 
+/// adds or removes one of the `_NET_WM_STATE` atoms, like _NET_WM_STATE_FULLSCREEN, from
+/// the client window
+pub fn ewmh_set_wm_state(
+    conn: &Arc<xcb::Connection>,
+    atoms: &Atoms,
+    window: xcb::x::Window,
+    state: xcb::x::Atom,
+    enabled: bool,
+) -> anyhow::Result<(), xcb::ProtocolError> {
+    if enabled {
+        xcb_change_prop!(
+            conn,
+            window,
+            xcb::x::PropMode::Append,
+            xcb::x::ATOM_ATOM,
+            atoms.net_wm_state,
+            &[state],
+        )
+    } else {
+        xcb_remove_prop!(conn, window, xcb::x::ATOM_ATOM, atoms.net_wm_state, state)
+    }
+}
+
 /// list all the clients currently managed by the window manager
 /// by order of insertion
 pub fn ewmh_set_client_list<'a, I>(
@@ -191,8 +225,7 @@ where
 }
 
 /// list all the clients currently managed by the window manager
-/// by stacking order, since we dont stack windows, this is the same
-/// as the other list
+/// by stacking order, from the bottom up
 pub fn ewmh_set_client_list_stacking<'a, I>(
     conn: &Arc<xcb::Connection>,
     root: xcb::x::Window,
@@ -306,3 +339,268 @@ pub fn ewmh_set_showing_desktop(
         &[u32::from(action)]
     )
 }
+
+/// the ICCCM `WM_CLASS` of a window, made of the instance and the class names
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WmClass {
+    pub instance: String,
+    pub class: String,
+}
+
+impl WmClass {
+    /// reads `WM_CLASS` from the window, which holds two null terminated strings
+    pub fn get(conn: &Arc<xcb::Connection>, window: xcb::x::Window) -> Option<Self> {
+        let reply = xcb_get_prop!(
+            conn,
+            window,
+            xcb::x::ATOM_WM_CLASS,
+            1024,
+            xcb::x::ATOM_STRING
+        )
+        .ok()?;
+        let mut names = reply
+            .value::<u8>()
+            .split(|&byte| byte == 0)
+            .map(|name| String::from_utf8_lossy(name).into_owned());
+
+        Some(WmClass {
+            instance: names.next()?,
+            class: names.next().unwrap_or_default(),
+        })
+    }
+
+    /// rules can refer to a window by either its instance or its class name
+    pub fn matches(&self, name: &str) -> bool {
+        self.instance.eq(name) || self.class.eq(name)
+    }
+}
+
+/// the process id that owns a window, as advertised by the client on `_NET_WM_PID`
+pub fn ewmh_get_pid(
+    conn: &Arc<xcb::Connection>,
+    atoms: &Atoms,
+    window: xcb::x::Window,
+) -> Option<u32> {
+    xcb_get_prop!(conn, window, atoms.net_wm_pid, 1, xcb::x::ATOM_CARDINAL)
+        .ok()
+        .and_then(|reply| reply.value::<u32>().first().copied())
+}
+
+/// whether the window declares itself a dock, like a bar or a panel, on `_NET_WM_WINDOW_TYPE`
+pub fn ewmh_is_dock(conn: &Arc<xcb::Connection>, atoms: &Atoms, window: xcb::x::Window) -> bool {
+    xcb_get_prop!(
+        conn,
+        window,
+        atoms.net_wm_window_type,
+        32,
+        xcb::x::ATOM_ATOM
+    )
+    .is_ok_and(|reply| {
+        reply
+            .value::<xcb::x::Atom>()
+            .contains(&atoms.net_wm_window_type_dock)
+    })
+}
+
+/// the window currently focused, as advertised on `_NET_ACTIVE_WINDOW` of the root window
+pub fn ewmh_get_active_window(
+    conn: &Arc<xcb::Connection>,
+    atoms: &Atoms,
+    root: xcb::x::Window,
+) -> Option<xcb::x::Window> {
+    xcb_get_prop!(conn, root, atoms.net_active_window, 1, xcb::x::ATOM_WINDOW)
+        .ok()
+        .and_then(|reply| reply.value::<xcb::x::Window>().first().copied())
+        .filter(|window| !window.is_none())
+}
+
+/// the workspace shown on the active screen, as advertised on `_NET_CURRENT_DESKTOP`
+pub fn ewmh_get_current_desktop(
+    conn: &Arc<xcb::Connection>,
+    atoms: &Atoms,
+    root: xcb::x::Window,
+) -> Option<u32> {
+    xcb_get_prop!(
+        conn,
+        root,
+        atoms.net_current_desktop,
+        1,
+        xcb::x::ATOM_CARDINAL
+    )
+    .ok()
+    .and_then(|reply| reply.value::<u32>().first().copied())
+}
+
+/// every managed window, as advertised on `_NET_CLIENT_LIST`
+pub fn ewmh_get_client_list(
+    conn: &Arc<xcb::Connection>,
+    atoms: &Atoms,
+    root: xcb::x::Window,
+) -> Vec<xcb::x::Window> {
+    xcb_get_prop!(
+        conn,
+        root,
+        atoms.net_client_list,
+        u32::MAX,
+        xcb::x::ATOM_WINDOW
+    )
+    .map(|reply| reply.value::<xcb::x::Window>().to_vec())
+    .unwrap_or_default()
+}
+
+/// whether a window wants to be decorated according to its `_MOTIF_WM_HINTS`, which holds the
+/// flags, functions, decorations, input mode and status fields. Windows without the property,
+/// or that don't set the decorations field, are decorated
+pub fn motif_wants_decorations(
+    conn: &Arc<xcb::Connection>,
+    atoms: &Atoms,
+    window: xcb::x::Window,
+) -> bool {
+    const MWM_HINTS_DECORATIONS: u32 = 1 << 1;
+    const MWM_DECOR_ALL: u32 = 1;
+    const MWM_DECOR_BORDER: u32 = 1 << 1;
+
+    xcb_get_prop!(conn, window, atoms.motif_wm_hints, 5, atoms.motif_wm_hints)
+        .ok()
+        .and_then(|reply| match reply.value::<u32>() {
+            [flags, _, decorations, ..] if flags & MWM_HINTS_DECORATIONS != 0 => {
+                Some(decorations & (MWM_DECOR_ALL | MWM_DECOR_BORDER) != 0)
+            }
+            _ => None,
+        })
+        .unwrap_or(true)
+}
+
+/// whether the urgency flag is set on the ICCCM `WM_HINTS` of a window
+pub fn icccm_is_urgent(conn: &Arc<xcb::Connection>, window: xcb::x::Window) -> bool {
+    const URGENCY_HINT: u32 = 1 << 8;
+
+    xcb_get_prop!(
+        conn,
+        window,
+        xcb::x::ATOM_WM_HINTS,
+        9,
+        xcb::x::ATOM_WM_HINTS
+    )
+    .ok()
+    .and_then(|reply| reply.value::<u32>().first().copied())
+    .is_some_and(|flags| flags & URGENCY_HINT != 0)
+}
+
+/// the ICCCM `WM_NORMAL_HINTS` of a window, only the fields that constrain its size are kept.
+/// Aspect ratios are kept as `(numerator, denominator)` pairs
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct SizeHints {
+    pub min: Option<(u32, u32)>,
+    pub max: Option<(u32, u32)>,
+    pub base: Option<(u32, u32)>,
+    pub increment: Option<(u32, u32)>,
+    pub min_aspect: Option<(u32, u32)>,
+    pub max_aspect: Option<(u32, u32)>,
+}
+
+impl SizeHints {
+    const P_MIN_SIZE: u32 = 1 << 4;
+    const P_MAX_SIZE: u32 = 1 << 5;
+    const P_RESIZE_INC: u32 = 1 << 6;
+    const P_ASPECT: u32 = 1 << 7;
+    const P_BASE_SIZE: u32 = 1 << 8;
+
+    /// reads `WM_NORMAL_HINTS` from the window, which is made of 18 values, the first one
+    /// flagging which of the others are set
+    pub fn get(conn: &Arc<xcb::Connection>, window: xcb::x::Window) -> Option<Self> {
+        let reply = xcb_get_prop!(
+            conn,
+            window,
+            xcb::x::ATOM_WM_NORMAL_HINTS,
+            18,
+            xcb::x::ATOM_WM_SIZE_HINTS
+        )
+        .ok()?;
+        let values = reply.value::<u32>();
+        if values.len() < 17 {
+            return None;
+        }
+
+        let flags = values[0];
+        let pair = |flag: u32, first: usize| {
+            (flags & flag != 0).then_some((values[first], values[first + 1]))
+        };
+
+        Some(SizeHints {
+            min: pair(Self::P_MIN_SIZE, 5),
+            max: pair(Self::P_MAX_SIZE, 7),
+            increment: pair(Self::P_RESIZE_INC, 9),
+            min_aspect: pair(Self::P_ASPECT, 11),
+            max_aspect: pair(Self::P_ASPECT, 13),
+            base: pair(Self::P_BASE_SIZE, 15),
+        })
+    }
+
+    /// the size closest to `width` and `height` the window accepts. Following the ICCCM the
+    /// base size is taken out before applying the aspect ratio and the increments, and the
+    /// minimum size is used as the base when there is none
+    pub fn constrain(&self, width: u32, height: u32) -> (u32, u32) {
+        let (base_width, base_height) = self.base.or(self.min).unwrap_or((0, 0));
+        let mut width = width.saturating_sub(base_width);
+        let mut height = height.saturating_sub(base_height);
+
+        if let Some((num, den)) = self.max_aspect.filter(|&(num, den)| num > 0 && den > 0) {
+            if width as u64 * den as u64 > height as u64 * num as u64 {
+                width = (height as u64 * num as u64 / den as u64) as u32;
+            }
+        }
+        if let Some((num, den)) = self.min_aspect.filter(|&(num, den)| num > 0 && den > 0) {
+            if height as u64 * num as u64 > width as u64 * den as u64 {
+                height = (width as u64 * den as u64 / num as u64) as u32;
+            }
+        }
+
+        if let Some((width_inc, height_inc)) = self.increment {
+            if width_inc > 0 {
+                width -= width % width_inc;
+            }
+            if height_inc > 0 {
+                height -= height % height_inc;
+            }
+        }
+
+        let (mut width, mut height) = (width + base_width, height + base_height);
+
+        if let Some((min_width, min_height)) = self.min.or(self.base) {
+            width = width.max(min_width);
+            height = height.max(min_height);
+        }
+        if let Some((max_width, max_height)) = self.max {
+            if max_width > 0 {
+                width = width.min(max_width);
+            }
+            if max_height > 0 {
+                height = height.min(max_height);
+            }
+        }
+
+        (width.max(1), height.max(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn size_hints_round_down_to_increments_and_respect_bounds() {
+        let hints = SizeHints {
+            base: Some((2, 4)),
+            increment: Some((7, 15)),
+            min: Some((30, 30)),
+            max: Some((500, 0)),
+            ..Default::default()
+        };
+
+        assert_eq!(hints.constrain(101, 100), (100, 94));
+        assert_eq!(hints.constrain(10, 10), (30, 30));
+        assert_eq!(hints.constrain(1000, 1000), (500, 994));
+        assert_eq!(SizeHints::default().constrain(123, 45), (123, 45));
+    }
+}
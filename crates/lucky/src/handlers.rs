@@ -1,16 +1,21 @@
 mod action;
+mod client_message;
 mod command;
 mod handler;
 mod hover;
+mod ipc;
 mod map_window;
 mod property_handler;
 mod unmap_window;
 
 use crate::event::EventContext;
+use crate::ipc::IpcRequest;
 use action::ActionHandler;
+use client_message::ClientMessageHandler;
 use command::CommandHandler;
 use handler::Handler;
 use hover::HoverHandler;
+use ipc::IpcHandler;
 use map_window::MapWindowHandler;
 use property_handler::PropertyHandler;
 use unmap_window::UnmapWindowHandler;
@@ -30,6 +35,8 @@ impl Default for Handlers {
                 Box::<UnmapWindowHandler>::default(),
                 Box::<HoverHandler>::default(),
                 Box::<PropertyHandler>::default(),
+                Box::<IpcHandler>::default(),
+                Box::<ClientMessageHandler>::default(),
             ],
         }
     }
@@ -48,6 +55,18 @@ impl Handlers {
         Ok(())
     }
 
+    #[tracing::instrument(skip_all, err)]
+    pub fn on_key_release(
+        &mut self,
+        context: EventContext<xcb::x::KeyReleaseEvent>,
+    ) -> anyhow::Result<()> {
+        for handler in self.handlers.iter_mut() {
+            handler.on_key_release(context.clone())?;
+        }
+
+        Ok(())
+    }
+
     #[tracing::instrument(skip_all, err)]
     pub fn on_map_request(
         &mut self,
@@ -106,4 +125,25 @@ impl Handlers {
 
         Ok(())
     }
+
+    #[tracing::instrument(skip_all, err)]
+    pub fn on_client_message(
+        &mut self,
+        context: EventContext<xcb::x::ClientMessageEvent>,
+    ) -> anyhow::Result<()> {
+        for handler in self.handlers.iter_mut() {
+            handler.on_client_message(context.clone())?;
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all, err)]
+    pub fn on_ipc(&mut self, context: EventContext<IpcRequest>) -> anyhow::Result<()> {
+        for handler in self.handlers.iter_mut() {
+            handler.on_ipc(context.clone())?;
+        }
+
+        Ok(())
+    }
 }
@@ -1,57 +1,124 @@
 use crate::event::EventContext;
 use crate::handlers::handler::Handler;
+use crate::screen::WorkspaceLayout;
 use crate::screen_manager::Direction;
-use config::keysyms::Keysym;
-use config::AvailableActions;
+use anyhow::Context;
+use config::{keysyms::Keysym, AvailableActions, KeyTrigger, Modifiers};
+use std::io::Write;
+use std::path::PathBuf;
+
+/// how much the master area grows or shrinks with each master action
+const MASTER_RATIO_STEP: f32 = 0.05;
 
 #[derive(Default, Debug)]
 pub struct ActionHandler {}
 
 impl Handler for ActionHandler {
     fn on_key_press(&mut self, context: EventContext<xcb::x::KeyPressEvent>) -> anyhow::Result<()> {
-        let keysym = context
+        self.handle_key(context, KeyTrigger::Press)
+    }
+
+    fn on_key_release(
+        &mut self,
+        context: EventContext<xcb::x::KeyReleaseEvent>,
+    ) -> anyhow::Result<()> {
+        self.handle_key(context, KeyTrigger::Release)
+    }
+}
+
+impl ActionHandler {
+    /// runs the action bound to the key of `context` that fires on `trigger`
+    fn handle_key(
+        &mut self,
+        context: EventContext<xcb::x::KeyPressEvent>,
+        trigger: KeyTrigger,
+    ) -> anyhow::Result<()> {
+        let keysyms = context.keyboard.keysyms(context.event.detail().into());
+        let modifiers = Modifiers::from(context.event.state());
+        let config = context.config.borrow();
+        let action = context
             .keyboard
-            .state
-            .key_get_one_sym(context.event.detail().into());
-
-        if let Ok(keysym) = Keysym::try_from(keysym) {
-            if let Some(action) = context.config.borrow().actions().iter().find(|action| {
-                action.key().eq(&keysym) && context.event.state().eq(&action.modifiers().into())
-            }) {
-                use AvailableActions::*;
-
-                match action.action() {
-                    Quit => std::process::exit(1),
-                    Close => self.handle_close(&context)?,
-                    FocusLeft => self.handle_focus_client(&context, Direction::Left)?,
-                    FocusDown => self.handle_focus_client(&context, Direction::Down)?,
-                    FocusUp => self.handle_focus_client(&context, Direction::Up)?,
-                    FocusRight => self.handle_focus_client(&context, Direction::Right)?,
-                    MoveLeft => self.handle_move_client(&context, Direction::Left)?,
-                    MoveDown => self.handle_move_client(&context, Direction::Down)?,
-                    MoveUp => self.handle_move_client(&context, Direction::Up)?,
-                    MoveRight => self.handle_move_client(&context, Direction::Right)?,
-                    Reload => context.action_tx.send(action.action())?,
-                    Fullscreen => self.handle_fullscreen(&context)?,
-                    Workspace1 => self.handle_change_workspace(&context, action.action())?,
-                    Workspace2 => self.handle_change_workspace(&context, action.action())?,
-                    Workspace3 => self.handle_change_workspace(&context, action.action())?,
-                    Workspace4 => self.handle_change_workspace(&context, action.action())?,
-                    Workspace5 => self.handle_change_workspace(&context, action.action())?,
-                    Workspace6 => self.handle_change_workspace(&context, action.action())?,
-                    Workspace7 => self.handle_change_workspace(&context, action.action())?,
-                    Workspace8 => self.handle_change_workspace(&context, action.action())?,
-                    Workspace9 => self.handle_change_workspace(&context, action.action())?,
-                    MoveToWorkspace1 => self.handle_move_to_workspace(&context, action.action())?,
-                    MoveToWorkspace2 => self.handle_move_to_workspace(&context, action.action())?,
-                    MoveToWorkspace3 => self.handle_move_to_workspace(&context, action.action())?,
-                    MoveToWorkspace4 => self.handle_move_to_workspace(&context, action.action())?,
-                    MoveToWorkspace5 => self.handle_move_to_workspace(&context, action.action())?,
-                    MoveToWorkspace6 => self.handle_move_to_workspace(&context, action.action())?,
-                    MoveToWorkspace7 => self.handle_move_to_workspace(&context, action.action())?,
-                    MoveToWorkspace8 => self.handle_move_to_workspace(&context, action.action())?,
-                    MoveToWorkspace9 => self.handle_move_to_workspace(&context, action.action())?,
+            .actions(&config)
+            .iter()
+            .find(|action| {
+                action.on().eq(&trigger)
+                    && keysyms.contains(&action.key())
+                    && modifiers.eq(&action.modifiers())
+            })
+            .map(|action| action.action())
+            // unless a mode binds it to something else, escape always leaves the mode
+            .or_else(|| {
+                let escape = trigger.eq(&KeyTrigger::Press)
+                    && keysyms.contains(&Keysym::XK_Escape)
+                    && modifiers.eq(&Modifiers::default())
+                    && context.keyboard.mode().is_some();
+                escape.then_some(AvailableActions::ExitMode)
+            });
+        drop(config);
+
+        if let Some(action) = action {
+            use AvailableActions::*;
+
+            match action {
+                Close => self.handle_close(&context)?,
+                FocusLeft => self.handle_focus_client(&context, Direction::Left)?,
+                FocusDown => self.handle_focus_client(&context, Direction::Down)?,
+                FocusUp => self.handle_focus_client(&context, Direction::Up)?,
+                FocusRight => self.handle_focus_client(&context, Direction::Right)?,
+                MoveLeft => self.handle_move_client(&context, Direction::Left)?,
+                MoveDown => self.handle_move_client(&context, Direction::Down)?,
+                MoveUp => self.handle_move_client(&context, Direction::Up)?,
+                MoveRight => self.handle_move_client(&context, Direction::Right)?,
+                ResizeLeft => self.handle_resize_client(&context, Direction::Left)?,
+                ResizeDown => self.handle_resize_client(&context, Direction::Down)?,
+                ResizeUp => self.handle_resize_client(&context, Direction::Up)?,
+                ResizeRight => self.handle_resize_client(&context, Direction::Right)?,
+                Quit | Reload | Restart => context.action_tx.send(action)?,
+                ToggleFullscreen => self.handle_toggle_fullscreen(&context)?,
+                Workspace1 => self.handle_change_workspace(&context, 0)?,
+                Workspace2 => self.handle_change_workspace(&context, 1)?,
+                Workspace3 => self.handle_change_workspace(&context, 2)?,
+                Workspace4 => self.handle_change_workspace(&context, 3)?,
+                Workspace5 => self.handle_change_workspace(&context, 4)?,
+                Workspace6 => self.handle_change_workspace(&context, 5)?,
+                Workspace7 => self.handle_change_workspace(&context, 6)?,
+                Workspace8 => self.handle_change_workspace(&context, 7)?,
+                Workspace9 => self.handle_change_workspace(&context, 8)?,
+                MoveToWorkspace1 => self.handle_move_to_workspace(&context, 0)?,
+                MoveToWorkspace2 => self.handle_move_to_workspace(&context, 1)?,
+                MoveToWorkspace3 => self.handle_move_to_workspace(&context, 2)?,
+                MoveToWorkspace4 => self.handle_move_to_workspace(&context, 3)?,
+                MoveToWorkspace5 => self.handle_move_to_workspace(&context, 4)?,
+                MoveToWorkspace6 => self.handle_move_to_workspace(&context, 5)?,
+                MoveToWorkspace7 => self.handle_move_to_workspace(&context, 6)?,
+                MoveToWorkspace8 => self.handle_move_to_workspace(&context, 7)?,
+                MoveToWorkspace9 => self.handle_move_to_workspace(&context, 8)?,
+                ToggleBorder => self.handle_toggle_border(&context)?,
+                CarouselWindow => self.handle_carousel_window(&context)?,
+                ToggleFloating => self.handle_toggle_floating(&context)?,
+                FocusBusiest => self.handle_focus_busiest(&context)?,
+                IncreaseMaster => self.handle_adjust_master_ratio(&context, MASTER_RATIO_STEP)?,
+                DecreaseMaster => self.handle_adjust_master_ratio(&context, -MASTER_RATIO_STEP)?,
+                IncreaseMasterCount => self.handle_adjust_master_count(&context, 1)?,
+                DecreaseMasterCount => self.handle_adjust_master_count(&context, -1)?,
+                DistributeEven => self.handle_distribute_even(&context)?,
+                CycleLayout => self.handle_set_layout(&context, None)?,
+                SetLayout(layout) => self.handle_set_layout(&context, Some(layout.into()))?,
+                ToggleScratchpad => self.handle_toggle_scratchpad(&context)?,
+                SwapWithMaster => self.handle_swap_with_master(&context)?,
+                FocusMaster => self.handle_focus_master(&context)?,
+                MoveWorkspaceToMonitor(direction) => {
+                    self.handle_move_workspace_to_monitor(&context, direction.into())?
                 }
+                CycleFocusNext => self.handle_cycle_focus(&context, true)?,
+                CycleFocusPrev => self.handle_cycle_focus(&context, false)?,
+                FocusLastWindow => self.handle_focus_last_window(&context)?,
+                ShowKeybinds => self.handle_show_keybinds(&context)?,
+                ToggleSticky => self.handle_toggle_sticky(&context)?,
+                ToggleStackDirection => self.handle_toggle_stack_direction(&context)?,
+                FocusMonitor(direction) => self.handle_focus_monitor(&context, direction.into())?,
+                EnterMode(mode) => self.handle_set_mode(&context, Some(mode)),
+                ExitMode => self.handle_set_mode(&context, None),
             }
         }
 
@@ -62,42 +129,29 @@ impl Handler for ActionHandler {
 
         Ok(())
     }
-}
 
-impl ActionHandler {
     fn handle_close(&self, context: &EventContext<xcb::x::KeyPressEvent>) -> anyhow::Result<()> {
-        let mut screen_manager = context.screen_manager.borrow_mut();
-        if let Some(client) = screen_manager.close_focused_client()? {
-            drop(screen_manager);
-            match context.layout_manager.close_client(&client, context.atoms) {
-                Ok(_) => {
-                    tracing::debug!(
-                        "focus left handled correctly for window {:?}",
-                        context.event.event()
-                    );
-                }
-                Err(e) => return Err(e),
-            };
-
-            match context
-                .layout_manager
-                .display_screens(&context.screen_manager, context.decorator)
-            {
-                Ok(_) => {
-                    tracing::debug!("displayed all clients successfully");
-                    return Ok(());
-                }
-                Err(e) => {
-                    tracing::error!(
-                        "failed to display the available windows: {:?}",
-                        context.event.event()
-                    );
-                    return Err(e);
-                }
+        let Some(client) = context
+            .screen_manager
+            .borrow()
+            .get_focused_client()
+            .cloned()
+        else {
+            return Ok(());
+        };
+
+        // the client is only removed from our state once it unmaps its window, so clients that
+        // prompt before closing, or refuse to close, keep being managed
+        match context.layout_manager.close_client(&client, context.atoms) {
+            Ok(_) => {
+                tracing::debug!("requested client {:?} to close", client.window);
+                Ok(())
+            }
+            Err(e) => {
+                tracing::error!("failed to close client {:?}", client.window);
+                Err(e)
             }
         }
-
-        Ok(())
     }
 
     fn handle_focus_client(
@@ -146,9 +200,9 @@ impl ActionHandler {
     fn handle_change_workspace(
         &self,
         context: &EventContext<xcb::x::KeyPressEvent>,
-        action: AvailableActions,
+        workspace: usize,
     ) -> anyhow::Result<()> {
-        match context.layout_manager.change_workspace(context, action) {
+        match context.layout_manager.change_workspace(context, workspace) {
             Ok(_) => Ok(()),
             Err(e) => {
                 tracing::error!(
@@ -163,9 +217,9 @@ impl ActionHandler {
     fn handle_move_to_workspace(
         &self,
         context: &EventContext<xcb::x::KeyPressEvent>,
-        action: AvailableActions,
+        workspace: usize,
     ) -> anyhow::Result<()> {
-        match context.layout_manager.move_to_workspace(context, action) {
+        match context.layout_manager.move_to_workspace(context, workspace) {
             Ok(_) => Ok(()),
             Err(e) => {
                 tracing::error!(
@@ -177,10 +231,405 @@ impl ActionHandler {
         }
     }
 
-    fn handle_fullscreen(
+    fn handle_set_layout(
         &self,
-        _context: &EventContext<xcb::x::KeyPressEvent>,
+        context: &EventContext<xcb::x::KeyPressEvent>,
+        layout: Option<WorkspaceLayout>,
     ) -> anyhow::Result<()> {
-        Ok(())
+        match context.layout_manager.set_layout(context, layout) {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                tracing::error!(
+                    "error while changing the workspace layout {:?} ",
+                    context.event.event()
+                );
+                Err(e)
+            }
+        }
+    }
+
+    fn handle_adjust_master_ratio(
+        &self,
+        context: &EventContext<xcb::x::KeyPressEvent>,
+        step: f32,
+    ) -> anyhow::Result<()> {
+        match context.layout_manager.adjust_master_ratio(context, step) {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                tracing::error!(
+                    "error while resizing the master area {:?} ",
+                    context.event.event()
+                );
+                Err(e)
+            }
+        }
+    }
+
+    fn handle_adjust_master_count(
+        &self,
+        context: &EventContext<xcb::x::KeyPressEvent>,
+        delta: isize,
+    ) -> anyhow::Result<()> {
+        match context.layout_manager.adjust_master_count(context, delta) {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                tracing::error!(
+                    "error while changing the master count {:?} ",
+                    context.event.event()
+                );
+                Err(e)
+            }
+        }
     }
+
+    fn handle_distribute_even(
+        &self,
+        context: &EventContext<xcb::x::KeyPressEvent>,
+    ) -> anyhow::Result<()> {
+        match context.layout_manager.distribute_even(context) {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                tracing::error!(
+                    "error while distributing the clients {:?} ",
+                    context.event.event()
+                );
+                Err(e)
+            }
+        }
+    }
+
+    fn handle_focus_busiest(
+        &self,
+        context: &EventContext<xcb::x::KeyPressEvent>,
+    ) -> anyhow::Result<()> {
+        match context.layout_manager.focus_busiest_workspace(context) {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                tracing::error!(
+                    "error while changing to the busiest workspace {:?} ",
+                    context.event.event()
+                );
+                Err(e)
+            }
+        }
+    }
+
+    fn handle_carousel_window(
+        &self,
+        context: &EventContext<xcb::x::KeyPressEvent>,
+    ) -> anyhow::Result<()> {
+        match context.layout_manager.carousel_client(context) {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                tracing::error!(
+                    "error while carrying client to the next workspace {:?} ",
+                    context.event.event()
+                );
+                Err(e)
+            }
+        }
+    }
+
+    fn handle_toggle_border(
+        &self,
+        context: &EventContext<xcb::x::KeyPressEvent>,
+    ) -> anyhow::Result<()> {
+        if !context
+            .screen_manager
+            .borrow_mut()
+            .toggle_focused_client_border()
+        {
+            return Ok(());
+        }
+
+        context
+            .layout_manager
+            .display_screens(&context.screen_manager, context.decorator)
+    }
+
+    fn handle_toggle_floating(
+        &self,
+        context: &EventContext<xcb::x::KeyPressEvent>,
+    ) -> anyhow::Result<()> {
+        match context.layout_manager.toggle_floating(context) {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                tracing::error!(
+                    "error while toggling floating for client {:?} ",
+                    context.event.event()
+                );
+                Err(e)
+            }
+        }
+    }
+
+    fn handle_toggle_scratchpad(
+        &self,
+        context: &EventContext<xcb::x::KeyPressEvent>,
+    ) -> anyhow::Result<()> {
+        match context.layout_manager.toggle_scratchpad(context) {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                tracing::error!(
+                    "error while toggling the scratchpad {:?} ",
+                    context.event.event()
+                );
+                Err(e)
+            }
+        }
+    }
+
+    fn handle_toggle_fullscreen(
+        &self,
+        context: &EventContext<xcb::x::KeyPressEvent>,
+    ) -> anyhow::Result<()> {
+        let Some(client) = context
+            .screen_manager
+            .borrow()
+            .get_focused_client()
+            .cloned()
+        else {
+            return Ok(());
+        };
+
+        match context
+            .layout_manager
+            .set_fullscreen(context, client.frame, !client.fullscreen)
+        {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                tracing::error!(
+                    "error while toggling fullscreen {:?} ",
+                    context.event.event()
+                );
+                Err(e)
+            }
+        }
+    }
+
+    fn handle_swap_with_master(
+        &self,
+        context: &EventContext<xcb::x::KeyPressEvent>,
+    ) -> anyhow::Result<()> {
+        match context.layout_manager.swap_with_master(context) {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                tracing::error!(
+                    "error while swapping with master {:?} ",
+                    context.event.event()
+                );
+                Err(e)
+            }
+        }
+    }
+
+    fn handle_focus_master(
+        &self,
+        context: &EventContext<xcb::x::KeyPressEvent>,
+    ) -> anyhow::Result<()> {
+        match context.layout_manager.focus_master(context) {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                tracing::error!(
+                    "error while focusing the master client {:?} ",
+                    context.event.event()
+                );
+                Err(e)
+            }
+        }
+    }
+
+    fn handle_move_workspace_to_monitor(
+        &self,
+        context: &EventContext<xcb::x::KeyPressEvent>,
+        direction: Direction,
+    ) -> anyhow::Result<()> {
+        match context
+            .layout_manager
+            .move_workspace_to_monitor(context, direction)
+        {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                tracing::error!(
+                    "error while moving workspace to monitor {:?} ",
+                    context.event.event()
+                );
+                Err(e)
+            }
+        }
+    }
+
+    fn handle_cycle_focus(
+        &self,
+        context: &EventContext<xcb::x::KeyPressEvent>,
+        forward: bool,
+    ) -> anyhow::Result<()> {
+        match context.layout_manager.cycle_focus(context, forward) {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                tracing::error!("error while cycling focus {:?} ", context.event.event());
+                Err(e)
+            }
+        }
+    }
+
+    fn handle_focus_last_window(
+        &self,
+        context: &EventContext<xcb::x::KeyPressEvent>,
+    ) -> anyhow::Result<()> {
+        match context.layout_manager.focus_last_window(context) {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                tracing::error!(
+                    "error while focusing the last window {:?} ",
+                    context.event.event()
+                );
+                Err(e)
+            }
+        }
+    }
+
+    /// the bindings are written to a file so any viewer can open them, a viewer that fails to
+    /// start leaves the file behind and logs the failure
+    fn handle_show_keybinds(
+        &self,
+        context: &EventContext<xcb::x::KeyPressEvent>,
+    ) -> anyhow::Result<()> {
+        let config = context.config.borrow();
+        let keybinds = config.keybinds();
+        let Some(viewer) = config.keybinds_viewer() else {
+            tracing::info!("key bindings:\n{keybinds}");
+            return Ok(());
+        };
+
+        let path = write_keybinds(&keybinds)?;
+
+        let mut parts = viewer.split_whitespace();
+        let Some(program) = parts.next() else {
+            return Ok(());
+        };
+        match std::process::Command::new(program)
+            .args(parts)
+            .arg(&path)
+            .spawn()
+        {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                tracing::error!("failed to spawn the key bindings viewer {viewer:?}");
+                Err(e.into())
+            }
+        }
+    }
+
+    fn handle_toggle_sticky(
+        &self,
+        context: &EventContext<xcb::x::KeyPressEvent>,
+    ) -> anyhow::Result<()> {
+        let Some(client) = context
+            .screen_manager
+            .borrow()
+            .get_focused_client()
+            .cloned()
+        else {
+            return Ok(());
+        };
+
+        match context
+            .layout_manager
+            .set_sticky(context, client.frame, !client.sticky)
+        {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                tracing::error!("error while toggling sticky {:?} ", context.event.event());
+                Err(e)
+            }
+        }
+    }
+
+    fn handle_toggle_stack_direction(
+        &self,
+        context: &EventContext<xcb::x::KeyPressEvent>,
+    ) -> anyhow::Result<()> {
+        match context.layout_manager.toggle_stack_direction(context) {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                tracing::error!(
+                    "error while toggling the stack direction {:?} ",
+                    context.event.event()
+                );
+                Err(e)
+            }
+        }
+    }
+
+    fn handle_focus_monitor(
+        &self,
+        context: &EventContext<xcb::x::KeyPressEvent>,
+        direction: Direction,
+    ) -> anyhow::Result<()> {
+        match context.layout_manager.focus_monitor(context, direction) {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                tracing::error!(
+                    "error while focusing monitor from {:?}",
+                    context.event.event()
+                );
+                Err(e)
+            }
+        }
+    }
+
+    fn handle_resize_client(
+        &self,
+        context: &EventContext<xcb::x::KeyPressEvent>,
+        direction: Direction,
+    ) -> anyhow::Result<()> {
+        match context.layout_manager.resize_client(context, direction) {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                tracing::error!(
+                    "error while resizing client {:?} towards {direction:?}",
+                    context.event.event()
+                );
+                Err(e)
+            }
+        }
+    }
+
+    fn handle_set_mode(&self, context: &EventContext<xcb::x::KeyPressEvent>, mode: Option<String>) {
+        tracing::debug!("switching to mode {mode:?}");
+        context.keyboard.set_mode(
+            &context.conn,
+            &context.config.borrow(),
+            context.screen_manager.borrow().root(),
+            mode,
+        );
+    }
+}
+
+/// writes the key bindings for the viewer to open. The runtime directory is private to the user,
+/// without it a file of our own is created in the shared temp directory, as an existing file or
+/// symlink there could belong to anyone
+fn write_keybinds(keybinds: &str) -> anyhow::Result<PathBuf> {
+    if let Some(dir) = std::env::var_os("XDG_RUNTIME_DIR") {
+        let path = PathBuf::from(dir).join("lucky-keybinds.txt");
+        std::fs::write(&path, keybinds)
+            .with_context(|| format!("failed to write the key bindings to {path:?}"))?;
+        return Ok(path);
+    }
+
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.subsec_nanos())
+        .unwrap_or_default();
+    let path =
+        std::env::temp_dir().join(format!("lucky-keybinds-{}-{nanos}.txt", std::process::id()));
+    std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(keybinds.as_bytes()))
+        .with_context(|| format!("failed to write the key bindings to {path:?}"))?;
+
+    Ok(path)
 }
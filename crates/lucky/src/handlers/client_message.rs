@@ -0,0 +1,86 @@
+use crate::event::EventContext;
+use crate::handlers::handler::Handler;
+use xcb::Xid;
+
+/// actions a client can request when changing its `_NET_WM_STATE`
+const NET_WM_STATE_REMOVE: u32 = 0;
+const NET_WM_STATE_ADD: u32 = 1;
+const NET_WM_STATE_TOGGLE: u32 = 2;
+
+#[derive(Default, Debug)]
+pub struct ClientMessageHandler {}
+
+impl Handler for ClientMessageHandler {
+    fn on_client_message(
+        &mut self,
+        context: EventContext<xcb::x::ClientMessageEvent>,
+    ) -> anyhow::Result<()> {
+        if context.event.r#type().eq(&context.atoms.net_wm_state) {
+            self.handle_wm_state(&context)?;
+        }
+
+        context
+            .screen_manager
+            .borrow_mut()
+            .update_atoms(context.atoms, &context.conn);
+
+        Ok(())
+    }
+}
+
+impl ClientMessageHandler {
+    /// clients ask to change up to two states at once, we only act on fullscreen, sticky and
+    /// demanding attention
+    fn handle_wm_state(
+        &self,
+        context: &EventContext<xcb::x::ClientMessageEvent>,
+    ) -> anyhow::Result<()> {
+        let xcb::x::ClientMessageData::Data32([action, first, second, ..]) = context.event.data()
+        else {
+            return Ok(());
+        };
+
+        let Some(client) = context
+            .screen_manager
+            .borrow()
+            .find_client(context.event.window())
+            .cloned()
+        else {
+            return Ok(());
+        };
+
+        let apply = |current: bool| match action {
+            NET_WM_STATE_REMOVE => Some(false),
+            NET_WM_STATE_ADD => Some(true),
+            NET_WM_STATE_TOGGLE => Some(!current),
+            _ => None,
+        };
+        let requested = |atom: xcb::x::Atom| [first, second].contains(&atom.resource_id());
+
+        if requested(context.atoms.net_wm_state_fullscreen) {
+            if let Some(fullscreen) = apply(client.fullscreen) {
+                context
+                    .layout_manager
+                    .set_fullscreen(context, client.frame, fullscreen)?;
+            }
+        }
+
+        if requested(context.atoms.net_wm_state_sticky) {
+            if let Some(sticky) = apply(client.sticky) {
+                context
+                    .layout_manager
+                    .set_sticky(context, client.frame, sticky)?;
+            }
+        }
+
+        if requested(context.atoms.net_wm_state_demands_attention) {
+            if let Some(urgent) = apply(client.urgent) {
+                context
+                    .layout_manager
+                    .set_urgent(context, client.frame, urgent)?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -1,33 +1,51 @@
 use crate::event::EventContext;
 use crate::handlers::handler::Handler;
-use config::keysyms::Keysym;
+use config::{KeyTrigger, Modifiers};
 
 #[derive(Default, Debug)]
 pub struct CommandHandler {}
 
 impl Handler for CommandHandler {
     fn on_key_press(&mut self, context: EventContext<xcb::x::KeyPressEvent>) -> anyhow::Result<()> {
-        let keysym = context
-            .keyboard
-            .state
-            .key_get_one_sym(context.event.detail().into());
-        if let Ok(keysym) = Keysym::try_from(keysym) {
-            if let Some(command) = context
-                .config
-                .borrow()
-                .commands()
-                .iter()
-                .find(|command| command.key().eq(&keysym))
+        self.handle_key(context, KeyTrigger::Press)
+    }
+
+    fn on_key_release(
+        &mut self,
+        context: EventContext<xcb::x::KeyReleaseEvent>,
+    ) -> anyhow::Result<()> {
+        self.handle_key(context, KeyTrigger::Release)
+    }
+}
+
+impl CommandHandler {
+    /// spawns the command bound to the key of `context` that fires on `trigger`
+    fn handle_key(
+        &mut self,
+        context: EventContext<xcb::x::KeyPressEvent>,
+        trigger: KeyTrigger,
+    ) -> anyhow::Result<()> {
+        let keysyms = context.keyboard.keysyms(context.event.detail().into());
+        let modifiers = Modifiers::from(context.event.state());
+
+        // commands are not bound while a mode is active
+        if context.keyboard.mode().is_some() {
+            return Ok(());
+        }
+
+        if let Some(command) = context.config.borrow().commands().iter().find(|command| {
+            command.on().eq(&trigger)
+                && keysyms.contains(&command.key())
+                && modifiers.eq(&command.modifiers())
+        }) {
+            match std::process::Command::new(command.command())
+                .args(command.args())
+                .spawn()
             {
-                match std::process::Command::new(command.command())
-                    .args(command.args())
-                    .spawn()
-                {
-                    Ok(_) => tracing::debug!("spawning command {:?} handled successfully", command),
-                    Err(_) => {
-                        tracing::error!("failed to spawn command {:?}", command);
-                        anyhow::bail!("failed to spawn command {:?}", command);
-                    }
+                Ok(_) => tracing::debug!("spawning command {:?} handled successfully", command),
+                Err(_) => {
+                    tracing::error!("failed to spawn command {:?}", command);
+                    anyhow::bail!("failed to spawn command {:?}", command);
                 }
             }
         }
@@ -1,4 +1,5 @@
 use crate::event::EventContext;
+use crate::ipc::IpcRequest;
 
 pub trait Handler: std::fmt::Debug {
     fn on_key_press(
@@ -8,6 +9,13 @@ pub trait Handler: std::fmt::Debug {
         Ok(())
     }
 
+    fn on_key_release(
+        &mut self,
+        _context: EventContext<xcb::x::KeyReleaseEvent>,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+
     fn on_map_request(
         &mut self,
         _context: EventContext<xcb::x::MapRequestEvent>,
@@ -42,4 +50,15 @@ pub trait Handler: std::fmt::Debug {
     ) -> anyhow::Result<()> {
         Ok(())
     }
+
+    fn on_client_message(
+        &mut self,
+        _context: EventContext<xcb::x::ClientMessageEvent>,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn on_ipc(&mut self, _context: EventContext<IpcRequest>) -> anyhow::Result<()> {
+        Ok(())
+    }
 }
@@ -9,9 +9,32 @@ impl Handler for HoverHandler {
         &mut self,
         context: EventContext<xcb::x::EnterNotifyEvent>,
     ) -> anyhow::Result<()> {
-        if context.config.borrow().focus_follow_mouse() {
+        // warping the pointer to the focused client makes it enter whatever is under it, which
+        // is not the user moving the mouse and must not steal the focus
+        let pointer = (context.event.root_x(), context.event.root_y());
+        let warped = context.layout_manager.is_warp_target(pointer);
+
+        // the pointer may have moved onto another monitor, in which case it becomes the active
+        // one, even when there is no window under the pointer
+        context.screen_manager.borrow_mut().maybe_switch_screen(
+            (pointer.0.into(), pointer.1.into()),
+            &context.conn,
+            context.atoms,
+        );
+        let on_root = context
+            .event
+            .event()
+            .eq(&context.screen_manager.borrow().root());
+
+        if context.config.borrow().focus_follow_mouse() && !warped && !on_root {
             let window = context.event.event();
             context.screen_manager.borrow_mut().focus_client(window);
+            let focused_frame = context
+                .screen_manager
+                .borrow()
+                .get_focused_client()
+                .map(|client| client.frame);
+            context.layout_manager.set_pointer_client(focused_frame);
             context
                 .layout_manager
                 .display_screens(&context.screen_manager, context.decorator)?;
@@ -0,0 +1,163 @@
+use crate::event::EventContext;
+use crate::handlers::handler::Handler;
+use crate::ipc::{IpcCommand, IpcQuery, IpcReply, IpcRequest};
+use config::AvailableActions;
+use serde_json::json;
+use xcb::Xid;
+
+#[derive(Default, Debug)]
+pub struct IpcHandler {}
+
+impl Handler for IpcHandler {
+    fn on_ipc(&mut self, context: EventContext<IpcRequest>) -> anyhow::Result<()> {
+        // errors are reported back to the client instead of being propagated, a bad command
+        // should never bring the window manager down
+        let reply = match self.handle_command(&context) {
+            Ok(data) => IpcReply::success(data),
+            Err(e) => {
+                tracing::error!(
+                    "error while handling ipc command {:?}",
+                    context.event.command
+                );
+                IpcReply::failure(e)
+            }
+        };
+
+        context.event.reply_tx.send(reply).ok();
+
+        context
+            .screen_manager
+            .borrow_mut()
+            .update_atoms(context.atoms, &context.conn);
+
+        Ok(())
+    }
+}
+
+impl IpcHandler {
+    fn handle_command(
+        &self,
+        context: &EventContext<IpcRequest>,
+    ) -> anyhow::Result<Option<serde_json::Value>> {
+        match context.event.command {
+            IpcCommand::Focus { dir } => context.layout_manager.change_focus(context, dir)?,
+            IpcCommand::Move { dir } => context.layout_manager.move_client(context, dir)?,
+            IpcCommand::Workspace { index } => {
+                let workspace = self.workspace_index(context, index)?;
+                context
+                    .layout_manager
+                    .change_workspace(context, workspace)?
+            }
+            IpcCommand::MoveToWorkspace { index } => {
+                let workspace = self.workspace_index(context, index)?;
+                context
+                    .layout_manager
+                    .move_to_workspace(context, workspace)?
+            }
+            IpcCommand::Close => {
+                let client = context
+                    .screen_manager
+                    .borrow()
+                    .get_focused_client()
+                    .cloned();
+                if let Some(client) = client {
+                    context
+                        .layout_manager
+                        .close_client(&client, context.atoms)?;
+                }
+            }
+            IpcCommand::Reload => context.action_tx.send(AvailableActions::Reload)?,
+            IpcCommand::Set { ref key, ref value } => {
+                context.config.borrow_mut().set(key, value)?;
+                context
+                    .decorator
+                    .refresh_borders(context.screen_manager.borrow().clients().values());
+                context
+                    .layout_manager
+                    .display_screens(&context.screen_manager, context.decorator)?;
+            }
+            IpcCommand::Subscribe => context
+                .screen_manager
+                .borrow_mut()
+                .add_subscriber(context.event.connection.clone()),
+            IpcCommand::Query { what } => return Ok(Some(self.query(context, what))),
+        };
+
+        Ok(None)
+    }
+
+    /// workspaces are 1-indexed over ipc, so they match the workspace actions and names
+    fn workspace_index(
+        &self,
+        context: &EventContext<IpcRequest>,
+        index: usize,
+    ) -> anyhow::Result<usize> {
+        let workspaces = context.config.borrow().workspace_limit() as usize;
+        if !(1..=workspaces).contains(&index) {
+            anyhow::bail!(
+                "workspace {index} does not exist, there are at most {workspaces} workspaces"
+            );
+        }
+        Ok(index - 1)
+    }
+
+    fn query(&self, context: &EventContext<IpcRequest>, what: IpcQuery) -> serde_json::Value {
+        let screen_manager = context.screen_manager.borrow();
+        let focused_client = screen_manager
+            .get_focused_client()
+            .map(|client| client.frame);
+        let active_screen_idx = screen_manager.active_screen_idx();
+        let clients = screen_manager.clients();
+
+        match what {
+            IpcQuery::Clients => screen_manager
+                .screens()
+                .iter()
+                .enumerate()
+                .flat_map(|(screen_idx, screen)| {
+                    screen.workspaces().iter().flat_map(move |workspace| {
+                        workspace
+                            .clients()
+                            .iter()
+                            .map(move |frame| (screen_idx, workspace, frame))
+                    })
+                })
+                .filter_map(|(screen_idx, workspace, frame)| {
+                    let client = clients.get(frame)?;
+                    Some(json!({
+                        "window": client.window.resource_id(),
+                        "frame": client.frame.resource_id(),
+                        "screen": screen_idx,
+                        "workspace": workspace.id() as usize + 1,
+                        "focused": focused_client == Some(client.frame),
+                        "floating": client.floating.is_some(),
+                        "urgent": client.urgent,
+                    }))
+                })
+                .collect(),
+            IpcQuery::Workspaces => screen_manager
+                .screens()
+                .iter()
+                .enumerate()
+                .flat_map(|(screen_idx, screen)| {
+                    screen.workspaces().iter().map(move |workspace| {
+                        json!({
+                            "index": workspace.id() as usize + 1,
+                            "name": workspace.name(),
+                            "screen": screen_idx,
+                            "active": screen.active_workspace().id() == workspace.id(),
+                            "focused": screen_idx == active_screen_idx
+                                && screen.active_workspace().id() == workspace.id(),
+                            "layout": format!("{:?}", workspace.layout()).to_lowercase(),
+                            "clients": workspace.clients().len(),
+                            "urgent": workspace
+                                .clients()
+                                .iter()
+                                .any(|frame| clients.get(frame).is_some_and(|client| client.urgent)),
+                        })
+                    })
+                })
+                .collect(),
+        }
+    }
+}
@@ -1,81 +1,175 @@
 use crate::event::EventContext;
-use crate::ewmh::{ewmh_set_active_window, ewmh_set_focus, EwmhFocusAction};
+use crate::ewmh::{
+    ewmh_get_pid, ewmh_is_dock, ewmh_set_active_window, ewmh_set_focus, motif_wants_decorations,
+    EwmhFocusAction, SizeHints, WmClass,
+};
 use crate::handlers::handler::Handler;
 use crate::position::Position;
 use crate::screen::ReservedClient;
+use crate::xcb_utils::{xcb_get_prop, xcb_map_win, xcb_unmap_win};
 use anyhow::Context;
+use config::{FloatPlacement, Rule};
+use xcb::Xid;
 
 #[derive(Default, Debug)]
 pub struct MapWindowHandler {}
 
+/// space a dock asks to reserve on each edge of the root window, as read from
+/// `_NET_WM_STRUT_PARTIAL` or the legacy `_NET_WM_STRUT`
+struct Strut {
+    left: u32,
+    right: u32,
+    top: u32,
+    bottom: u32,
+}
+
 impl MapWindowHandler {
+    /// reads the strut of a window, preferring `_NET_WM_STRUT_PARTIAL` and falling back to the
+    /// legacy `_NET_WM_STRUT`, only the first four values matter to us, as the partial ranges
+    /// are implied by the screen the dock is placed on
+    fn get_strut(&self, context: &EventContext<xcb::x::MapRequestEvent>) -> Option<Strut> {
+        let window = context.event.window();
+
+        [
+            (context.atoms.net_wm_strut_partial, 12),
+            (context.atoms.net_wm_strut, 4),
+        ]
+        .into_iter()
+        .find_map(|(atom, len)| {
+            let reply =
+                xcb_get_prop!(context.conn, window, atom, len, xcb::x::ATOM_CARDINAL).ok()?;
+            // this is the order that the values come for some reason, dont
+            // ask me, i didn't make this decision, its on the spec.
+            match reply.value::<u32>() {
+                [left, right, top, bottom, ..] => Some(Strut {
+                    left: *left,
+                    right: *right,
+                    top: *top,
+                    bottom: *bottom,
+                }),
+                _ => None,
+            }
+        })
+    }
+
+    /// guesses the strut of a dock that sets none from the edge of its screen it is flush
+    /// against, bars along the top or bottom edges reserve their height and side panels their
+    /// width. Only used with `dock_auto_reserve`
+    fn infer_dock_strut(&self, context: &EventContext<xcb::x::MapRequestEvent>) -> Option<Strut> {
+        let window = context.event.window();
+        if !context.config.borrow().dock_auto_reserve()
+            || !ewmh_is_dock(&context.conn, context.atoms, window)
+        {
+            return None;
+        }
+
+        let geometry = context
+            .conn
+            .wait_for_reply(context.conn.send_request(&xcb::x::GetGeometry {
+                drawable: xcb::x::Drawable::Window(window),
+            }))
+            .ok()?;
+        let position = Position::new(
+            geometry.x().into(),
+            geometry.y().into(),
+            geometry.width().into(),
+            geometry.height().into(),
+        );
+
+        let screen_manager = context.screen_manager.borrow();
+        let screen_idx = screen_manager.screen_at(
+            position.x + position.width as i32 / 2,
+            position.y + position.height as i32 / 2,
+        )?;
+        let area = screen_manager.screen(screen_idx).position();
+
+        let root = context.conn.get_setup().roots().next()?;
+        let root_width = root.width_in_pixels() as i32;
+        let root_height = root.height_in_pixels() as i32;
+
+        // struts are measured from the edges of the root window
+        let mut strut = Strut {
+            left: 0,
+            right: 0,
+            top: 0,
+            bottom: 0,
+        };
+        if position.width >= position.height {
+            if position.top().eq(&area.top()) {
+                strut.top = position.bottom() as u32;
+            } else if position.bottom().eq(&area.bottom()) {
+                strut.bottom = (root_height - position.top()) as u32;
+            } else {
+                return None;
+            }
+        } else if position.left().eq(&area.left()) {
+            strut.left = position.right() as u32;
+        } else if position.right().eq(&area.right()) {
+            strut.right = (root_width - position.left()) as u32;
+        } else {
+            return None;
+        }
+
+        Some(strut)
+    }
+
+    /// struts are relative to the edges of the root window, so we translate them into the area
+    /// that has to be reserved on the screen the dock is on, which matters when there are
+    /// multiple monitors
     fn setup_reserved_client(
         &self,
-        values: &[u32],
+        strut: Strut,
         context: &EventContext<xcb::x::MapRequestEvent>,
-    ) {
-        // this is the order that the values come for some reason, dont
-        // ask me, i didn't make this decision, its on the spec.
-        let left = values[0];
-        let right = values[1];
-        let top = values[2];
-        let bottom = values[3];
-        let left_start_y = values[4];
-        let left_end_y = values[5];
-        let right_start_y = values[6];
-        let right_end_y = values[7];
-        let top_start_x = values[8];
-        let top_end_x = values[9];
-        let bottom_start_x = values[10];
-        let bottom_end_x = values[11];
+    ) -> anyhow::Result<()> {
+        let window = context.event.window();
+        let geometry = context
+            .conn
+            .wait_for_reply(context.conn.send_request(&xcb::x::GetGeometry {
+                drawable: xcb::x::Drawable::Window(window),
+            }))
+            .context("failed to get the geometry of the reserved client")?;
+        let position = Position::new(
+            geometry.x().into(),
+            geometry.y().into(),
+            geometry.width().into(),
+            geometry.height().into(),
+        );
+
+        let root = context
+            .conn
+            .get_setup()
+            .roots()
+            .next()
+            .expect("should have at least one screen to manage");
+        let root_width = root.width_in_pixels() as i32;
+        let root_height = root.height_in_pixels() as i32;
 
         let mut screen_manager = context.screen_manager.borrow_mut();
-        let screen_idx = screen_manager.active_screen_idx();
+        let (center_x, center_y) = (
+            position.x + position.width as i32 / 2,
+            position.y + position.height as i32 / 2,
+        );
+        let screen_idx = screen_manager
+            .screen_at(center_x, center_y)
+            .unwrap_or(screen_manager.active_screen_idx());
         let screen = screen_manager.screen_mut(screen_idx);
-        let position = screen.position().clone();
-
-        let position = match (left, bottom, top, right) {
-            (_, _, _, _) if left > 0 => {
-                screen.add_left_reserved_area(left);
-                Position {
-                    x: 0,
-                    y: left_start_y as i32,
-                    width: left,
-                    height: left_end_y - left_start_y,
-                }
-            }
-            (_, _, _, _) if bottom > 0 => {
-                screen.add_bottom_reserved_area(bottom);
-                Position {
-                    x: bottom_start_x as i32,
-                    y: position.bottom() - bottom as i32,
-                    width: bottom_end_x - bottom_start_x,
-                    height: bottom,
-                }
-            }
-            (_, _, _, _) if top > 0 => {
-                screen.add_top_reserved_area(top);
-                Position {
-                    x: top_start_x as i32,
-                    y: 0,
-                    width: top_end_x - top_start_x,
-                    height: top,
-                }
-            }
-            (_, _, _, _) if right > 0 => {
-                screen.add_right_reserved_area(top);
-                Position {
-                    x: position.right() - right as i32,
-                    y: right_start_y as i32,
-                    width: right,
-                    height: right_end_y - right_start_y,
-                }
-            }
-            _ => unreachable!(),
+        let area = screen.position().clone();
+
+        let reserved = |strut: u32, distance_to_root_edge: i32, size: u32| {
+            (strut as i32 - distance_to_root_edge).clamp(0, size as i32) as u32
         };
+        let left = reserved(strut.left, area.left(), area.width);
+        let right = reserved(strut.right, root_width - area.right(), area.width);
+        let top = reserved(strut.top, area.top(), area.height);
+        let bottom = reserved(strut.bottom, root_height - area.bottom(), area.height);
+
+        screen.add_left_reserved_area(left);
+        screen.add_right_reserved_area(right);
+        screen.add_top_reserved_area(top);
+        screen.add_bottom_reserved_area(bottom);
 
         let reserved_client = ReservedClient {
-            window: context.event.window(),
+            window,
             show_on_all_workspaces: true,
             workspace: 0,
             position,
@@ -86,6 +180,221 @@ impl MapWindowHandler {
         };
 
         screen.add_reserved_client(reserved_client);
+
+        Ok(())
+    }
+
+    /// whether the window should become the scratchpad, only one scratchpad is kept at a time
+    fn is_scratchpad(
+        &self,
+        wm_class: Option<&WmClass>,
+        context: &EventContext<xcb::x::MapRequestEvent>,
+    ) -> bool {
+        context.screen_manager.borrow().scratchpad().is_none()
+            && context
+                .config
+                .borrow()
+                .scratchpad_class()
+                .zip(wm_class)
+                .is_some_and(|(class, wm_class)| wm_class.matches(class))
+    }
+
+    /// the first configured rule matching the class of the window
+    fn find_rule(
+        &self,
+        wm_class: Option<&WmClass>,
+        context: &EventContext<xcb::x::MapRequestEvent>,
+    ) -> Option<Rule> {
+        let wm_class = wm_class?;
+        context
+            .config
+            .borrow()
+            .rules()
+            .iter()
+            .find(|rule| wm_class.matches(rule.class()))
+            .cloned()
+    }
+
+    /// floating windows keep the size they asked for, and are placed according to
+    /// `float_placement`
+    fn float_new_client(
+        &self,
+        frame: xcb::x::Window,
+        context: &EventContext<xcb::x::MapRequestEvent>,
+    ) -> anyhow::Result<()> {
+        let geometry = context
+            .conn
+            .wait_for_reply(context.conn.send_request(&xcb::x::GetGeometry {
+                drawable: xcb::x::Drawable::Window(context.event.window()),
+            }))
+            .context("failed to get the geometry of the new client")?;
+        let border_double = context.config.borrow().border_width() as u32 * 2;
+        let cursor = match context.config.borrow().float_placement() {
+            FloatPlacement::Cursor => context
+                .conn
+                .wait_for_reply(context.conn.send_request(&xcb::x::QueryPointer {
+                    window: context.screen_manager.borrow().root(),
+                }))
+                .ok()
+                .map(|pointer| (pointer.root_x().into(), pointer.root_y().into())),
+            _ => None,
+        };
+
+        let mut screen_manager = context.screen_manager.borrow_mut();
+        let screen = screen_manager
+            .client_screen(frame)
+            .unwrap_or(screen_manager.active_screen_idx());
+        let position = screen_manager.place_floating(
+            screen,
+            geometry.width() as u32 + border_double,
+            geometry.height() as u32 + border_double,
+            cursor,
+        );
+        screen_manager.float_client(frame, position);
+
+        Ok(())
+    }
+
+    /// the window a transient, like a dialog, was opened for
+    fn get_transient_for(
+        &self,
+        context: &EventContext<xcb::x::MapRequestEvent>,
+    ) -> Option<xcb::x::Window> {
+        let reply = xcb_get_prop!(
+            context.conn,
+            context.event.window(),
+            xcb::x::ATOM_WM_TRANSIENT_FOR,
+            1,
+            xcb::x::ATOM_WINDOW
+        )
+        .ok()?;
+
+        reply
+            .value::<xcb::x::Window>()
+            .first()
+            .copied()
+            .filter(|parent| !parent.is_none())
+    }
+
+    /// transients float centered over their parent, or over the screen when the parent is
+    /// not one of our clients
+    fn float_transient_client(
+        &self,
+        frame: xcb::x::Window,
+        parent: xcb::x::Window,
+        context: &EventContext<xcb::x::MapRequestEvent>,
+    ) -> anyhow::Result<()> {
+        let geometry = context
+            .conn
+            .wait_for_reply(context.conn.send_request(&xcb::x::GetGeometry {
+                drawable: xcb::x::Drawable::Window(context.event.window()),
+            }))
+            .context("failed to get the geometry of the transient client")?;
+        let border_double = context.config.borrow().border_width() as u32 * 2;
+        let position = Position::new(
+            0,
+            0,
+            geometry.width() as u32 + border_double,
+            geometry.height() as u32 + border_double,
+        );
+
+        let area = self.parent_geometry(parent, context).unwrap_or_else(|| {
+            let screen_manager = context.screen_manager.borrow();
+            screen_manager
+                .screen(screen_manager.active_screen_idx())
+                .get_available_area()
+        });
+
+        context
+            .screen_manager
+            .borrow_mut()
+            .float_client(frame, position.centered_in(&area));
+
+        Ok(())
+    }
+
+    /// floating clients know their own geometry, tiled ones are asked to the server through
+    /// their frame
+    fn parent_geometry(
+        &self,
+        parent: xcb::x::Window,
+        context: &EventContext<xcb::x::MapRequestEvent>,
+    ) -> Option<Position> {
+        let parent = context.screen_manager.borrow().find_client(parent)?.clone();
+        if let Some(geometry) = parent.floating {
+            return Some(geometry);
+        }
+
+        let geometry = context
+            .conn
+            .wait_for_reply(context.conn.send_request(&xcb::x::GetGeometry {
+                drawable: xcb::x::Drawable::Window(parent.frame),
+            }))
+            .ok()?;
+        let border_double = geometry.border_width() as u32 * 2;
+
+        Some(Position::new(
+            geometry.x().into(),
+            geometry.y().into(),
+            geometry.width() as u32 + border_double,
+            geometry.height() as u32 + border_double,
+        ))
+    }
+
+    /// the closest terminal the process owning the window was launched from, walking up its
+    /// parent processes. Terminals that are already swallowed are skipped
+    fn find_terminal(
+        &self,
+        pid: u32,
+        context: &EventContext<xcb::x::MapRequestEvent>,
+    ) -> Option<xcb::x::Window> {
+        let ancestors = std::iter::successors(parent_pid(pid), |&pid| parent_pid(pid))
+            .take_while(|&pid| pid > 1)
+            .collect::<Vec<_>>();
+        let screen_manager = context.screen_manager.borrow();
+        let config = context.config.borrow();
+
+        ancestors.into_iter().find_map(|ancestor| {
+            screen_manager
+                .clients()
+                .values()
+                .filter(|client| client.pid.is_some_and(|pid| pid.eq(&ancestor)))
+                .filter(|client| !screen_manager.is_swallowed(client.frame))
+                .find(|client| {
+                    WmClass::get(&context.conn, client.window).is_some_and(|wm_class| {
+                        config
+                            .terminal_classes()
+                            .iter()
+                            .any(|class| wm_class.matches(class))
+                    })
+                })
+                .map(|client| client.frame)
+        })
+    }
+
+    /// the scratchpad starts shown, floating at the center of the screen with the size the
+    /// window asked for
+    fn setup_scratchpad(
+        &self,
+        frame: xcb::x::Window,
+        context: &EventContext<xcb::x::MapRequestEvent>,
+    ) -> anyhow::Result<()> {
+        let geometry = context
+            .conn
+            .wait_for_reply(context.conn.send_request(&xcb::x::GetGeometry {
+                drawable: xcb::x::Drawable::Window(context.event.window()),
+            }))
+            .context("failed to get the geometry of the scratchpad")?;
+        let border_double = context.config.borrow().border_width() as u32 * 2;
+
+        let mut screen_manager = context.screen_manager.borrow_mut();
+        screen_manager.set_scratchpad(Some(frame));
+        screen_manager.show_scratchpad(
+            geometry.width() as u32 + border_double,
+            geometry.height() as u32 + border_double,
+        );
+
+        Ok(())
     }
 }
 
@@ -96,22 +405,32 @@ impl Handler for MapWindowHandler {
     ) -> anyhow::Result<()> {
         let window = context.event.window();
 
-        let cookie = context.conn.send_request(&xcb::x::GetProperty {
-            delete: false,
-            window,
-            property: context.atoms.net_wm_strut_partial,
-            r#type: xcb::x::ATOM_CARDINAL,
-            long_offset: 0,
-            long_length: 12,
-        });
+        let override_redirect = context
+            .conn
+            .wait_for_reply(
+                context
+                    .conn
+                    .send_request(&xcb::x::GetWindowAttributes { window }),
+            )
+            .ok()
+            .map(|attributes| attributes.override_redirect());
+
+        if !should_manage(override_redirect) {
+            if override_redirect.is_some() {
+                xcb_map_win!(context.conn, window);
+            }
+            return Ok(());
+        }
 
         // if this window is requesting to reserve space onscreen, so we have
         // to do a few things to ensure it is handled properly, the first is
         // that this should not be handled as a regular client, but as a
         // reserved client.
-        let is_reserving_space = context.conn.wait_for_reply(cookie).unwrap();
-        if let Some(values) = is_reserving_space.value::<u32>().get(0..12) {
-            self.setup_reserved_client(values, &context);
+        if let Some(strut) = self
+            .get_strut(&context)
+            .or_else(|| self.infer_dock_strut(&context))
+        {
+            self.setup_reserved_client(strut, &context)?;
             context
                 .layout_manager
                 .display_screens(&context.screen_manager, context.decorator)
@@ -123,7 +442,12 @@ impl Handler for MapWindowHandler {
             return Ok(());
         }
 
-        let frame = context.decorator.decorate_client(window)?;
+        let wm_class = WmClass::get(&context.conn, window);
+        let is_scratchpad = self.is_scratchpad(wm_class.as_ref(), &context);
+        let rule = self.find_rule(wm_class.as_ref(), &context);
+        let transient_for = self.get_transient_for(&context);
+        let decorated = motif_wants_decorations(&context.conn, context.atoms, window);
+        let frame = context.decorator.decorate_client(window, decorated)?;
         let current_focused_client = context
             .screen_manager
             .borrow()
@@ -140,28 +464,86 @@ impl Handler for MapWindowHandler {
             .enable_client_events(frame)
             .context("failed to enable events for frame")?;
 
-        context
+        context.screen_manager.borrow_mut().create_client(
+            frame,
+            window,
+            rule.as_ref().and_then(Rule::monitor),
+            rule.as_ref().and_then(Rule::workspace),
+            rule.as_ref().and_then(Rule::focus),
+        );
+
+        if wm_class.as_ref().is_some_and(|wm_class| {
+            context
+                .config
+                .borrow()
+                .no_focus_classes()
+                .iter()
+                .any(|class| wm_class.matches(class))
+        }) {
+            context
+                .screen_manager
+                .borrow_mut()
+                .set_focusable(frame, false);
+        }
+
+        // the border can still be toggled back on through `ToggleBorder`
+        if !decorated {
+            context.screen_manager.borrow_mut().set_border(frame, false);
+        }
+
+        if let Some(size_hints) = SizeHints::get(&context.conn, window) {
+            context
+                .screen_manager
+                .borrow_mut()
+                .set_size_hints(frame, size_hints);
+        }
+
+        let pid = ewmh_get_pid(&context.conn, context.atoms, window);
+        if let Some(pid) = pid {
+            context.screen_manager.borrow_mut().set_pid(frame, pid);
+        }
+
+        if is_scratchpad {
+            self.setup_scratchpad(frame, &context)?;
+        } else if let Some(parent) = transient_for {
+            self.float_transient_client(frame, parent, &context)?;
+        } else if rule.as_ref().and_then(Rule::floating).unwrap_or(false) {
+            self.float_new_client(frame, &context)?;
+        } else if let Some(terminal) = pid
+            .filter(|_| context.config.borrow().swallowing())
+            .and_then(|pid| self.find_terminal(pid, &context))
+        {
+            context.screen_manager.borrow_mut().swallow(terminal, frame);
+            xcb_unmap_win!(context.conn, terminal);
+        }
+
+        // clients placed on another workspace by a rule, or not focused because of
+        // `focus_new_clients`, must not take the focus away
+        let is_focused = context
             .screen_manager
-            .borrow_mut()
-            .create_client(frame, window);
+            .borrow()
+            .get_focused_client()
+            .is_some_and(|client| client.frame.eq(&frame));
 
-        current_focused_client.map(|client| {
-            ewmh_set_focus(
+        if is_focused {
+            current_focused_client.map(|client| {
+                ewmh_set_focus(
+                    &context.conn,
+                    context.atoms,
+                    client.window,
+                    EwmhFocusAction::Unfocus,
+                )
+                .ok()
+            });
+            ewmh_set_focus(&context.conn, context.atoms, window, EwmhFocusAction::Focus).ok();
+            ewmh_set_active_window(
                 &context.conn,
+                context.screen_manager.borrow().root(),
                 context.atoms,
-                client.window,
-                EwmhFocusAction::Unfocus,
+                window,
             )
-            .ok()
-        });
-        ewmh_set_focus(&context.conn, context.atoms, window, EwmhFocusAction::Focus).ok();
-        ewmh_set_active_window(
-            &context.conn,
-            context.screen_manager.borrow().root(),
-            context.atoms,
-            window,
-        )
-        .ok();
+            .ok();
+        }
 
         context
             .layout_manager
@@ -176,3 +558,31 @@ impl Handler for MapWindowHandler {
         Ok(())
     }
 }
+
+/// whether a window asking to be mapped should become a client. Override-redirect windows,
+/// like menus and tooltips, place themselves and must never be framed, and a window whose
+/// attributes can't be read is already gone
+fn should_manage(override_redirect: Option<bool>) -> bool {
+    override_redirect.is_some_and(|override_redirect| !override_redirect)
+}
+
+/// the parent of a process, read from `/proc/<pid>/stat`. The process name can contain spaces
+/// and parentheses, so the fields are read after the last closing parenthesis
+fn parent_pid(pid: u32) -> Option<u32> {
+    let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+    let (_, fields) = stat.rsplit_once(')')?;
+    // the fields after the name are the state of the process followed by its parent
+    fields.split_whitespace().nth(1)?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn override_redirect_and_gone_windows_are_not_managed() {
+        assert!(should_manage(Some(false)));
+        assert!(!should_manage(Some(true)));
+        assert!(!should_manage(None));
+    }
+}
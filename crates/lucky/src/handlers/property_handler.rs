@@ -1,4 +1,5 @@
 use crate::event::EventContext;
+use crate::ewmh::{icccm_is_urgent, SizeHints};
 use crate::handlers::Handler;
 
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
@@ -7,8 +8,72 @@ pub struct PropertyHandler;
 impl Handler for PropertyHandler {
     fn on_property_notify(
         &mut self,
-        _context: EventContext<xcb::x::PropertyNotifyEvent>,
+        context: EventContext<xcb::x::PropertyNotifyEvent>,
     ) -> anyhow::Result<()> {
+        if context.event.atom().eq(&xcb::x::ATOM_WM_NORMAL_HINTS) {
+            self.handle_size_hints(&context)?;
+        }
+
+        if context.event.atom().eq(&xcb::x::ATOM_WM_HINTS) {
+            self.handle_urgency(&context)?;
+        }
+
         Ok(())
     }
 }
+
+impl PropertyHandler {
+    /// clients flag themselves as urgent through `WM_HINTS`, which is mirrored on
+    /// `_NET_WM_STATE_DEMANDS_ATTENTION` so bars can pick it up either way
+    fn handle_urgency(
+        &self,
+        context: &EventContext<xcb::x::PropertyNotifyEvent>,
+    ) -> anyhow::Result<()> {
+        let window = context.event.window();
+        let Some(frame) = context
+            .screen_manager
+            .borrow()
+            .find_client(window)
+            .map(|client| client.frame)
+        else {
+            return Ok(());
+        };
+
+        let urgent = icccm_is_urgent(&context.conn, window);
+        context.layout_manager.set_urgent(context, frame, urgent)?;
+
+        context
+            .screen_manager
+            .borrow_mut()
+            .update_atoms(context.atoms, &context.conn);
+
+        Ok(())
+    }
+
+    /// clients may change their constraints after being mapped, like terminals that change
+    /// their font size, so they are read again and the client is redrawn with them
+    fn handle_size_hints(
+        &self,
+        context: &EventContext<xcb::x::PropertyNotifyEvent>,
+    ) -> anyhow::Result<()> {
+        let window = context.event.window();
+        let Some(frame) = context
+            .screen_manager
+            .borrow()
+            .find_client(window)
+            .map(|client| client.frame)
+        else {
+            return Ok(());
+        };
+
+        let size_hints = SizeHints::get(&context.conn, window).unwrap_or_default();
+        context
+            .screen_manager
+            .borrow_mut()
+            .set_size_hints(frame, size_hints);
+
+        context
+            .layout_manager
+            .display_screens(&context.screen_manager, context.decorator)
+    }
+}
@@ -5,12 +5,14 @@ use crate::{event::EventContext, handlers::Handler};
 pub struct UnmapWindowHandler {}
 
 impl UnmapWindowHandler {
-    fn try_unmap_reserved_client(
+    /// releases the space reserved by a dock, returning whether the window was a dock at all
+    fn try_unmap_reserved_client<E>(
         &self,
-        context: &EventContext<xcb::x::UnmapNotifyEvent>,
-    ) -> anyhow::Result<()> {
-        let window = context.event.window();
+        window: xcb::x::Window,
+        context: &EventContext<E>,
+    ) -> bool {
         let mut screen_manager = context.screen_manager.borrow_mut();
+        let mut released = false;
 
         for screen in screen_manager.screens_mut() {
             let reserved_clients = screen.reserved_clients_mut();
@@ -19,21 +21,20 @@ impl UnmapWindowHandler {
                 .position(|client| client.window.eq(&window));
             if let Some(reserved_client_idx) = reserved_client_idx {
                 let reserved_client = reserved_clients[reserved_client_idx].clone();
-                context
-                    .layout_manager
-                    .close_client(&reserved_client, context.atoms)?;
                 screen.sub_left_reserved_area(reserved_client.reserved_left);
                 screen.sub_bottom_reserved_area(reserved_client.reserved_bottom);
                 screen.sub_top_reserved_area(reserved_client.reserved_top);
                 screen.sub_right_reserved_area(reserved_client.reserved_right);
                 screen.remove_reserved_client(reserved_client_idx);
+                released = true;
             }
         }
-        Ok(())
+
+        released
     }
 
-    fn try_unmap_client(&self, context: &EventContext<xcb::x::UnmapNotifyEvent>) {
-        let window = context.event.window();
+    /// stops managing a client, returning whether the window belonged to a client at all
+    fn try_unmap_client<E>(&self, window: xcb::x::Window, context: &EventContext<E>) -> bool {
         let mut screen_manager = context.screen_manager.borrow_mut();
 
         if let Some(client) = screen_manager
@@ -44,25 +45,31 @@ impl UnmapWindowHandler {
             .find(|client| client.window.eq(&window))
         {
             let frame = client.frame;
-            match context.layout_manager.close_client(client, context.atoms) {
-                Ok(_) => tracing::debug!("succesfully unmapped window {:?}", window),
-                // some softwares close their clients without waiting for the window manager
-                // thus making this fails, it is fine to keep going even though we coudlnt
-                // kill the client;
-                // if it don't exist on the X server it should not exist on our state
-                Err(_) => tracing::error!("failed to unmap client {:?}", window),
-            }
+            context
+                .layout_manager
+                .destroy_frame(client, screen_manager.root());
+            context.decorator.remove_shadow(frame);
+            tracing::debug!("succesfully unmapped window {:?}", window);
 
+            screen_manager.unswallow(frame);
+            screen_manager.forget_swallowed(frame);
             screen_manager.screens_mut().iter_mut().for_each(|s| {
+                s.remove_sticky_client(frame);
                 s.workspaces_mut()
                     .iter_mut()
                     .for_each(|ws| ws.remove_client(frame))
             });
             screen_manager.clients_mut().remove(&frame);
-            let index = screen_manager.active_screen_idx();
-            let workspace = screen_manager.screen_mut(index).active_workspace_mut();
-            workspace.set_focused_client(workspace.clients().first().copied());
+            if screen_manager
+                .scratchpad()
+                .is_some_and(|scratchpad| scratchpad.eq(&frame))
+            {
+                screen_manager.set_scratchpad(None);
+            }
+            return true;
         }
+
+        false
     }
 }
 
@@ -71,8 +78,16 @@ impl Handler for UnmapWindowHandler {
         &mut self,
         context: EventContext<xcb::x::UnmapNotifyEvent>,
     ) -> anyhow::Result<()> {
-        self.try_unmap_reserved_client(&context)?;
-        self.try_unmap_client(&context);
+        self.try_unmap_reserved_client(context.event.window(), &context);
+        // clients live inside of their frames, so an unmap reported on the root window is about
+        // a window we were not managing yet, like the ones released when adopting a session
+        if context
+            .event
+            .event()
+            .ne(&context.screen_manager.borrow().root())
+        {
+            self.try_unmap_client(context.event.window(), &context);
+        }
 
         context
             .layout_manager
@@ -100,4 +115,30 @@ impl Handler for UnmapWindowHandler {
 
         Ok(())
     }
+
+    /// a window that is destroyed while unmapped never sends an unmap notify, so whatever it
+    /// was holding, like the space reserved by a dock, has to be released here as well
+    fn on_destroy_notify(
+        &mut self,
+        context: EventContext<xcb::x::DestroyNotifyEvent>,
+    ) -> anyhow::Result<()> {
+        let window = context.event.window();
+        let released_reserved_client = self.try_unmap_reserved_client(window, &context);
+        let released_client = self.try_unmap_client(window, &context);
+
+        if !released_reserved_client && !released_client {
+            return Ok(());
+        }
+
+        context
+            .layout_manager
+            .display_screens(&context.screen_manager, context.decorator)?;
+
+        context
+            .screen_manager
+            .borrow_mut()
+            .update_atoms(context.atoms, &context.conn);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,525 @@
+use crate::lucky::XEvent;
+use crate::screen_manager::Direction;
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::sync::mpsc::{channel, Sender};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// how long writing an event to a subscriber may block before the subscriber is dropped, so a
+/// client that stops reading cannot stall the window manager
+const SUBSCRIBER_WRITE_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// a command sent by an external tool through the socket, one JSON object per line, like
+/// `{"cmd":"focus","dir":"left"}`. Workspace indexes start at 1, matching the workspace
+/// actions
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+pub enum IpcCommand {
+    Focus {
+        dir: Direction,
+    },
+    Move {
+        dir: Direction,
+    },
+    Workspace {
+        index: usize,
+    },
+    MoveToWorkspace {
+        index: usize,
+    },
+    Close,
+    Reload,
+    /// changes a config value in memory until the next reload, like `border_width` to `"4"`
+    Set {
+        key: String,
+        value: String,
+    },
+    Query {
+        what: IpcQuery,
+    },
+    /// keeps the connection open and streams `IpcEvent`s through it until it is closed
+    Subscribe,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IpcQuery {
+    Clients,
+    Workspaces,
+}
+
+/// the answer written back for every command, `data` is only present on queries
+#[derive(Debug, Serialize)]
+pub struct IpcReply {
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl IpcReply {
+    pub fn success(data: Option<serde_json::Value>) -> Self {
+        IpcReply {
+            ok: true,
+            data,
+            error: None,
+        }
+    }
+
+    pub fn failure(error: impl ToString) -> Self {
+        IpcReply {
+            ok: false,
+            data: None,
+            error: Some(error.to_string()),
+        }
+    }
+}
+
+/// a command forwarded into the main loop, along with where the reply should be sent to and
+/// the connection it came from
+#[derive(Debug, Clone)]
+pub struct IpcRequest {
+    pub command: IpcCommand,
+    pub reply_tx: Sender<IpcReply>,
+    pub connection: Arc<UnixStream>,
+}
+
+/// state transitions streamed to subscribers, one JSON object per line, like
+/// `{"event":"focus_changed","window":4194310}`
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum IpcEvent {
+    WorkspaceChanged { screen: usize, workspace: usize },
+    FocusChanged { window: Option<u32> },
+    ClientAdded { window: u32 },
+    ClientRemoved { window: u32 },
+    UrgencyChanged { window: u32, urgent: bool },
+}
+
+/// connections subscribed to events, along with the state last seen, so that only the
+/// transitions between two updates are broadcast
+#[derive(Debug, Default)]
+pub struct Subscribers {
+    connections: Vec<Arc<UnixStream>>,
+    workspaces: Vec<usize>,
+    focused_client: Option<u32>,
+    clients: HashSet<u32>,
+    urgent_clients: HashSet<u32>,
+}
+
+impl Subscribers {
+    pub fn add(&mut self, connection: Arc<UnixStream>) {
+        connection
+            .set_write_timeout(Some(SUBSCRIBER_WRITE_TIMEOUT))
+            .ok();
+        self.connections.push(connection);
+    }
+
+    /// compares the given state with the last one seen and broadcasts every difference.
+    /// `workspaces` holds the active workspace index of each screen, starting at 1
+    pub fn update(
+        &mut self,
+        workspaces: Vec<usize>,
+        focused_client: Option<u32>,
+        clients: HashSet<u32>,
+        urgent_clients: HashSet<u32>,
+    ) {
+        let mut events = vec![];
+
+        for (screen, workspace) in workspaces.iter().enumerate() {
+            if self.workspaces.get(screen) != Some(workspace) {
+                events.push(IpcEvent::WorkspaceChanged {
+                    screen,
+                    workspace: *workspace,
+                });
+            }
+        }
+        events.extend(
+            clients
+                .difference(&self.clients)
+                .map(|&window| IpcEvent::ClientAdded { window }),
+        );
+        events.extend(
+            self.clients
+                .difference(&clients)
+                .map(|&window| IpcEvent::ClientRemoved { window }),
+        );
+        events.extend(
+            urgent_clients
+                .difference(&self.urgent_clients)
+                .map(|&window| IpcEvent::UrgencyChanged {
+                    window,
+                    urgent: true,
+                }),
+        );
+        // clients that went away are already reported as removed
+        events.extend(
+            self.urgent_clients
+                .difference(&urgent_clients)
+                .filter(|window| clients.contains(window))
+                .map(|&window| IpcEvent::UrgencyChanged {
+                    window,
+                    urgent: false,
+                }),
+        );
+        if focused_client != self.focused_client {
+            events.push(IpcEvent::FocusChanged {
+                window: focused_client,
+            });
+        }
+
+        self.workspaces = workspaces;
+        self.focused_client = focused_client;
+        self.clients = clients;
+        self.urgent_clients = urgent_clients;
+
+        if self.connections.is_empty() {
+            return;
+        }
+
+        for event in events {
+            // subscribers that disconnected, or stopped reading, fail to be written to
+            self.connections
+                .retain(|connection| write_message(connection, &event).is_ok());
+        }
+    }
+}
+
+/// the directory for files no other user should reach, `$XDG_RUNTIME_DIR` or else a private
+/// directory of our own on the temporary directory. Fails when that directory is not a
+/// directory, belongs to someone else or can be reached by other users, as anyone could have
+/// created it before us
+pub fn runtime_dir() -> anyhow::Result<PathBuf> {
+    if let Some(dir) = std::env::var_os("XDG_RUNTIME_DIR") {
+        return Ok(PathBuf::from(dir));
+    }
+
+    // SAFETY: getuid cannot fail and has no preconditions
+    let uid = unsafe { libc::getuid() };
+    let dir = std::env::temp_dir().join(format!("lucky-{uid}"));
+    match std::fs::DirBuilder::new().mode(0o700).create(&dir) {
+        Ok(()) => {}
+        Err(e) if e.kind().eq(&ErrorKind::AlreadyExists) => {}
+        Err(e) => return Err(e).with_context(|| format!("failed to create {}", dir.display())),
+    }
+
+    let metadata = std::fs::symlink_metadata(&dir)
+        .with_context(|| format!("failed to read {}", dir.display()))?;
+    if !metadata.is_dir() || metadata.uid().ne(&uid) || metadata.mode() & 0o077 != 0 {
+        anyhow::bail!("{} is not a private directory of this user", dir.display());
+    }
+
+    Ok(dir)
+}
+
+/// `$DISPLAY` made safe to be part of a file name, like `1` for `:1`
+pub fn display_name() -> Option<String> {
+    std::env::var("DISPLAY")
+        .ok()
+        .filter(|display| !display.is_empty())
+        .map(|display| display.trim_start_matches(':').replace('/', "_"))
+}
+
+/// the socket lives on the [`runtime_dir`], so only our user can connect to it. It is named
+/// after `$DISPLAY`, like `lucky-1.sock` for `:1`, so every instance, like one running nested
+/// on Xephyr, gets a socket of its own
+pub fn socket_path() -> anyhow::Result<PathBuf> {
+    let name = match display_name() {
+        Some(display) => format!("lucky-{display}.sock"),
+        None => String::from("lucky.sock"),
+    };
+    Ok(runtime_dir()?.join(name))
+}
+
+/// binds the socket and accepts connections on a separate thread, every command received is
+/// sent through `event_tx` so it is handled by the main loop like any other event
+#[tracing::instrument(skip_all, err)]
+pub fn listen(event_tx: Sender<XEvent>) -> anyhow::Result<()> {
+    let path = socket_path()?;
+
+    // a previous session that did not exit cleanly leaves the socket behind, which would make
+    // binding fail. A socket that still accepts connections belongs to a running instance
+    if path.exists() {
+        if UnixStream::connect(&path).is_ok() {
+            anyhow::bail!(
+                "another instance is already listening on {}",
+                path.display()
+            );
+        }
+        std::fs::remove_file(&path).context("failed to remove stale ipc socket")?;
+    }
+
+    let listener = UnixListener::bind(&path).context("failed to bind ipc socket")?;
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let event_tx = event_tx.clone();
+                    std::thread::spawn(move || {
+                        if let Err(e) = handle_connection(stream, event_tx) {
+                            tracing::error!("ipc connection failed: {e:?}");
+                        }
+                    });
+                }
+                Err(e) => tracing::error!("failed to accept ipc connection: {e:?}"),
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// serializes the message into a single line before writing it, so that messages written
+/// from different threads never interleave
+fn write_message(connection: &UnixStream, message: &impl Serialize) -> anyhow::Result<()> {
+    let mut buffer = serde_json::to_vec(message)?;
+    buffer.push(b'\n');
+    (&*connection).write_all(&buffer)?;
+    Ok(())
+}
+
+fn handle_connection(stream: UnixStream, event_tx: Sender<XEvent>) -> anyhow::Result<()> {
+    let reader = BufReader::new(stream.try_clone()?);
+    let connection = Arc::new(stream);
+
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let command = match serde_json::from_str::<IpcCommand>(&line) {
+            Ok(command) => command,
+            Err(e) => {
+                write_message(
+                    &connection,
+                    &IpcReply::failure(format!("invalid command: {e}")),
+                )?;
+                continue;
+            }
+        };
+
+        let (reply_tx, reply_rx) = channel();
+        let subscribe = matches!(command, IpcCommand::Subscribe);
+
+        // the subscription is acknowledged before it is registered, so no event can be written
+        // ahead of the reply. From then on the main loop owns the connection
+        if subscribe {
+            write_message(&connection, &IpcReply::success(None))?;
+        }
+
+        event_tx
+            .send(XEvent::Ipc(IpcRequest {
+                command,
+                reply_tx,
+                connection: connection.clone(),
+            }))
+            .context("failed to send ipc command through channel")?;
+
+        if subscribe {
+            return Ok(());
+        }
+
+        let reply = reply_rx
+            .recv()
+            .unwrap_or_else(|_| IpcReply::failure("command was not handled"));
+        write_message(&connection, &reply)?;
+    }
+
+    Ok(())
+}
+
+/// builds the json command from the words given to `lucky msg`, like `focus left` or
+/// `workspace 2`
+fn command_from_args(args: &[String]) -> anyhow::Result<serde_json::Value> {
+    let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+
+    let command = match args.as_slice() {
+        [cmd @ ("focus" | "move"), dir] => serde_json::json!({ "cmd": cmd, "dir": dir }),
+        [cmd @ ("workspace" | "move_to_workspace"), index] => {
+            let index = index
+                .parse::<usize>()
+                .with_context(|| format!("invalid workspace index: {index}"))?;
+            serde_json::json!({ "cmd": cmd, "index": index })
+        }
+        [cmd @ ("close" | "reload" | "subscribe")] => serde_json::json!({ "cmd": cmd }),
+        ["set", key, value @ ..] if !value.is_empty() => {
+            serde_json::json!({ "cmd": "set", "key": key, "value": value.join(" ") })
+        }
+        ["query", what] => serde_json::json!({ "cmd": "query", "what": what }),
+        [] => anyhow::bail!("missing command, usage: lucky msg <command> [arguments]"),
+        _ => anyhow::bail!("unknown command: {}", args.join(" ")),
+    };
+
+    Ok(command)
+}
+
+/// sends a single command to the running window manager and prints its reply, returning the
+/// exit code for the process
+pub fn send_message(args: &[String]) -> i32 {
+    let command = match command_from_args(args) {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("lucky: {e}");
+            return 2;
+        }
+    };
+
+    let path = match socket_path() {
+        Ok(path) => path,
+        Err(e) => {
+            eprintln!("lucky: {e:#}");
+            return 1;
+        }
+    };
+    let stream = match UnixStream::connect(&path) {
+        Ok(stream) => stream,
+        Err(e) if matches!(e.kind(), ErrorKind::NotFound | ErrorKind::ConnectionRefused) => {
+            eprintln!(
+                "lucky: could not find the window manager socket at {}, is lucky running?",
+                path.display()
+            );
+            return 1;
+        }
+        Err(e) => {
+            eprintln!("lucky: failed to connect to {}: {e}", path.display());
+            return 1;
+        }
+    };
+
+    let subscribe = command["cmd"] == "subscribe";
+    let exchange = || -> anyhow::Result<String> {
+        let mut writer = stream.try_clone()?;
+        serde_json::to_writer(&mut writer, &command)?;
+        writer.write_all(b"\n")?;
+
+        let mut reader = BufReader::new(stream);
+        let mut reply = String::new();
+        reader.read_line(&mut reply)?;
+
+        // subscribers keep printing every event until the window manager goes away
+        if subscribe {
+            print!("{reply}");
+            for event in reader.lines() {
+                println!("{}", event?);
+            }
+        }
+
+        Ok(reply)
+    };
+
+    match exchange() {
+        Ok(_) if subscribe => 0,
+        Ok(reply) => {
+            print!("{reply}");
+            let ok = serde_json::from_str::<serde_json::Value>(&reply)
+                .ok()
+                .and_then(|reply| reply["ok"].as_bool())
+                .unwrap_or(false);
+            if ok {
+                0
+            } else {
+                1
+            }
+        }
+        Err(e) => {
+            eprintln!("lucky: failed to talk to the window manager: {e}");
+            1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// workspaces per screen, focused client, clients, urgent clients and the events expected
+    type Step = (
+        Vec<usize>,
+        Option<u32>,
+        &'static [u32],
+        &'static [u32],
+        Vec<serde_json::Value>,
+    );
+
+    #[test]
+    fn subscribers_only_hear_about_changes() {
+        let (ours, theirs) = UnixStream::pair().unwrap();
+        theirs.set_nonblocking(true).unwrap();
+        let mut reader = BufReader::new(theirs);
+        let mut subscribers = Subscribers::default();
+        subscribers.add(Arc::new(ours));
+
+        #[rustfmt::skip]
+        let steps: [Step; 4] = [
+            (vec![1], None, &[10], &[], vec![
+                json!({ "event": "workspace_changed", "screen": 0, "workspace": 1 }),
+                json!({ "event": "client_added", "window": 10 }),
+            ]),
+            (vec![1], Some(10), &[10, 11], &[11], vec![
+                json!({ "event": "client_added", "window": 11 }),
+                json!({ "event": "urgency_changed", "window": 11, "urgent": true }),
+                json!({ "event": "focus_changed", "window": 10 }),
+            ]),
+            // a client that goes away is only reported as removed, not as no longer urgent
+            (vec![2], Some(10), &[10], &[], vec![
+                json!({ "event": "workspace_changed", "screen": 0, "workspace": 2 }),
+                json!({ "event": "client_removed", "window": 11 }),
+            ]),
+            (vec![2], Some(10), &[10], &[], vec![]),
+        ];
+
+        for (idx, (workspaces, focused, clients, urgent, expected)) in steps.into_iter().enumerate()
+        {
+            subscribers.update(
+                workspaces,
+                focused,
+                clients.iter().copied().collect(),
+                urgent.iter().copied().collect(),
+            );
+
+            let mut events = vec![];
+            let mut line = String::new();
+            while reader.read_line(&mut line).is_ok_and(|read| read > 0) {
+                events.push(serde_json::from_str::<serde_json::Value>(&line).unwrap());
+                line.clear();
+            }
+            assert_eq!(events, expected, "step {idx}");
+        }
+    }
+
+    #[test]
+    fn msg_arguments_become_commands() {
+        #[rustfmt::skip]
+        let cases: [(&[&str], Option<serde_json::Value>); 14] = [
+            (&["focus", "left"], Some(json!({ "cmd": "focus", "dir": "left" }))),
+            (&["move", "up"], Some(json!({ "cmd": "move", "dir": "up" }))),
+            (&["workspace", "2"], Some(json!({ "cmd": "workspace", "index": 2 }))),
+            (&["move_to_workspace", "3"], Some(json!({ "cmd": "move_to_workspace", "index": 3 }))),
+            (&["workspace", "two"], None),
+            (&["close"], Some(json!({ "cmd": "close" }))),
+            (&["reload"], Some(json!({ "cmd": "reload" }))),
+            (&["subscribe"], Some(json!({ "cmd": "subscribe" }))),
+            (&["set", "border_color", "#ff0000"], Some(json!({ "cmd": "set", "key": "border_color", "value": "#ff0000" }))),
+            (&["set", "layout", "tall", "wide"], Some(json!({ "cmd": "set", "key": "layout", "value": "tall wide" }))),
+            (&["set", "border_color"], None),
+            (&["query", "clients"], Some(json!({ "cmd": "query", "what": "clients" }))),
+            (&[], None),
+            (&["dance"], None),
+        ];
+
+        for (args, expected) in cases {
+            let args = args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+            assert_eq!(command_from_args(&args).ok(), expected, "{args:?}");
+        }
+    }
+}
@@ -1,13 +1,30 @@
-use config::Config;
+use anyhow::Context;
+use config::{keysyms::Keysym, Action, Config, Modifiers};
 use std::{cell::RefCell, collections::HashMap, rc::Rc, sync::Arc};
-use xcb::x::{GrabKey, GrabMode, ModMask};
+use xcb::x::{GrabKey, GrabMode, ModMask, UngrabKey};
 use xkbcommon::xkb;
 
 pub struct Keyboard {
-    pub state: xkbcommon::xkb::State,
+    state: xkb::State,
+    keymap: xkb::Keymap,
+    device_id: i32,
+    /// the mode whose actions are grabbed instead of the regular bindings, if any
+    mode: RefCell<Option<String>>,
 }
 
 impl Keyboard {
+    /// every keysym the given keycode can produce on its active layout, across all shift levels.
+    ///
+    /// we don't track modifier state, so querying only the base level would make keys whose
+    /// interesting symbol lives on another level unmatchable, eg: numpad keys report `KP_End`
+    /// instead of `KP_1` on the base level.
+    pub fn keysyms(&self, keycode: xkb::Keycode) -> Vec<Keysym> {
+        keysyms_for_keycode(&self.keymap, &self.state, keycode)
+            .into_iter()
+            .filter_map(|keysym| Keysym::try_from(keysym).ok())
+            .collect()
+    }
+
     pub fn new(
         conn: &Arc<xcb::Connection>,
         config: Rc<RefCell<Config>>,
@@ -42,6 +59,22 @@ impl Keyboard {
         }))
         .expect("failed to select events from xkb");
 
+        // holding a key makes the server repeat it as release and press pairs, which would fire
+        // release bindings over and over. Detectable auto repeat only sends the release once the
+        // key is really let go
+        if let Err(e) = conn.wait_for_reply(conn.send_request(&xcb::xkb::PerClientFlags {
+            device_spec: xcb::xkb::Id::UseCoreKbd as xcb::xkb::DeviceSpec,
+            change: xcb::xkb::PerClientFlag::DETECTABLE_AUTO_REPEAT,
+            value: xcb::xkb::PerClientFlag::DETECTABLE_AUTO_REPEAT,
+            ctrls_to_change: xcb::xkb::BoolCtrl::empty(),
+            auto_ctrls: xcb::xkb::BoolCtrl::empty(),
+            auto_ctrls_values: xcb::xkb::BoolCtrl::empty(),
+        })) {
+            tracing::warn!(
+                "failed to enable detectable auto repeat, held keys repeat release bindings: {e:?}"
+            );
+        }
+
         let context = xkb::Context::new(xkb::CONTEXT_NO_FLAGS);
         let device_id = xkb::x11::get_core_keyboard_device_id(conn);
         let keymap = xkb::x11::keymap_new_from_device(
@@ -51,56 +84,217 @@ impl Keyboard {
             xkb::KEYMAP_COMPILE_NO_FLAGS,
         );
         let state = xkbcommon::xkb::x11::state_new_from_device(&keymap, conn, device_id);
-        let mut keycode_map = HashMap::new();
+        let keyboard = Keyboard {
+            state,
+            keymap,
+            device_id,
+            mode: RefCell::default(),
+        };
+        keyboard.regrab(conn, &config.borrow(), root);
+
+        Ok(keyboard)
+    }
+
+    /// rebuilds the keymap and its state from the server, used when the keyboard or its
+    /// mapping changes, like when running `setxkbmap`
+    pub fn reload_keymap(&mut self, conn: &Arc<xcb::Connection>) {
+        let context = xkb::Context::new(xkb::CONTEXT_NO_FLAGS);
+        self.keymap = xkb::x11::keymap_new_from_device(
+            &context,
+            conn,
+            self.device_id,
+            xkb::KEYMAP_COMPILE_NO_FLAGS,
+        );
+        self.state = xkb::x11::state_new_from_device(&self.keymap, conn, self.device_id);
+    }
+
+    /// keeps the state in sync with the server, returning whether the active layout changed,
+    /// in which case the keys producing each keysym may have changed as well
+    pub fn update_state(&mut self, event: &xcb::xkb::StateNotifyEvent) -> bool {
+        let changed = self.state.update_mask(
+            event.base_mods().bits(),
+            event.latched_mods().bits(),
+            event.locked_mods().bits(),
+            event.base_group() as xkb::LayoutIndex,
+            event.latched_group() as xkb::LayoutIndex,
+            event.locked_group() as xkb::LayoutIndex,
+        );
+
+        changed & xkb::STATE_LAYOUT_EFFECTIVE != 0
+    }
+
+    pub fn mode(&self) -> Option<String> {
+        self.mode.borrow().clone()
+    }
+
+    /// the actions that can fire right now, the ones of the active mode when there is one
+    pub fn actions<'a>(&self, config: &'a Config) -> &'a [Action] {
+        self.mode
+            .borrow()
+            .as_deref()
+            .and_then(|mode| config.mode_actions(mode))
+            .unwrap_or(config.actions())
+    }
+
+    /// switches to the bindings of `mode`, or back to the regular ones when `None`
+    pub fn set_mode(
+        &self,
+        conn: &Arc<xcb::Connection>,
+        config: &Config,
+        root: xcb::x::Window,
+        mode: Option<String>,
+    ) {
+        *self.mode.borrow_mut() = mode;
+        self.regrab(conn, config, root);
+    }
+
+    /// releases every key grabbed on the root window and grabs exactly the bindings on the
+    /// configuration, so reloading it never leaves stale bindings swallowing keys. While in a
+    /// mode only its actions are grabbed, along with `Escape` to leave it
+    pub fn regrab(&self, conn: &Arc<xcb::Connection>, config: &Config, root: xcb::x::Window) {
+        conn.send_request(&UngrabKey {
+            key: xcb::x::GRAB_ANY,
+            grab_window: root,
+            modifiers: ModMask::ANY,
+        });
 
-        keymap.key_for_each(|_, keycode| {
-            if let Some(keysym) = state.key_get_one_sym(keycode).name() {
-                keycode_map.insert(keysym, keycode.raw());
+        let mut keycode_map = HashMap::new();
+        self.keymap.key_for_each(|keymap, keycode| {
+            for keysym in keysyms_for_keycode(keymap, &self.state, keycode) {
+                if let Some(name) = keysym.name() {
+                    keycode_map.entry(name).or_insert(keycode.raw());
+                }
             }
         });
 
-        for action in config.borrow().actions().iter() {
-            let keycode = match keycode_map.get(action.key().canonical_name()) {
-                Some(e) => *e as u8,
-                None => {
-                    tracing::error!("failed to grab key: {}", action.key());
-                    anyhow::bail!(format!("failed to grab key: {}", action.key()))
+        // a keysym may be valid on the configuration but not be produced by any key on the
+        // current keymap, those bindings can never fire, so we let the user know about them
+        let mut ungrabbable = vec![];
+        // another client may already hold a combination, the server refuses to grab it for us
+        let mut taken = vec![];
+
+        // the mode may be gone after a reload, leaving it is the only way to not lose every key
+        let mode = self.mode();
+        if mode.is_some_and(|mode| config.mode_actions(&mode).is_none()) {
+            *self.mode.borrow_mut() = None;
+        }
+        let in_mode = self.mode.borrow().is_some();
+
+        if in_mode {
+            if let Some(keycode) = keycode_map.get(Keysym::XK_Escape.canonical_name()) {
+                if grab_key(conn.clone(), Modifiers::default(), *keycode as u8, root).is_err() {
+                    taken.push(String::from("Escape (leaving the mode)"));
+                }
+            }
+        }
+
+        for action in self.actions(config).iter() {
+            match keycode_map.get(action.key().canonical_name()) {
+                Some(keycode) => {
+                    if grab_key(conn.clone(), action.modifiers(), *keycode as u8, root).is_err() {
+                        taken.push(format!(
+                            "{} (action {:?})",
+                            chord(action.modifiers(), &action.key()),
+                            action.action()
+                        ));
+                    }
                 }
-            };
-            grab_key(conn.clone(), action.modifiers().inner(), keycode, root);
+                None => ungrabbable.push(format!(
+                    "{} (action {:?})",
+                    action.key().canonical_name(),
+                    action.action()
+                )),
+            }
         }
 
-        for command in config.borrow().commands().iter() {
-            let keycode = match keycode_map.get(command.key().canonical_name()) {
-                Some(e) => *e as u8,
-                None => {
-                    tracing::error!("failed to grab key: {}", command.key());
-                    anyhow::bail!(format!("failed to grab key: {}", command.key()))
+        let commands = if in_mode { &[][..] } else { config.commands() };
+        for command in commands.iter() {
+            match keycode_map.get(command.key().canonical_name()) {
+                Some(keycode) => {
+                    if grab_key(conn.clone(), command.modifiers(), *keycode as u8, root).is_err() {
+                        taken.push(format!(
+                            "{} (command {})",
+                            chord(command.modifiers(), &command.key()),
+                            command.command()
+                        ));
+                    }
                 }
-            };
-            grab_key(conn.clone(), command.modifiers(), keycode, root);
+                None => ungrabbable.push(format!(
+                    "{} (command {})",
+                    command.key().canonical_name(),
+                    command.command()
+                )),
+            }
+        }
+
+        if !ungrabbable.is_empty() {
+            tracing::warn!(
+                "no key on the current keymap produces the keysyms of these bindings, they will not work: {}",
+                ungrabbable.join(", ")
+            );
         }
 
-        Ok(Keyboard { state })
+        if !taken.is_empty() {
+            tracing::warn!(
+                "these bindings are already grabbed by another client, they will not work: {}",
+                taken.join(", ")
+            );
+        }
+    }
+}
+
+fn keysyms_for_keycode(
+    keymap: &xkb::Keymap,
+    state: &xkb::State,
+    keycode: xkb::Keycode,
+) -> Vec<xkb::Keysym> {
+    let layout = state.key_get_layout(keycode);
+    (0..keymap.num_levels_for_key(keycode, layout))
+        .flat_map(|level| {
+            keymap
+                .key_get_syms_by_level(keycode, layout, level)
+                .to_vec()
+        })
+        .collect()
+}
+
+/// a binding written the way it is on the configuration, like `Super+Shift+q`
+fn chord(modifiers: Modifiers, key: &Keysym) -> String {
+    match modifiers.inner() {
+        0 => key.to_string(),
+        _ => format!("{modifiers}+{key}"),
     }
 }
 
 fn grab_key(
     conn: Arc<xcb::Connection>,
-    modifiers: xkbcommon::xkb::ModMask,
+    modifiers: Modifiers,
     key: u8,
     grab_window: xcb::x::Window,
-) {
-    conn.check_request(
-        conn.send_request_checked(&GrabKey {
-            modifiers: ModMask::from_bits(modifiers)
-                .expect("no invalid modifiers should be exist at this point"),
-            grab_window,
-            key,
-            keyboard_mode: GrabMode::Async,
-            pointer_mode: GrabMode::Async,
-            owner_events: true,
-        }),
-    )
-    .expect("failed to grab keyboard key");
+) -> anyhow::Result<()> {
+    // the server only delivers a grabbed key when the modifier state matches exactly, so we
+    // also grab every combination of caps lock and num lock to keep bindings working with them
+    let locks = [
+        Modifiers::default(),
+        Modifiers::LOCK,
+        Modifiers::MOD2,
+        Modifiers::LOCKS,
+    ];
+
+    for lock in locks {
+        conn.check_request(
+            conn.send_request_checked(&GrabKey {
+                modifiers: ModMask::from_bits((modifiers | lock).inner())
+                    .expect("no invalid modifiers should be exist at this point"),
+                grab_window,
+                key,
+                keyboard_mode: GrabMode::Async,
+                pointer_mode: GrabMode::Async,
+                owner_events: true,
+            }),
+        )
+        .context("failed to grab keyboard key")?;
+    }
+
+    Ok(())
 }
@@ -1,27 +1,48 @@
+mod floating_layout;
+mod fullscreen_layout;
+mod monocle_layout;
 mod tall_layout;
-use crate::ewmh::{ewmh_set_active_window, ewmh_set_focus, EwmhFocusAction};
+use crate::ewmh::{ewmh_set_active_window, ewmh_set_focus, ewmh_set_wm_state, EwmhFocusAction};
 
 use crate::xcb_utils::*;
 use crate::{
     atoms::Atoms,
     decorator::Decorator,
     event::EventContext,
-    layout_manager::tall_layout::TallLayout,
-    screen::{Workspace, WorkspaceLayout},
+    layout_manager::{
+        floating_layout::FloatingLayout, fullscreen_layout::FullscreenLayout,
+        monocle_layout::MonocleLayout, tall_layout::TallLayout,
+    },
+    position::Position,
+    screen::{Client, Screen, Workspace, WorkspaceLayout},
     screen_manager::{Direction, ScreenManager},
 };
-use config::{AvailableActions, Config};
-use std::{cell::RefCell, rc::Rc, sync::Arc};
+use anyhow::Context;
+use config::{Config, WorkspaceScope, WorkspaceSwitchFocus};
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+    sync::Arc,
+};
 use xcb::Xid;
 
 pub struct LayoutManager {
     config: Rc<RefCell<Config>>,
     conn: Arc<xcb::Connection>,
+    /// frame the pointer was last brought to, or found on, with `warp_cursor`
+    pointer_client: Cell<Option<xcb::x::Window>>,
+    /// where the pointer was last warped to, the enter notify it causes is not a focus change
+    warp_target: Cell<Option<(i16, i16)>>,
 }
 
 impl LayoutManager {
     pub fn new(conn: Arc<xcb::Connection>, config: Rc<RefCell<Config>>) -> Self {
-        LayoutManager { config, conn }
+        LayoutManager {
+            config,
+            conn,
+            pointer_client: Cell::new(None),
+            warp_target: Cell::new(None),
+        }
     }
 
     pub fn enable_client_events(&self, window: xcb::x::Window) -> anyhow::Result<()> {
@@ -38,15 +59,57 @@ impl LayoutManager {
         Ok(())
     }
 
+    /// lays out every screen and flushes all of it to the server at once.
+    ///
+    /// every request sent while displaying is unchecked, so they are only buffered by the
+    /// connection, and the layout ends with a single explicit flush instead of waiting for the
+    /// one done after the event is handled. A client can go away at any moment, and the errors
+    /// caused by configuring a window that is already gone are harmless. The only round trip
+    /// left is reading the input model of the focused client, whose two `GetProperty` requests
+    /// are sent together before waiting on either reply
+    ///
+    /// the geometry applied to every frame is stored on its client once everything is placed
     pub fn display_screens(
         &self,
         screen_manager: &Rc<RefCell<ScreenManager>>,
         decorator: &Decorator,
     ) -> anyhow::Result<()> {
-        for screen in screen_manager.borrow().screens() {
+        self.display(screen_manager, decorator, None)
+    }
+
+    /// like `display_screens`, but only redraws the screen at `index`, for changes that
+    /// cannot affect the other screens
+    pub fn display_screen(
+        &self,
+        screen_manager: &Rc<RefCell<ScreenManager>>,
+        decorator: &Decorator,
+        index: usize,
+    ) -> anyhow::Result<()> {
+        self.display(screen_manager, decorator, Some(index))
+    }
+
+    /// redraws every screen, or only the one at `only` when given
+    fn display(
+        &self,
+        screen_manager: &Rc<RefCell<ScreenManager>>,
+        decorator: &Decorator,
+        only: Option<usize>,
+    ) -> anyhow::Result<()> {
+        let mut geometries = vec![];
+        let mut shadowed = vec![];
+        let mut raised = vec![];
+
+        for (index, screen) in screen_manager.borrow().screens().iter().enumerate() {
+            if only.is_some_and(|only| only.ne(&index)) {
+                continue;
+            }
             let workspace = screen.active_workspace();
             let screen_manager = screen_manager.borrow();
 
+            // docks are displayed regardless of the workspace having clients, otherwise a bar
+            // mapped on, or switched to, an empty workspace would never show up
+            self.display_reserved_clients(screen);
+
             let visible_clients = screen_manager
                 .get_visible_screen_clients(screen)
                 .into_iter()
@@ -59,26 +122,269 @@ impl LayoutManager {
             }
 
             let focused_client = screen_manager.get_focused_client();
+            let (fullscreen_clients, visible_clients): (Vec<_>, Vec<_>) = visible_clients
+                .into_iter()
+                .partition(|client| client.fullscreen);
+            let (floating_clients, tiled_clients): (Vec<_>, Vec<_>) = visible_clients
+                .into_iter()
+                .partition(|client| client.floating.is_some());
 
-            match workspace.layout() {
+            let tiled_geometries = match workspace.layout() {
                 WorkspaceLayout::Tall => TallLayout::display_clients(
                     &self.conn,
                     &self.config,
                     screen,
-                    visible_clients,
+                    tiled_clients.clone(),
                     focused_client,
                     decorator,
                 )?,
-            }
+                WorkspaceLayout::Monocle => MonocleLayout::display_clients(
+                    &self.conn,
+                    &self.config,
+                    screen,
+                    tiled_clients.clone(),
+                    focused_client,
+                    decorator,
+                )?,
+            };
+
+            let floating_geometries = FloatingLayout::display_clients(
+                &self.conn,
+                &self.config,
+                floating_clients.clone(),
+                focused_client,
+                decorator,
+            )?;
+
+            let fullscreen_geometries = FullscreenLayout::display_clients(
+                &self.conn,
+                screen,
+                fullscreen_clients.clone(),
+                focused_client,
+                decorator,
+            )?;
+
+            // gapless monocle frames have no border to round, so they stay rectangular just like
+            // the fullscreen ones
+            let gapless = matches!(workspace.layout(), WorkspaceLayout::Monocle)
+                && workspace.gapless_monocle(&self.config.borrow());
+            let (rounded_geometries, rectangular_geometries) = match gapless {
+                true => (
+                    vec![],
+                    tiled_geometries
+                        .iter()
+                        .chain(&fullscreen_geometries)
+                        .cloned()
+                        .collect(),
+                ),
+                false => (tiled_geometries.clone(), fullscreen_geometries.clone()),
+            };
+            let config = self.config.borrow();
+            let tiled_border_width = |client: &Client| match workspace.layout() {
+                WorkspaceLayout::Tall => {
+                    TallLayout::border_width(client, &config, tiled_clients.len())
+                }
+                WorkspaceLayout::Monocle => {
+                    smart_border(client, &config, tiled_clients.len()).border_width(&config)
+                }
+            };
+            self.shape_frames(
+                &screen_manager,
+                &rounded_geometries,
+                &floating_geometries,
+                &rectangular_geometries,
+                &tiled_border_width,
+                decorator,
+            );
+            drop(config);
+
+            raised.extend(self.restack_clients(
+                &tiled_clients,
+                &floating_clients,
+                &fullscreen_clients,
+                focused_client,
+            ));
+
+            shadowed.extend(tiled_geometries.iter().chain(&floating_geometries).cloned());
+            geometries.extend(
+                tiled_geometries
+                    .into_iter()
+                    .chain(floating_geometries)
+                    .chain(fullscreen_geometries),
+            );
         }
 
+        // shadows on the screens that were not redrawn are left as they are
+        let owned = |frame: xcb::x::Window| match only {
+            None => true,
+            Some(index) => screen_manager
+                .borrow()
+                .screen(index)
+                .workspaces()
+                .iter()
+                .any(|workspace| workspace.clients().contains(&frame)),
+        };
+        decorator
+            .place_shadows(&shadowed, owned)
+            .context("failed to place the shadows")?;
+
+        let mut screen_manager = screen_manager.borrow_mut();
+        for (frame, geometry) in geometries {
+            screen_manager.set_position(frame, geometry);
+        }
+        screen_manager.raise_clients(&raised);
+        self.warp_to_focused_client(&screen_manager);
+
+        self.conn
+            .flush()
+            .context("failed to flush the layout to the server")?;
+
         Ok(())
     }
 
+    /// with `warp_cursor`, moves the pointer to the middle of the focused frame whenever the
+    /// focus moved to another client. Redrawing without a focus change leaves it alone
+    fn warp_to_focused_client(&self, screen_manager: &ScreenManager) {
+        if !self.config.borrow().warp_cursor() {
+            return;
+        }
+
+        let Some(client) = screen_manager.get_focused_client() else {
+            return;
+        };
+        if self.pointer_client.get().eq(&Some(client.frame)) {
+            return;
+        }
+        let Some(position) = client.position.as_ref() else {
+            return;
+        };
+
+        let x = (position.x + position.width as i32 / 2) as i16;
+        let y = (position.y + position.height as i32 / 2) as i16;
+        self.conn.send_request(&xcb::x::WarpPointer {
+            src_window: xcb::x::Window::none(),
+            dst_window: screen_manager.root(),
+            src_x: 0,
+            src_y: 0,
+            src_width: 0,
+            src_height: 0,
+            dst_x: x,
+            dst_y: y,
+        });
+        self.pointer_client.set(Some(client.frame));
+        self.warp_target.set(Some((x, y)));
+    }
+
+    /// the pointer is already on this frame, so focusing it must not warp the pointer
+    pub fn set_pointer_client(&self, frame: Option<xcb::x::Window>) {
+        self.pointer_client.set(frame);
+    }
+
+    /// whether the pointer entering a window at this position was caused by our own warp
+    pub fn is_warp_target(&self, position: (i16, i16)) -> bool {
+        self.warp_target.get().eq(&Some(position))
+    }
+
+    /// rounds the corners of tiled and floating frames, fullscreen ones cover the whole screen
+    /// and stay rectangular. The border width must match the one the layouts gave each frame,
+    /// which is why tiled frames ask the layout of their workspace for it
+    fn shape_frames(
+        &self,
+        screen_manager: &ScreenManager,
+        tiled_geometries: &[(xcb::x::Window, Position)],
+        floating_geometries: &[(xcb::x::Window, Position)],
+        fullscreen_geometries: &[(xcb::x::Window, Position)],
+        tiled_border_width: &dyn Fn(&Client) -> u32,
+        decorator: &Decorator,
+    ) {
+        let config = self.config.borrow();
+        let radius = config.border_radius();
+        if radius.eq(&0) {
+            return;
+        }
+
+        let frames = tiled_geometries
+            .iter()
+            .map(|geometry| (geometry, true, radius))
+            .chain(
+                floating_geometries
+                    .iter()
+                    .map(|geometry| (geometry, false, radius)),
+            )
+            .chain(
+                fullscreen_geometries
+                    .iter()
+                    .map(|geometry| (geometry, false, 0)),
+            );
+
+        for ((frame, geometry), tiled, radius) in frames {
+            let Some(client) = screen_manager.clients().get(frame) else {
+                continue;
+            };
+            let border_width = match tiled {
+                true => tiled_border_width(client),
+                false => client.border_width(&config),
+            };
+            decorator.shape_frame(*frame, geometry, border_width, radius);
+        }
+    }
+
+    /// stacks the clients of a screen from the bottom up, the focused tiled client first, then
+    /// the floating clients and the fullscreen ones above everything. The focused client ends
+    /// up on top of its own group, so focusing an overlapping client brings it forward.
+    ///
+    /// returns the raised frames, in the order they were raised
+    fn restack_clients(
+        &self,
+        tiled_clients: &[&Client],
+        floating_clients: &[&Client],
+        fullscreen_clients: &[&Client],
+        focused_client: Option<&Client>,
+    ) -> Vec<xcb::x::Window> {
+        let is_focused =
+            |client: &&&Client| focused_client.is_some_and(|focused| focused.eq(**client));
+
+        let stack = tiled_clients
+            .iter()
+            .filter(is_focused)
+            .chain(floating_clients.iter().filter(|client| !is_focused(client)))
+            .chain(floating_clients.iter().filter(is_focused))
+            .chain(
+                fullscreen_clients
+                    .iter()
+                    .filter(|client| !is_focused(client)),
+            )
+            .chain(fullscreen_clients.iter().filter(is_focused));
+
+        stack
+            .map(|client| {
+                raise_window(&self.conn, client.frame);
+                client.frame
+            })
+            .collect()
+    }
+
+    /// docks reserve space on the screen rather than on a workspace, so they are shown on every
+    /// workspace and their reservation is kept across workspace switches
+    fn display_reserved_clients(&self, screen: &Screen) {
+        for client in screen.reserved_clients() {
+            self.conn.send_request(&xcb::x::ConfigureWindow {
+                window: client.window,
+                value_list: &[
+                    xcb::x::ConfigWindow::X(client.position.x),
+                    xcb::x::ConfigWindow::Y(client.position.y),
+                    xcb::x::ConfigWindow::Width(client.position.width),
+                    xcb::x::ConfigWindow::Height(client.position.height),
+                ],
+            });
+            xcb_map_win!(self.conn, client.window);
+        }
+    }
+
     #[tracing::instrument(skip_all, err)]
-    pub fn change_focus(
+    pub fn change_focus<E>(
         &self,
-        context: &EventContext<xcb::x::KeyPressEvent>,
+        context: &EventContext<E>,
         direction: Direction,
     ) -> anyhow::Result<()> {
         let mut screen_manager = context.screen_manager.borrow_mut();
@@ -87,7 +393,10 @@ impl LayoutManager {
         let workspace = screen.active_workspace();
 
         let result = match workspace.layout() {
-            WorkspaceLayout::Tall => TallLayout::focus_client(&mut screen_manager, direction)?,
+            // monocle clients are navigated in the same order as the tall layout stacks them
+            WorkspaceLayout::Tall | WorkspaceLayout::Monocle => {
+                TallLayout::focus_client(&mut screen_manager, direction)?
+            }
         };
 
         if let Some((prev_client, curr_client)) = result {
@@ -123,16 +432,25 @@ impl LayoutManager {
             }
         }
 
+        // moving past the edge of the workspace reaches into the neighbouring screen
+        let same_screen = screen_manager.active_screen_idx().eq(&active_screen_idx);
         drop(screen_manager);
-        self.display_screens(&context.screen_manager, context.decorator)?;
+        match same_screen {
+            true => self.display_screen(
+                &context.screen_manager,
+                context.decorator,
+                active_screen_idx,
+            )?,
+            false => self.display_screens(&context.screen_manager, context.decorator)?,
+        }
 
         Ok(())
     }
 
     #[tracing::instrument(skip_all, err)]
-    pub fn move_client(
+    pub fn move_client<E>(
         &self,
-        context: &EventContext<xcb::x::KeyPressEvent>,
+        context: &EventContext<E>,
         direction: Direction,
     ) -> anyhow::Result<()> {
         let mut screen_manager = context.screen_manager.borrow_mut();
@@ -141,7 +459,9 @@ impl LayoutManager {
         let workspace = screen.active_workspace();
 
         let result = match workspace.layout() {
-            WorkspaceLayout::Tall => TallLayout::move_client(&mut screen_manager, direction),
+            WorkspaceLayout::Tall | WorkspaceLayout::Monocle => {
+                TallLayout::move_client(&mut screen_manager, direction)
+            }
         };
 
         if let Some(focused_client) = result {
@@ -162,37 +482,78 @@ impl LayoutManager {
             .ok();
         }
 
+        // moving past the edge of the workspace reaches into the neighbouring screen
+        let same_screen = screen_manager.active_screen_idx().eq(&active_screen_idx);
         drop(screen_manager);
-        self.display_screens(&context.screen_manager, context.decorator)?;
+        match same_screen {
+            true => self.display_screen(
+                &context.screen_manager,
+                context.decorator,
+                active_screen_idx,
+            )?,
+            false => self.display_screens(&context.screen_manager, context.decorator)?,
+        }
 
         Ok(())
     }
 
-    pub fn change_workspace(
+    /// switches the active screen to the workspace at `workspace`, indexes past the last
+    /// workspace are ignored unless dynamic workspaces create them
+    pub fn change_workspace<E>(
         &self,
-        context: &EventContext<xcb::x::KeyPressEvent>,
-        action: AvailableActions,
+        context: &EventContext<E>,
+        workspace: usize,
     ) -> anyhow::Result<()> {
         let mut screen_manager = context.screen_manager.borrow_mut();
         let index = screen_manager.active_screen_idx();
+        let active_workspace_id = screen_manager.screen(index).active_workspace().id();
+
+        if !screen_manager.ensure_workspace(index, workspace) {
+            return Ok(());
+        }
+
+        // a global workspace shown on another monitor is reached by focusing that monitor
+        if self
+            .config
+            .borrow()
+            .workspace_scope()
+            .eq(&WorkspaceScope::Global)
+        {
+            if let Some(target) = screen_manager
+                .workspace_screen(workspace as u8)
+                .filter(|target| target.ne(&index))
+                .filter(|&target| {
+                    screen_manager
+                        .screen(target)
+                        .active_workspace_id()
+                        .eq(&workspace)
+                })
+            {
+                drop(screen_manager);
+                return self.focus_screen(context, target);
+            }
+            screen_manager.take_workspace(index, workspace as u8);
+        }
+
         let screen = screen_manager.screen_mut(index);
-        let active_workspace_id = screen.active_workspace().id();
-
-        match action {
-            AvailableActions::Workspace1 => screen.set_active_workspace(0),
-            AvailableActions::Workspace2 => screen.set_active_workspace(1),
-            AvailableActions::Workspace3 => screen.set_active_workspace(2),
-            AvailableActions::Workspace4 => screen.set_active_workspace(3),
-            AvailableActions::Workspace5 => screen.set_active_workspace(4),
-            AvailableActions::Workspace6 => screen.set_active_workspace(5),
-            AvailableActions::Workspace7 => screen.set_active_workspace(6),
-            AvailableActions::Workspace8 => screen.set_active_workspace(7),
-            AvailableActions::Workspace9 => screen.set_active_workspace(8),
-            _ => {}
-        };
+        screen.set_active_workspace(workspace as u8);
 
         if screen.active_workspace().id().ne(&active_workspace_id) {
             self.hide_workspace(&screen.workspaces()[active_workspace_id as usize]);
+
+            let policy = self.config.borrow().workspace_switch_focus();
+            let cursor = match policy {
+                WorkspaceSwitchFocus::Cursor => self
+                    .conn
+                    .wait_for_reply(self.conn.send_request(&xcb::x::QueryPointer {
+                        window: screen_manager.root(),
+                    }))
+                    .ok()
+                    .map(|pointer| (pointer.root_x().into(), pointer.root_y().into())),
+                _ => None,
+            };
+            screen_manager.focus_after_switch(index, cursor);
+
             drop(screen_manager);
             self.display_screens(&context.screen_manager, context.decorator)?;
         }
@@ -200,38 +561,702 @@ impl LayoutManager {
         Ok(())
     }
 
-    pub fn move_to_workspace(
+    /// moves the focused client to the workspace at `workspace` without leaving the active
+    /// workspace, unless `follow` is set. Indexes that are not configured are ignored
+    pub fn move_to_workspace<E>(
         &self,
-        context: &EventContext<xcb::x::KeyPressEvent>,
-        action: AvailableActions,
+        context: &EventContext<E>,
+        workspace: usize,
     ) -> anyhow::Result<()> {
         let mut screen_manager = context.screen_manager.borrow_mut();
         let index = screen_manager.active_screen_idx();
         if let Some(active_client) = screen_manager.get_focused_client() {
             let client_frame = active_client.frame;
+            if !screen_manager.ensure_workspace(index, workspace) {
+                return Ok(());
+            }
+
             let screen = screen_manager.screen_mut(index);
             let active_workspace_id = screen.active_workspace_id();
             let workspaces = screen.workspaces_mut();
+
+            if workspace.eq(&active_workspace_id) {
+                return Ok(());
+            }
+
+            // removing the client hands the focus back to the client focused before it
             workspaces[active_workspace_id].remove_client(client_frame);
 
-            let new_workspace_id = match action {
-                AvailableActions::MoveToWorkspace1 => 0,
-                AvailableActions::MoveToWorkspace2 => 1,
-                AvailableActions::MoveToWorkspace3 => 2,
-                AvailableActions::MoveToWorkspace4 => 3,
-                AvailableActions::MoveToWorkspace5 => 4,
-                AvailableActions::MoveToWorkspace6 => 5,
-                AvailableActions::MoveToWorkspace7 => 6,
-                AvailableActions::MoveToWorkspace8 => 7,
-                AvailableActions::MoveToWorkspace9 => 8,
-                _ => unreachable!(),
-            };
+            let target = self.workspace_target(&screen_manager, workspace, index);
+            let target_screen = screen_manager.screen_mut(target);
+            target_screen.workspaces_mut()[workspace].new_client(client_frame);
 
-            workspaces[new_workspace_id]
-                .clients_mut()
-                .push(client_frame);
+            if self.config.borrow().follow() {
+                target_screen.workspaces_mut()[workspace].set_focused_client(Some(client_frame));
+                drop(screen_manager);
+                return self.change_workspace(context, workspace);
+            }
+
+            if target_screen.active_workspace_id().ne(&workspace) {
+                self.hide_client(&client_frame);
+            }
 
-            self.hide_client(&client_frame);
+            if let Some(client) = screen_manager.get_focused_client() {
+                ewmh_set_focus(
+                    &context.conn,
+                    context.atoms,
+                    client.window,
+                    EwmhFocusAction::Focus,
+                )
+                .ok();
+                ewmh_set_active_window(
+                    &context.conn,
+                    screen_manager.root(),
+                    context.atoms,
+                    client.window,
+                )
+                .ok();
+            }
+        }
+
+        drop(screen_manager);
+        self.display_screens(&context.screen_manager, context.decorator)?;
+
+        Ok(())
+    }
+
+    /// the screen clients sent to `workspace` from the screen at `index` go to, a global
+    /// workspace keeps all of its clients on the monitor it lives on
+    fn workspace_target(
+        &self,
+        screen_manager: &ScreenManager,
+        workspace: usize,
+        index: usize,
+    ) -> usize {
+        match self.config.borrow().workspace_scope() {
+            WorkspaceScope::Global => screen_manager
+                .workspace_screen(workspace as u8)
+                .unwrap_or(index),
+            WorkspaceScope::PerMonitor => index,
+        }
+    }
+
+    /// promotes the focused client to the master area, swapping places with the current master
+    pub fn swap_with_master(
+        &self,
+        context: &EventContext<xcb::x::KeyPressEvent>,
+    ) -> anyhow::Result<()> {
+        let mut screen_manager = context.screen_manager.borrow_mut();
+        let index = screen_manager.active_screen_idx();
+
+        match screen_manager.screen(index).active_workspace().layout() {
+            // monocle clients are ordered like the tall layout stacks them
+            WorkspaceLayout::Tall | WorkspaceLayout::Monocle => {
+                TallLayout::swap_with_master(&mut screen_manager)?
+            }
+        }
+
+        drop(screen_manager);
+        self.display_screen(&context.screen_manager, context.decorator, index)?;
+
+        Ok(())
+    }
+
+    /// focuses the first client of the active workspace, which is the master on the tall
+    /// layout, wherever the focus was. Empty workspaces, and masters that never take the
+    /// focus, are left alone
+    pub fn focus_master(
+        &self,
+        context: &EventContext<xcb::x::KeyPressEvent>,
+    ) -> anyhow::Result<()> {
+        let mut screen_manager = context.screen_manager.borrow_mut();
+        let index = screen_manager.active_screen_idx();
+        let workspace = screen_manager.screen(index).active_workspace();
+        let Some(master) = workspace.clients().first().copied() else {
+            return Ok(());
+        };
+        let focusable = screen_manager
+            .clients()
+            .get(&master)
+            .is_some_and(|client| client.focusable);
+        if !focusable || workspace.focused_client().eq(&Some(master)) {
+            return Ok(());
+        }
+
+        if let Some(client) = screen_manager.get_focused_client() {
+            ewmh_set_focus(
+                &context.conn,
+                context.atoms,
+                client.window,
+                EwmhFocusAction::Unfocus,
+            )
+            .ok();
+        }
+        screen_manager
+            .screen_mut(index)
+            .active_workspace_mut()
+            .set_focused_client(Some(master));
+        if let Some(client) = screen_manager.clients().get(&master) {
+            ewmh_set_focus(
+                &context.conn,
+                context.atoms,
+                client.window,
+                EwmhFocusAction::Focus,
+            )
+            .ok();
+            ewmh_set_active_window(
+                &context.conn,
+                screen_manager.root(),
+                context.atoms,
+                client.window,
+            )
+            .ok();
+        }
+
+        drop(screen_manager);
+        self.display_screen(&context.screen_manager, context.decorator, index)?;
+
+        Ok(())
+    }
+
+    /// focuses the next or previous client across every workspace and monitor, switching to
+    /// the workspace and monitor it is on
+    pub fn cycle_focus(
+        &self,
+        context: &EventContext<xcb::x::KeyPressEvent>,
+        forward: bool,
+    ) -> anyhow::Result<()> {
+        let target = context.screen_manager.borrow().cycle_target(forward);
+        self.focus_target(context, target)
+    }
+
+    /// goes back to the client focused before the current one, switching to the workspace and
+    /// monitor it is on. Used repeatedly, it goes back and forth between the same two clients
+    pub fn focus_last_window(
+        &self,
+        context: &EventContext<xcb::x::KeyPressEvent>,
+    ) -> anyhow::Result<()> {
+        let target = context.screen_manager.borrow().last_focused_target();
+        self.focus_target(context, target)
+    }
+
+    /// focuses `frame` on the given screen and workspace, which become the active ones
+    fn focus_target(
+        &self,
+        context: &EventContext<xcb::x::KeyPressEvent>,
+        target: Option<(usize, u8, xcb::x::Window)>,
+    ) -> anyhow::Result<()> {
+        let Some((screen_idx, workspace_id, frame)) = target else {
+            return Ok(());
+        };
+        let mut screen_manager = context.screen_manager.borrow_mut();
+
+        if let Some(client) = screen_manager.get_focused_client() {
+            ewmh_set_focus(
+                &context.conn,
+                context.atoms,
+                client.window,
+                EwmhFocusAction::Unfocus,
+            )
+            .ok();
+        }
+
+        let screen = screen_manager.screen_mut(screen_idx);
+        let active_workspace_id = screen.active_workspace_id();
+        screen.set_active_workspace(workspace_id);
+        screen
+            .active_workspace_mut()
+            .set_focused_client(Some(frame));
+        if active_workspace_id.ne(&(workspace_id as usize)) {
+            self.hide_workspace(&screen.workspaces()[active_workspace_id]);
+        }
+        screen_manager.set_active_screen(screen_idx);
+
+        if let Some(client) = screen_manager.get_focused_client() {
+            ewmh_set_focus(
+                &context.conn,
+                context.atoms,
+                client.window,
+                EwmhFocusAction::Focus,
+            )
+            .ok();
+            ewmh_set_active_window(
+                &context.conn,
+                screen_manager.root(),
+                context.atoms,
+                client.window,
+            )
+            .ok();
+        }
+
+        drop(screen_manager);
+        self.display_screens(&context.screen_manager, context.decorator)?;
+
+        Ok(())
+    }
+
+    /// moves the clients of the active workspace to the workspace shown on the monitor in
+    /// `direction`, focus follows them to that monitor
+    pub fn move_workspace_to_monitor(
+        &self,
+        context: &EventContext<xcb::x::KeyPressEvent>,
+        direction: Direction,
+    ) -> anyhow::Result<()> {
+        let mut screen_manager = context.screen_manager.borrow_mut();
+        let Some(target) = screen_manager.get_relative_screen_idx(direction) else {
+            return Ok(());
+        };
+
+        screen_manager.move_workspace_to_screen(target);
+
+        if let Some(client) = screen_manager.get_focused_client() {
+            ewmh_set_active_window(
+                &context.conn,
+                screen_manager.root(),
+                context.atoms,
+                client.window,
+            )
+            .ok();
+        }
+
+        drop(screen_manager);
+        self.display_screens(&context.screen_manager, context.decorator)?;
+
+        Ok(())
+    }
+
+    /// focuses the monitor in `direction`, along with the client that was focused on it or its
+    /// first client. Unlike moving the focus, this works wherever the focused client is
+    pub fn focus_monitor(
+        &self,
+        context: &EventContext<xcb::x::KeyPressEvent>,
+        direction: Direction,
+    ) -> anyhow::Result<()> {
+        let Some(target) = context
+            .screen_manager
+            .borrow()
+            .get_relative_screen_idx(direction)
+        else {
+            return Ok(());
+        };
+
+        self.focus_screen(context, target)
+    }
+
+    /// makes `target` the active screen, focusing its focused client, or its first client
+    fn focus_screen<E>(&self, context: &EventContext<E>, target: usize) -> anyhow::Result<()> {
+        let mut screen_manager = context.screen_manager.borrow_mut();
+
+        if let Some(client) = screen_manager.get_focused_client() {
+            ewmh_set_focus(
+                &context.conn,
+                context.atoms,
+                client.window,
+                EwmhFocusAction::Unfocus,
+            )
+            .ok();
+        }
+
+        screen_manager.set_active_screen(target);
+        let screen = screen_manager.screen_mut(target);
+        if screen.focused_client().is_none() {
+            let workspace = screen.active_workspace_mut();
+            let first_client = workspace.clients().first().copied();
+            workspace.set_focused_client(first_client);
+        }
+
+        if let Some(client) = screen_manager.get_focused_client() {
+            ewmh_set_focus(
+                &context.conn,
+                context.atoms,
+                client.window,
+                EwmhFocusAction::Focus,
+            )
+            .ok();
+            ewmh_set_active_window(
+                &context.conn,
+                screen_manager.root(),
+                context.atoms,
+                client.window,
+            )
+            .ok();
+        }
+
+        drop(screen_manager);
+        self.display_screens(&context.screen_manager, context.decorator)?;
+
+        Ok(())
+    }
+
+    /// sets the layout of the active workspace, cycling to the next layout when none is given.
+    /// the layout is kept on the workspace, so switching away and back preserves it
+    pub fn set_layout(
+        &self,
+        context: &EventContext<xcb::x::KeyPressEvent>,
+        layout: Option<WorkspaceLayout>,
+    ) -> anyhow::Result<()> {
+        let mut screen_manager = context.screen_manager.borrow_mut();
+        let index = screen_manager.active_screen_idx();
+        let workspace = screen_manager.screen_mut(index).active_workspace_mut();
+        let layout = layout.unwrap_or_else(|| workspace.layout().next());
+        workspace.set_layout(layout);
+
+        drop(screen_manager);
+        self.display_screen(&context.screen_manager, context.decorator, index)?;
+
+        Ok(())
+    }
+
+    /// grows or shrinks the master area of the active workspace by `step`
+    pub fn adjust_master_ratio(
+        &self,
+        context: &EventContext<xcb::x::KeyPressEvent>,
+        step: f32,
+    ) -> anyhow::Result<()> {
+        let mut screen_manager = context.screen_manager.borrow_mut();
+        let index = screen_manager.active_screen_idx();
+        screen_manager
+            .screen_mut(index)
+            .active_workspace_mut()
+            .adjust_master_ratio(&self.config.borrow(), step);
+
+        drop(screen_manager);
+        self.display_screen(&context.screen_manager, context.decorator, index)?;
+
+        Ok(())
+    }
+
+    /// changes how many clients share the master area of the active workspace
+    pub fn adjust_master_count(
+        &self,
+        context: &EventContext<xcb::x::KeyPressEvent>,
+        delta: isize,
+    ) -> anyhow::Result<()> {
+        let mut screen_manager = context.screen_manager.borrow_mut();
+        let index = screen_manager.active_screen_idx();
+        screen_manager
+            .screen_mut(index)
+            .active_workspace_mut()
+            .adjust_master_count(delta);
+
+        drop(screen_manager);
+        self.display_screen(&context.screen_manager, context.decorator, index)?;
+
+        Ok(())
+    }
+
+    /// balances the master area and the stack of the active workspace for its current clients
+    pub fn distribute_even(
+        &self,
+        context: &EventContext<xcb::x::KeyPressEvent>,
+    ) -> anyhow::Result<()> {
+        let mut screen_manager = context.screen_manager.borrow_mut();
+        let index = screen_manager.active_screen_idx();
+        screen_manager
+            .screen_mut(index)
+            .active_workspace_mut()
+            .distribute_even();
+
+        drop(screen_manager);
+        self.display_screen(&context.screen_manager, context.decorator, index)?;
+
+        Ok(())
+    }
+
+    /// moves a border of the focused client towards `direction`, only tiled clients on the
+    /// tall layout can be resized
+    pub fn resize_client(
+        &self,
+        context: &EventContext<xcb::x::KeyPressEvent>,
+        direction: Direction,
+    ) -> anyhow::Result<()> {
+        let mut screen_manager = context.screen_manager.borrow_mut();
+        let Some(focused_client) = screen_manager
+            .get_focused_client()
+            .filter(|client| !client.fullscreen && client.floating.is_none())
+            .map(|client| client.frame)
+        else {
+            return Ok(());
+        };
+
+        let index = screen_manager.active_screen_idx();
+        let screen = screen_manager.screen(index);
+        if !matches!(screen.active_workspace().layout(), WorkspaceLayout::Tall) {
+            return Ok(());
+        }
+
+        let tiled_clients = screen_manager
+            .get_visible_screen_clients(screen)
+            .into_iter()
+            .filter(|client| client.visible && !client.fullscreen && client.floating.is_none())
+            .map(|client| client.frame)
+            .collect::<Vec<_>>();
+
+        TallLayout::resize_client(
+            screen_manager.screen_mut(index),
+            &tiled_clients,
+            focused_client,
+            direction,
+            &self.config.borrow(),
+        );
+
+        drop(screen_manager);
+        self.display_screen(&context.screen_manager, context.decorator, index)?;
+
+        Ok(())
+    }
+
+    /// splits the stack of the active workspace the other way around
+    pub fn toggle_stack_direction(
+        &self,
+        context: &EventContext<xcb::x::KeyPressEvent>,
+    ) -> anyhow::Result<()> {
+        let mut screen_manager = context.screen_manager.borrow_mut();
+        let index = screen_manager.active_screen_idx();
+        screen_manager
+            .screen_mut(index)
+            .active_workspace_mut()
+            .toggle_stack_direction();
+
+        drop(screen_manager);
+        self.display_screen(&context.screen_manager, context.decorator, index)?;
+
+        Ok(())
+    }
+
+    /// switches the active screen to the workspace holding the most clients, ties are broken by
+    /// the lowest workspace index
+    pub fn focus_busiest_workspace(
+        &self,
+        context: &EventContext<xcb::x::KeyPressEvent>,
+    ) -> anyhow::Result<()> {
+        let mut screen_manager = context.screen_manager.borrow_mut();
+        let index = screen_manager.active_screen_idx();
+        let screen = screen_manager.screen_mut(index);
+        let active_workspace_id = screen.active_workspace_id();
+
+        // `max_by_key` returns the last of the maximum elements, iterating in reverse makes it
+        // the one with the lowest index
+        let Some(busiest_workspace_id) = screen
+            .workspaces()
+            .iter()
+            .enumerate()
+            .rev()
+            .max_by_key(|(_, workspace)| workspace.clients().len())
+            .map(|(id, _)| id)
+        else {
+            return Ok(());
+        };
+
+        if busiest_workspace_id.ne(&active_workspace_id) {
+            drop(screen_manager);
+            self.change_workspace(context, busiest_workspace_id)?;
+        }
+
+        Ok(())
+    }
+
+    /// moves the focused client to the next workspace of the active screen and switches to it,
+    /// so repeated calls carry the client through every workspace, wrapping at the last one.
+    pub fn carousel_client(
+        &self,
+        context: &EventContext<xcb::x::KeyPressEvent>,
+    ) -> anyhow::Result<()> {
+        let mut screen_manager = context.screen_manager.borrow_mut();
+        let Some(client_frame) = screen_manager
+            .get_focused_client()
+            .map(|client| client.frame)
+        else {
+            return Ok(());
+        };
+
+        let index = screen_manager.active_screen_idx();
+        let screen = screen_manager.screen_mut(index);
+        let active_workspace_id = screen.active_workspace_id();
+        let next_workspace_id = (active_workspace_id + 1) % screen.workspaces().len();
+
+        if next_workspace_id.eq(&active_workspace_id) {
+            return Ok(());
+        }
+
+        screen.workspaces_mut()[active_workspace_id].remove_client(client_frame);
+        let target = self.workspace_target(&screen_manager, next_workspace_id, index);
+        let workspace = &mut screen_manager.screen_mut(target).workspaces_mut()[next_workspace_id];
+        workspace.new_client(client_frame);
+        workspace.set_focused_client(Some(client_frame));
+        self.hide_client(&client_frame);
+
+        drop(screen_manager);
+        self.change_workspace(context, next_workspace_id)
+    }
+
+    /// switches the focused client between tiled and floating, a client that starts floating
+    /// keeps the geometry its frame currently has
+    pub fn toggle_floating(
+        &self,
+        context: &EventContext<xcb::x::KeyPressEvent>,
+    ) -> anyhow::Result<()> {
+        let mut screen_manager = context.screen_manager.borrow_mut();
+        let Some(client) = screen_manager.get_focused_client() else {
+            return Ok(());
+        };
+        let frame = client.frame;
+
+        if client.floating.is_some() {
+            screen_manager.tile_client(frame);
+        } else {
+            let geometry = self
+                .conn
+                .wait_for_reply(self.conn.send_request(&xcb::x::GetGeometry {
+                    drawable: xcb::x::Drawable::Window(frame),
+                }))
+                .context("failed to get the geometry of the client frame")?;
+            let border_double = geometry.border_width() as u32 * 2;
+
+            screen_manager.float_client(
+                frame,
+                Position::new(
+                    geometry.x().into(),
+                    geometry.y().into(),
+                    geometry.width() as u32 + border_double,
+                    geometry.height() as u32 + border_double,
+                ),
+            );
+        }
+
+        drop(screen_manager);
+        self.display_screens(&context.screen_manager, context.decorator)?;
+
+        Ok(())
+    }
+
+    /// makes the client cover its whole screen, or puts it back where it was, tiled or
+    /// floating, letting the other clients re-tile around it
+    pub fn set_fullscreen<E>(
+        &self,
+        context: &EventContext<E>,
+        frame: xcb::x::Window,
+        fullscreen: bool,
+    ) -> anyhow::Result<()> {
+        let mut screen_manager = context.screen_manager.borrow_mut();
+        let Some(client) = screen_manager.clients().get(&frame) else {
+            return Ok(());
+        };
+
+        if client.fullscreen.eq(&fullscreen) {
+            return Ok(());
+        }
+
+        ewmh_set_wm_state(
+            &context.conn,
+            context.atoms,
+            client.window,
+            context.atoms.net_wm_state_fullscreen,
+            fullscreen,
+        )
+        .context("failed to update the fullscreen state of the client")?;
+        screen_manager.set_fullscreen(frame, fullscreen);
+
+        drop(screen_manager);
+        self.display_screens(&context.screen_manager, context.decorator)?;
+
+        Ok(())
+    }
+
+    /// shows the client on every workspace of its screen, or back on the active one only
+    pub fn set_sticky<E>(
+        &self,
+        context: &EventContext<E>,
+        frame: xcb::x::Window,
+        sticky: bool,
+    ) -> anyhow::Result<()> {
+        let mut screen_manager = context.screen_manager.borrow_mut();
+        let Some(client) = screen_manager.clients().get(&frame) else {
+            return Ok(());
+        };
+
+        if client.sticky.eq(&sticky) {
+            return Ok(());
+        }
+
+        ewmh_set_wm_state(
+            &context.conn,
+            context.atoms,
+            client.window,
+            context.atoms.net_wm_state_sticky,
+            sticky,
+        )
+        .context("failed to update the sticky state of the client")?;
+        screen_manager.set_sticky(frame, sticky);
+        screen_manager.update_atoms(context.atoms, &context.conn);
+
+        Ok(())
+    }
+
+    /// marks a client as demanding attention, drawing it with the `urgent_border_color` until
+    /// it gets focused. The focused client is never marked, as it already has the attention
+    pub fn set_urgent<E>(
+        &self,
+        context: &EventContext<E>,
+        frame: xcb::x::Window,
+        urgent: bool,
+    ) -> anyhow::Result<()> {
+        let mut screen_manager = context.screen_manager.borrow_mut();
+        let is_focused = screen_manager
+            .get_focused_client()
+            .is_some_and(|client| client.frame.eq(&frame));
+        let Some(client) = screen_manager.clients().get(&frame) else {
+            return Ok(());
+        };
+
+        let urgent = urgent && !is_focused;
+        if client.urgent.eq(&urgent) {
+            return Ok(());
+        }
+
+        ewmh_set_wm_state(
+            &context.conn,
+            context.atoms,
+            client.window,
+            context.atoms.net_wm_state_demands_attention,
+            urgent,
+        )
+        .context("failed to update the attention state of the client")?;
+        screen_manager.set_urgent(frame, urgent);
+
+        drop(screen_manager);
+        self.display_screens(&context.screen_manager, context.decorator)?;
+
+        Ok(())
+    }
+
+    /// hides the scratchpad when it is visible, otherwise brings it to the active workspace
+    /// keeping its size
+    pub fn toggle_scratchpad(
+        &self,
+        context: &EventContext<xcb::x::KeyPressEvent>,
+    ) -> anyhow::Result<()> {
+        let mut screen_manager = context.screen_manager.borrow_mut();
+        let Some(frame) = screen_manager.scratchpad() else {
+            return Ok(());
+        };
+
+        if screen_manager.is_scratchpad_visible() {
+            screen_manager.hide_scratchpad();
+            self.hide_client(&frame);
+        } else {
+            let geometry = self
+                .conn
+                .wait_for_reply(self.conn.send_request(&xcb::x::GetGeometry {
+                    drawable: xcb::x::Drawable::Window(frame),
+                }))
+                .context("failed to get the geometry of the scratchpad frame")?;
+            let border_double = geometry.border_width() as u32 * 2;
+
+            screen_manager.show_scratchpad(
+                geometry.width() as u32 + border_double,
+                geometry.height() as u32 + border_double,
+            );
         }
 
         drop(screen_manager);
@@ -250,12 +1275,16 @@ impl LayoutManager {
         xcb_unmap_win!(self.conn, *client);
     }
 
-    /// Closes an open client.
+    /// Asks an open client to close.
     ///
     /// we need to query the `WM_PROTOCOLS` defined on the window to define how to properly
     /// close it. Modern clients will usually support `WM_DELETE_WINDOW`, and in this case
-    /// we can close by sending a `ClientMessageEvent`, otherwise we have to manually close
-    /// it through the `DestroyWindow` event.
+    /// we send a `ClientMessageEvent` and let the client close itself, giving it the chance
+    /// to save or prompt the user. Otherwise we have to forcefully terminate it through
+    /// `KillClient`.
+    ///
+    /// the client is kept on our state, it is only removed once its window gets unmapped or
+    /// destroyed, which is also when its frame gets destroyed.
     pub fn close_client<C>(&self, client: &C, atoms: &Atoms) -> anyhow::Result<()>
     where
         C: crate::screen::IntoClient,
@@ -265,8 +1294,7 @@ impl LayoutManager {
                 .map(|cookie| {
                     cookie
                         .value::<xcb::x::Atom>()
-                        .iter()
-                        .any(|&atom| atom == atoms.wm_delete_window)
+                        .contains(&atoms.wm_delete_window)
                 })
                 .unwrap_or(false);
 
@@ -288,13 +1316,112 @@ impl LayoutManager {
                 xcb::x::SendEventDest::Window(client.get_window()),
                 &event
             );
-            if let Some(frame) = client.get_frame() {
-                xcb_destroy_win!(self.conn, frame);
-            }
-        } else if let Some(frame) = client.get_frame() {
-            xcb_destroy_win!(self.conn, frame);
+        } else {
+            self.conn
+                .check_request(self.conn.send_request_checked(&xcb::x::KillClient {
+                    resource: client.get_window().resource_id(),
+                }))
+                .context("failed to kill client")?;
         }
 
         Ok(())
     }
+
+    /// destroys the frame of a client that is no longer managed.
+    ///
+    /// a client that only withdrew its window still owns it, so the window is handed back to
+    /// the root window first, otherwise destroying the frame would take it down as well. When
+    /// the window is already gone the reparent fails, which is fine.
+    pub fn destroy_frame<C>(&self, client: &C, root: xcb::x::Window)
+    where
+        C: crate::screen::IntoClient,
+    {
+        if let Some(frame) = client.get_frame() {
+            xcb_reparent_win!(self.conn, client.get_window(), root).ok();
+            xcb_destroy_win!(self.conn, frame);
+        }
+    }
+}
+
+/// with `smart_borders`, a client that is the only tiled client on its workspace is drawn
+/// without a border, the border comes back as soon as another client is tiled next to it
+fn smart_border(client: &Client, config: &Config, tiled_clients: usize) -> Client {
+    let mut client = client.clone();
+    if config.smart_borders() && tiled_clients.eq(&1) {
+        client.border = false;
+    }
+    client
+}
+
+/// with `smart_gaps`, a client that is the only tiled client on its workspace takes the whole
+/// layout area instead of being surrounded by the outer gap
+fn smart_tiling_area(screen: &Screen, config: &Config, tiled_clients: usize) -> Position {
+    if config.smart_gaps() && tiled_clients.eq(&1) {
+        screen.get_layout_area(config)
+    } else {
+        screen.get_tiling_area(config)
+    }
+}
+
+/// with `fake_shadow`, tiled cells give up the space the shadow takes past their right and
+/// bottom edges, so the shadow never covers a neighbour
+fn shadow_inset(cell: Position, config: &Config) -> Position {
+    if !config.fake_shadow() {
+        return cell;
+    }
+
+    let offset = config.shadow_offset();
+    Position::new(
+        cell.x,
+        cell.y,
+        cell.width.saturating_sub(offset),
+        cell.height.saturating_sub(offset),
+    )
+}
+
+/// places a frame, `frame_pos` holds the size inside of the border. Returns the outer geometry
+/// of the frame, border included, which is what gets stored on the client
+fn configure_frame(
+    conn: &Arc<xcb::Connection>,
+    frame: xcb::x::Window,
+    frame_pos: Position,
+    border_width: u32,
+) -> Position {
+    conn.send_request(&xcb::x::ConfigureWindow {
+        window: frame,
+        value_list: &[
+            xcb::x::ConfigWindow::X(frame_pos.x),
+            xcb::x::ConfigWindow::Y(frame_pos.y),
+            xcb::x::ConfigWindow::Width(frame_pos.width),
+            xcb::x::ConfigWindow::Height(frame_pos.height),
+            xcb::x::ConfigWindow::BorderWidth(border_width),
+        ],
+    });
+
+    let border_double = border_width * 2;
+    Position::new(
+        frame_pos.x,
+        frame_pos.y,
+        frame_pos.width + border_double,
+        frame_pos.height + border_double,
+    )
+}
+
+fn configure_window(conn: &Arc<xcb::Connection>, window: xcb::x::Window, client_pos: Position) {
+    conn.send_request(&xcb::x::ConfigureWindow {
+        window,
+        value_list: &[
+            xcb::x::ConfigWindow::X(client_pos.x),
+            xcb::x::ConfigWindow::Y(client_pos.y),
+            xcb::x::ConfigWindow::Width(client_pos.width),
+            xcb::x::ConfigWindow::Height(client_pos.height),
+        ],
+    });
+}
+
+fn raise_window(conn: &Arc<xcb::Connection>, window: xcb::x::Window) {
+    conn.send_request(&xcb::x::ConfigureWindow {
+        window,
+        value_list: &[xcb::x::ConfigWindow::StackMode(xcb::x::StackMode::Above)],
+    });
 }
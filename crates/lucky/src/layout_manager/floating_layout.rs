@@ -0,0 +1,69 @@
+use anyhow::Context;
+use config::Config;
+
+use super::{configure_frame, configure_window};
+use crate::decorator::Decorator;
+use crate::position::Position;
+use crate::screen::Client;
+use crate::xcb_utils::xcb_map_win;
+
+use std::cell::RefCell;
+use std::ops::Mul;
+use std::rc::Rc;
+use std::sync::Arc;
+
+pub struct FloatingLayout {}
+
+impl FloatingLayout {
+    /// floating clients are placed at their own geometry, they are stacked above the tiled
+    /// clients once the whole screen is displayed
+    pub fn display_clients(
+        conn: &Arc<xcb::Connection>,
+        config: &Rc<RefCell<Config>>,
+        clients: Vec<&Client>,
+        focused_client: Option<&Client>,
+        decorator: &Decorator,
+    ) -> anyhow::Result<Vec<(xcb::x::Window, Position)>> {
+        let mut geometries = Vec::with_capacity(clients.len());
+
+        for client in clients.iter() {
+            let Some(geometry) = &client.floating else {
+                continue;
+            };
+
+            decorator
+                .unfocus_client(client)
+                .context("failed to unfocus client")?;
+
+            let border_width = client.border_width(&config.borrow());
+            let border_double = border_width.mul(2);
+            let (width, height) = client.size_hints.constrain(
+                geometry.width.saturating_sub(border_double),
+                geometry.height.saturating_sub(border_double),
+            );
+
+            let frame_geometry = configure_frame(
+                conn,
+                client.frame,
+                Position::new(geometry.x, geometry.y, width, height),
+                border_width,
+            );
+            configure_window(conn, client.window, Position::new(0, 0, width, height));
+            geometries.push((client.frame, frame_geometry));
+
+            xcb_map_win!(conn, client.window);
+            xcb_map_win!(conn, client.frame);
+        }
+
+        let Some(focused_client) = focused_client else {
+            return Ok(geometries);
+        };
+
+        clients
+            .iter()
+            .find(|&&client| client == focused_client)
+            .map(|client| decorator.focus_client(client));
+
+        Ok(geometries)
+    }
+}
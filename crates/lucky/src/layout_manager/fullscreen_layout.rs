@@ -0,0 +1,54 @@
+use anyhow::Context;
+
+use super::{configure_frame, configure_window};
+use crate::decorator::Decorator;
+use crate::position::Position;
+use crate::screen::{Client, Screen};
+use crate::xcb_utils::xcb_map_win;
+
+use std::sync::Arc;
+
+pub struct FullscreenLayout {}
+
+impl FullscreenLayout {
+    /// fullscreen clients cover the whole screen without any border, ignoring the areas
+    /// reserved by docks, and are stacked above every other client
+    pub fn display_clients(
+        conn: &Arc<xcb::Connection>,
+        screen: &Screen,
+        clients: Vec<&Client>,
+        focused_client: Option<&Client>,
+        decorator: &Decorator,
+    ) -> anyhow::Result<Vec<(xcb::x::Window, Position)>> {
+        let mut geometries = Vec::with_capacity(clients.len());
+
+        let area = screen.position();
+
+        for client in clients.iter() {
+            decorator
+                .unfocus_client(client)
+                .context("failed to unfocus client")?;
+
+            let frame_geometry = configure_frame(conn, client.frame, area.clone(), 0);
+            configure_window(
+                conn,
+                client.window,
+                Position::new(0, 0, area.width, area.height),
+            );
+            geometries.push((client.frame, frame_geometry));
+
+            xcb_map_win!(conn, client.window);
+            xcb_map_win!(conn, client.frame);
+        }
+
+        let Some(focused_client) = focused_client else {
+            return Ok(geometries);
+        };
+
+        if let Some(client) = clients.iter().find(|&&client| client == focused_client) {
+            decorator.focus_client(client)?;
+        }
+
+        Ok(geometries)
+    }
+}
@@ -0,0 +1,79 @@
+use anyhow::Context;
+use config::Config;
+
+use super::{configure_frame, configure_window, shadow_inset, smart_border, smart_tiling_area};
+use crate::decorator::Decorator;
+use crate::position::Position;
+use crate::screen::{Client, Screen};
+use crate::xcb_utils::xcb_map_win;
+
+use std::cell::RefCell;
+use std::ops::Mul;
+use std::rc::Rc;
+use std::sync::Arc;
+
+pub struct MonocleLayout {}
+
+impl MonocleLayout {
+    /// every client takes the whole tiling area, and the focused client is raised above the
+    /// others so it is the only one visible. With `gapless_monocle` the clients take the whole
+    /// monitor instead, without borders
+    pub fn display_clients(
+        conn: &Arc<xcb::Connection>,
+        config: &Rc<RefCell<Config>>,
+        screen: &Screen,
+        clients: Vec<&Client>,
+        focused_client: Option<&Client>,
+        decorator: &Decorator,
+    ) -> anyhow::Result<Vec<(xcb::x::Window, Position)>> {
+        let mut geometries = Vec::with_capacity(clients.len());
+
+        let gapless = screen.active_workspace().gapless_monocle(&config.borrow());
+        let available_area = match gapless {
+            true => screen.get_layout_area(&config.borrow()),
+            false => shadow_inset(
+                smart_tiling_area(screen, &config.borrow(), clients.len()),
+                &config.borrow(),
+            ),
+        };
+
+        for client in clients.iter() {
+            decorator
+                .unfocus_client(client)
+                .context("failed to unfocus client")?;
+
+            let border_width = match gapless {
+                true => 0,
+                false => smart_border(client, &config.borrow(), clients.len())
+                    .border_width(&config.borrow()),
+            };
+            let border_double = border_width.mul(2);
+            let width = available_area.width.saturating_sub(border_double);
+            let height = available_area.height.saturating_sub(border_double);
+            let (hinted_width, hinted_height) = client.size_hints.constrain(width, height);
+            let (width, height) = (hinted_width.min(width), hinted_height.min(height));
+
+            let frame_geometry = configure_frame(
+                conn,
+                client.frame,
+                Position::new(available_area.x, available_area.y, width, height),
+                border_width,
+            );
+            configure_window(conn, client.window, Position::new(0, 0, width, height));
+            geometries.push((client.frame, frame_geometry));
+
+            xcb_map_win!(conn, client.window);
+            xcb_map_win!(conn, client.frame);
+        }
+
+        let Some(focused_client) = focused_client else {
+            return Ok(geometries);
+        };
+
+        if let Some(client) = clients.iter().find(|&&client| client == focused_client) {
+            decorator.focus_client(client)?;
+        }
+
+        Ok(geometries)
+    }
+}
@@ -1,9 +1,10 @@
 use anyhow::Context;
-use config::Config;
+use config::{Config, LayoutOrientation};
 
+use super::{configure_frame, configure_window, shadow_inset, smart_border, smart_tiling_area};
 use crate::decorator::Decorator;
 use crate::position::Position;
-use crate::screen::{Client, Screen};
+use crate::screen::{Client, Screen, StackDirection};
 use crate::screen_manager::{Direction, ScreenManager};
 use crate::xcb_utils::xcb_map_win;
 
@@ -22,43 +23,19 @@ impl TallLayout {
         clients: Vec<&Client>,
         focused_client: Option<&Client>,
         decorator: &Decorator,
-    ) -> anyhow::Result<()> {
-        let visible_clients_len = clients.len();
-        let available_area = screen.get_available_area();
-
-        let main_width = if visible_clients_len.eq(&1) {
-            available_area.width
-        } else {
-            available_area.width.div(2)
-        };
-
-        for client in screen.reserved_clients() {
-            Self::configure_window(conn, client.window, client.position.clone());
-            conn.send_request(&xcb::x::MapWindow {
-                window: client.window,
-            });
-        }
+    ) -> anyhow::Result<Vec<(xcb::x::Window, Position)>> {
+        let positions = Self::compute_positions(screen, &clients, &config.borrow());
 
-        for (i, client) in clients.iter().enumerate() {
+        for (client, (_, position)) in clients.iter().zip(positions.iter()) {
             decorator
                 .unfocus_client(client)
                 .context("failed to unfocus client")?;
-            match i {
-                0 => Self::display_main_client(conn, client, &available_area, main_width, config),
-                _ => Self::display_side_client(
-                    conn,
-                    client,
-                    &available_area,
-                    i,
-                    visible_clients_len,
-                    main_width,
-                    config,
-                ),
-            }
+            let border_width = Self::border_width(client, &config.borrow(), clients.len());
+            Self::display_client(conn, client, position, border_width);
         }
 
         let Some(focused_client) = focused_client else {
-            return Ok(());
+            return Ok(positions);
         };
 
         clients
@@ -66,77 +43,287 @@ impl TallLayout {
             .find(|&&client| client == focused_client)
             .map(|client| decorator.focus_client(client));
 
-        Ok(())
+        Ok(positions)
     }
 
-    fn display_main_client(
-        conn: &Arc<xcb::Connection>,
-        client: &Client,
+    /// the border of a tiled client, a lone client goes without one when `single_window_border`
+    /// is off, or with `smart_borders`
+    pub fn border_width(client: &Client, config: &Config, tiled_clients: usize) -> u32 {
+        if tiled_clients.eq(&1) && !config.single_window_border() {
+            return 0;
+        }
+        smart_border(client, config, tiled_clients).border_width(config)
+    }
+
+    /// computes where every client goes on the active workspace of `screen` without talking to
+    /// the server, returning the outer geometry of each frame, border included
+    pub fn compute_positions(
+        screen: &Screen,
+        clients: &[&Client],
+        config: &Config,
+    ) -> Vec<(xcb::x::Window, Position)> {
+        let visible_clients_len = clients.len();
+        let available_area = smart_tiling_area(screen, config, visible_clients_len);
+        let workspace = screen.active_workspace();
+        let master_count = workspace.master_count();
+        let orientation = config.layout_orientation();
+        let layout_area = Self::layout_area(&available_area, &orientation);
+        let main_width = Self::main_width(
+            &layout_area,
+            visible_clients_len,
+            master_count,
+            workspace.master_ratio(config),
+        );
+        let main_total = master_count.min(visible_clients_len);
+        let inner_gap = workspace.inner_gap(config);
+        let weights = clients
+            .iter()
+            .map(|client| workspace.weight(client.frame))
+            .collect::<Vec<_>>();
+        let (main_weights, side_weights) = weights.split_at(main_total);
+
+        clients
+            .iter()
+            .enumerate()
+            .map(|(i, client)| {
+                let cell = match i {
+                    _ if i < master_count => {
+                        Self::main_cell(&layout_area, i, main_weights, main_width)
+                    }
+                    _ => Self::side_cell(
+                        &layout_area,
+                        i.sub(master_count),
+                        side_weights,
+                        main_width,
+                        workspace.stack_direction(),
+                    ),
+                };
+                let cell = Self::orient_cell(cell, &available_area, &orientation);
+                let cell = Self::apply_inner_gap(cell, &available_area, inner_gap);
+                let cell = shadow_inset(cell, config);
+                let border_width = Self::border_width(client, config, visible_clients_len);
+                (client.frame, Self::fit_client(client, cell, border_width))
+            })
+            .collect()
+    }
+
+    /// when every client fits in the master area it takes the whole width
+    fn main_width(
         available_area: &Position,
+        total: usize,
+        master_count: usize,
+        master_ratio: f32,
+    ) -> u32 {
+        if total.le(&master_count) {
+            available_area.width
+        } else {
+            (available_area.width as f32 * master_ratio) as u32
+        }
+    }
+
+    /// cells are always computed as if the master area was on the left, for the top and bottom
+    /// orientations that happens over the transposed area, so the "width" of the master
+    /// column becomes the height of the master row
+    fn layout_area(available_area: &Position, orientation: &LayoutOrientation) -> Position {
+        match orientation {
+            LayoutOrientation::Left | LayoutOrientation::Right => available_area.clone(),
+            LayoutOrientation::Top | LayoutOrientation::Bottom => Position::new(
+                available_area.x,
+                available_area.y,
+                available_area.height,
+                available_area.width,
+            ),
+        }
+    }
+
+    /// maps a cell computed over the layout area back onto the available area, mirroring it for
+    /// the right orientation, and transposing it for the top and bottom orientations
+    fn orient_cell(
+        cell: Position,
+        available_area: &Position,
+        orientation: &LayoutOrientation,
+    ) -> Position {
+        let transposed = || {
+            Position::new(
+                available_area.x.add(cell.y.sub(available_area.y)),
+                available_area.y.add(cell.x.sub(available_area.x)),
+                cell.height,
+                cell.width,
+            )
+        };
+
+        match orientation {
+            LayoutOrientation::Left => cell.clone(),
+            LayoutOrientation::Right => Position::new(
+                available_area.left() + available_area.right() - cell.right(),
+                cell.y,
+                cell.width,
+                cell.height,
+            ),
+            LayoutOrientation::Top => transposed(),
+            LayoutOrientation::Bottom => {
+                let cell = transposed();
+                Position::new(
+                    cell.x,
+                    available_area.top() + available_area.bottom() - cell.bottom(),
+                    cell.width,
+                    cell.height,
+                )
+            }
+        }
+    }
+
+    /// the master column is split vertically among the master clients, each taking a share
+    /// of it proportional to its weight
+    fn main_cell(
+        available_area: &Position,
+        index: usize,
+        weights: &[f32],
         main_width: u32,
-        config: &Rc<RefCell<Config>>,
-    ) {
-        let border_double = config.borrow().border_width().mul(2) as u32;
-        let frame_position = Position::new(
+    ) -> Position {
+        let (position_y, height) = Self::split_length(available_area.height, index, weights);
+
+        Position::new(
             available_area.x,
-            available_area.y,
-            main_width.sub(border_double),
-            available_area.height.sub(border_double),
-        );
-        let client_position = Position::new(
-            0,
-            0,
-            main_width.sub(config.borrow().border_width() as u32),
-            available_area
-                .height
-                .sub(config.borrow().border_width() as u32),
-        );
+            available_area.y.add(position_y),
+            main_width,
+            height,
+        )
+    }
 
-        Self::configure_window(conn, client.frame, frame_position);
-        Self::configure_window(conn, client.window, client_position);
+    /// the stack column takes whatever the master column left and is split among the stacked
+    /// clients by their weights, one above the other or side by side depending on
+    /// `stack_direction`
+    fn side_cell(
+        available_area: &Position,
+        index: usize,
+        weights: &[f32],
+        main_width: u32,
+        stack_direction: &StackDirection,
+    ) -> Position {
+        let stack_x = available_area.x.add(main_width as i32);
+        let stack_width = available_area.width.sub(main_width);
+
+        match stack_direction {
+            StackDirection::Vertical => {
+                let (position_y, height) =
+                    Self::split_length(available_area.height, index, weights);
+                Position::new(
+                    stack_x,
+                    available_area.y.add(position_y),
+                    stack_width,
+                    height,
+                )
+            }
+            StackDirection::Horizontal => {
+                let (position_x, width) = Self::split_length(stack_width, index, weights);
+                Position::new(
+                    stack_x.add(position_x),
+                    available_area.y,
+                    width,
+                    available_area.height,
+                )
+            }
+        }
+    }
 
-        xcb_map_win!(conn, client.window);
-        xcb_map_win!(conn, client.frame);
+    /// the offset and size of the slice at `index` when `length` is split into slices sized
+    /// after `weights`. The pixels lost to rounding go to the first slices, one each, so the
+    /// slices never overlap and the last one ends exactly where `length` does
+    fn split_length(length: u32, index: usize, weights: &[f32]) -> (i32, u32) {
+        let total = weights.iter().map(|&weight| weight as f64).sum::<f64>();
+        if total <= 0.0 {
+            return (0, length);
+        }
+
+        let sizes = weights
+            .iter()
+            .map(|&weight| (length as f64 * weight as f64 / total) as u32)
+            .collect::<Vec<_>>();
+        let remainder = length.saturating_sub(sizes.iter().sum());
+        let size = |i: usize| sizes[i] + u32::from((i as u32) < remainder);
+        let offset = (0..index).map(size).sum::<u32>();
+
+        (offset as i32, size(index))
+    }
+
+    /// fits a client inside of its cell, a cell is the slice of the available area that was
+    /// assigned to the client, already shrunk by the inner gap. Returns the outer geometry of
+    /// the frame, which can be smaller than the cell when the client asked for less
+    fn fit_client(client: &Client, cell: Position, border_width: u32) -> Position {
+        let border_double = border_width.mul(2);
+        let width = cell.width.saturating_sub(border_double);
+        let height = cell.height.saturating_sub(border_double);
+        // a client can ask for less than its cell but never for more, otherwise it would
+        // overlap its neighbours
+        let (hinted_width, hinted_height) = client.size_hints.constrain(width, height);
+        let (width, height) = (hinted_width.min(width), hinted_height.min(height));
+
+        Position::new(
+            cell.x,
+            cell.y,
+            width.add(border_double),
+            height.add(border_double),
+        )
     }
 
-    fn display_side_client(
+    /// sends the geometry computed for a client to the server, `position` is the outer
+    /// geometry of its frame
+    fn display_client(
         conn: &Arc<xcb::Connection>,
         client: &Client,
-        available_area: &Position,
-        index: usize,
-        total: usize,
-        main_width: u32,
-        config: &Rc<RefCell<Config>>,
+        position: &Position,
+        border_width: u32,
     ) {
-        let width = available_area.width.sub(main_width);
-        let total_siblings = total.sub(1);
-        let height = available_area.height.div_ceil(total_siblings as u32);
-        let sibling_index = index.sub(1);
-        let border_double = config.borrow().border_width().mul(2) as u32;
-        let position_y = height.mul(sibling_index as u32) as i32;
-
-        let height = height.sub(border_double);
+        let border_double = border_width.mul(2);
+        let width = position.width.saturating_sub(border_double);
+        let height = position.height.saturating_sub(border_double);
 
-        Self::configure_window(
+        configure_frame(
             conn,
             client.frame,
-            Position::new(
-                available_area.x.add(main_width as i32),
-                available_area.y.add(position_y),
-                width.sub(border_double),
-                height,
-            ),
-        );
-        Self::configure_window(
-            conn,
-            client.window,
-            Position::new(0, 0, width.sub(border_double), height),
+            Position::new(position.x, position.y, width, height),
+            border_width,
         );
+        configure_window(conn, client.window, Position::new(0, 0, width, height));
 
         xcb_map_win!(conn, client.window);
         xcb_map_win!(conn, client.frame);
     }
 
+    /// half of the gap is taken from each of the two clients sharing an edge, edges touching the
+    /// boundaries of the available area are left as is, as those are handled by the outer gap
+    fn apply_inner_gap(cell: Position, available_area: &Position, gap: u32) -> Position {
+        let half = gap.div(2);
+        let left = if cell.left() > available_area.left() {
+            half
+        } else {
+            0
+        };
+        let top = if cell.top() > available_area.top() {
+            half
+        } else {
+            0
+        };
+        let right = if cell.right() < available_area.right() {
+            gap.sub(half)
+        } else {
+            0
+        };
+        let bottom = if cell.bottom() < available_area.bottom() {
+            gap.sub(half)
+        } else {
+            0
+        };
+
+        Position::new(
+            cell.x.add(left as i32),
+            cell.y.add(top as i32),
+            cell.width.saturating_sub(left + right),
+            cell.height.saturating_sub(top + bottom),
+        )
+    }
+
     fn is_first(screen: &mut Screen, client: xcb::x::Window) -> bool {
         screen
             .active_workspace()
@@ -165,6 +352,8 @@ impl TallLayout {
         Ok(())
     }
 
+    /// swaps the client with the one before it, the first client has nothing to swap with and
+    /// is left in place
     fn swap_prev(screen: &mut Screen, client: xcb::x::Window) -> anyhow::Result<()> {
         let index = screen
             .active_workspace()
@@ -173,14 +362,18 @@ impl TallLayout {
             .position(|c| c.eq(&client))
             .context("workspace clients vector should include selected client")?;
 
-        screen
-            .active_workspace_mut()
-            .clients_mut()
-            .swap(index, index.sub(1));
+        if let Some(prev) = index.checked_sub(1) {
+            screen
+                .active_workspace_mut()
+                .clients_mut()
+                .swap(index, prev);
+        }
 
         Ok(())
     }
 
+    /// swaps the client with the one after it, the last client has nothing to swap with and is
+    /// left in place
     fn swap_next(screen: &mut Screen, client: xcb::x::Window) -> anyhow::Result<()> {
         let index = screen
             .active_workspace()
@@ -189,10 +382,10 @@ impl TallLayout {
             .position(|c| c.eq(&client))
             .context("workspace clients vector should include selected client")?;
 
-        screen
-            .active_workspace_mut()
-            .clients_mut()
-            .swap(index, index.add(1));
+        let clients = screen.active_workspace_mut().clients_mut();
+        if index.add(1).lt(&clients.len()) {
+            clients.swap(index, index.add(1));
+        }
 
         Ok(())
     }
@@ -216,6 +409,7 @@ impl TallLayout {
         last_client
     }
 
+    /// focuses the client before the given one, the first client keeps the focus
     fn focus_prev(screen: &mut Screen, client: xcb::x::Window) -> Option<xcb::x::Window> {
         let index = screen
             .active_workspace()
@@ -224,17 +418,20 @@ impl TallLayout {
             .position(|c| c.eq(&client))
             .expect("workspace clients vector should include selected client");
 
-        let client = screen
-            .active_workspace()
-            .clients()
-            .get(index.sub(1))
-            .copied();
+        let client = index
+            .checked_sub(1)
+            .and_then(|prev| screen.active_workspace().clients().get(prev))
+            .copied()
+            .unwrap_or(client);
 
-        screen.active_workspace_mut().set_focused_client(client);
+        screen
+            .active_workspace_mut()
+            .set_focused_client(Some(client));
 
-        client
+        Some(client)
     }
 
+    /// focuses the client after the given one, the last client keeps the focus
     fn focus_next(screen: &mut Screen, client: xcb::x::Window) -> Option<xcb::x::Window> {
         let index = screen
             .active_workspace()
@@ -247,11 +444,14 @@ impl TallLayout {
             .active_workspace()
             .clients()
             .get(index.add(1))
-            .copied();
+            .copied()
+            .unwrap_or(client);
 
-        screen.active_workspace_mut().set_focused_client(client);
+        screen
+            .active_workspace_mut()
+            .set_focused_client(Some(client));
 
-        client
+        Some(client)
     }
 
     /// focus a client in a given direction. Possibly focusing a client on an adjacent
@@ -351,9 +551,7 @@ impl TallLayout {
         };
 
         if should_change_screen {
-            let Some(new_screen) = screen_manager.get_relative_screen_idx(direction) else {
-                return None;
-            };
+            let new_screen = screen_manager.get_relative_screen_idx(direction)?;
 
             screen_manager
                 .screen_mut(index)
@@ -375,21 +573,104 @@ impl TallLayout {
             Direction::Down => Self::swap_next(screen, client),
             Direction::Up => Self::swap_prev(screen, client),
             Direction::Right => Self::swap_next(screen, client),
-        };
+        }
+        .ok();
 
         None
     }
 
-    fn configure_window(conn: &Arc<xcb::Connection>, window: xcb::x::Window, client_pos: Position) {
-        conn.send_request(&xcb::x::ConfigureWindow {
-            window,
-            value_list: &[
-                xcb::x::ConfigWindow::X(client_pos.x),
-                xcb::x::ConfigWindow::Y(client_pos.y),
-                xcb::x::ConfigWindow::Width(client_pos.width),
-                xcb::x::ConfigWindow::Height(client_pos.height),
-            ],
-        });
+    /// swaps the focused client with the master client, which is the first client on the
+    /// workspace. Does nothing when the focused client already is the master
+    pub fn swap_with_master(screen_manager: &mut ScreenManager) -> anyhow::Result<()> {
+        let index = screen_manager.active_screen_idx();
+        let screen = screen_manager.screen_mut(index);
+
+        let Some(client) = screen.focused_client() else {
+            return Ok(());
+        };
+
+        if Self::is_first(screen, client) {
+            return Ok(());
+        }
+
+        Self::swap_first(screen, client)
+    }
+
+    /// maps a direction on the screen to the one it points at on the layout area, where the
+    /// master column always sits on the left
+    fn layout_direction(direction: Direction, orientation: &LayoutOrientation) -> Direction {
+        match (orientation, direction) {
+            (LayoutOrientation::Left, direction) => direction,
+            (LayoutOrientation::Right, Direction::Left) => Direction::Right,
+            (LayoutOrientation::Right, Direction::Right) => Direction::Left,
+            (LayoutOrientation::Right, direction) => direction,
+            (LayoutOrientation::Top | LayoutOrientation::Bottom, Direction::Left) => Direction::Up,
+            (LayoutOrientation::Top | LayoutOrientation::Bottom, Direction::Right) => {
+                Direction::Down
+            }
+            (LayoutOrientation::Top, Direction::Up)
+            | (LayoutOrientation::Bottom, Direction::Down) => Direction::Left,
+            (LayoutOrientation::Top, Direction::Down)
+            | (LayoutOrientation::Bottom, Direction::Up) => Direction::Right,
+        }
+    }
+
+    /// moves a border of `client` towards `direction` by `resize_step`, the one shared with
+    /// its neighbour on that side, or the opposite one when there is no such neighbour.
+    ///
+    /// Borders between clients of the same column change how the column is split among them,
+    /// while the border between the master and the stack changes the master ratio. `tiled`
+    /// holds the frames of the tiled clients of the active workspace, in layout order
+    pub fn resize_client(
+        screen: &mut Screen,
+        tiled: &[xcb::x::Window],
+        client: xcb::x::Window,
+        direction: Direction,
+        config: &Config,
+    ) {
+        let Some(index) = tiled.iter().position(|frame| frame.eq(&client)) else {
+            return;
+        };
+
+        let workspace = screen.active_workspace_mut();
+        let main_total = workspace.master_count().min(tiled.len());
+        let in_master = index < main_total;
+        let column = if in_master {
+            0..main_total
+        } else {
+            main_total..tiled.len()
+        };
+        let vertical = in_master || matches!(workspace.stack_direction(), StackDirection::Vertical);
+        let direction = Self::layout_direction(direction, &config.layout_orientation());
+        let forward = matches!(direction, Direction::Down | Direction::Right);
+        let along_column = vertical.eq(&matches!(direction, Direction::Up | Direction::Down));
+        // the first client of a horizontal stack has the master column to its left
+        let borders_master = !vertical && !forward && index.eq(&column.start);
+        let step = config.resize_step();
+
+        if along_column && !borders_master {
+            if column.len() < 2 {
+                return;
+            }
+            let (before, after) = match forward {
+                true if index + 1 < column.end => (index, index + 1),
+                true => (index - 1, index),
+                false if index > column.start => (index - 1, index),
+                false => (index, index + 1),
+            };
+            let (grow, shrink) = if forward {
+                (before, after)
+            } else {
+                (after, before)
+            };
+            workspace.resize_pair(tiled[grow], tiled[shrink], step);
+            return;
+        }
+
+        let has_stack = tiled.len() > main_total;
+        if has_stack && matches!(direction, Direction::Left | Direction::Right) {
+            workspace.adjust_master_ratio(config, if forward { step } else { -step });
+        }
     }
 }
 
@@ -418,8 +699,8 @@ mod tests {
 
         let (frame_a, client_a) = create_fake_client();
         let (frame_b, client_b) = create_fake_client();
-        screen_manager.create_client(frame_a, client_a);
-        screen_manager.create_client(frame_b, client_b);
+        screen_manager.create_client(frame_a, client_a, None, None, None);
+        screen_manager.create_client(frame_b, client_b, None, None, None);
         let screen = screen_manager.screen_mut(0);
         let workspace = screen.active_workspace_mut();
 
@@ -463,4 +744,345 @@ mod tests {
         let screen = screen_manager.screen_mut(0);
         assert!(screen.focused_client().eq(&Some(frame_a)));
     }
+
+    #[test]
+    fn test_neighbours_at_the_edges() {
+        let config = Rc::new(RefCell::new(Config::default()));
+        let root = unsafe { xcb::x::Window::new(0) };
+        let screen_positions = vec![Screen::new(&config, Position::new(0, 0, 100, 100))];
+        let mut screen_manager = ScreenManager::new(screen_positions, config, root);
+
+        let (frame_a, client_a) = create_fake_client();
+        let (frame_b, client_b) = create_fake_client();
+        screen_manager.create_client(frame_a, client_a, None, None, None);
+        screen_manager.create_client(frame_b, client_b, None, None, None);
+        let screen = screen_manager.screen_mut(0);
+
+        // the last client has nothing after it, so it keeps both its place and the focus
+        TallLayout::swap_next(screen, frame_b).unwrap();
+        assert_eq!(screen.active_workspace().clients(), &[frame_a, frame_b]);
+        assert_eq!(TallLayout::focus_next(screen, frame_b), Some(frame_b));
+        assert_eq!(screen.focused_client(), Some(frame_b));
+
+        // and the same goes for the first client, which has nothing before it
+        TallLayout::swap_prev(screen, frame_a).unwrap();
+        assert_eq!(screen.active_workspace().clients(), &[frame_a, frame_b]);
+        assert_eq!(TallLayout::focus_prev(screen, frame_a), Some(frame_a));
+        assert_eq!(screen.focused_client(), Some(frame_a));
+    }
+
+    #[test]
+    fn test_swap_with_master() {
+        let config = Rc::new(RefCell::new(Config::default()));
+        let root = unsafe { xcb::x::Window::new(0) };
+        let screen_positions = vec![Screen::new(&config, Position::new(0, 0, 100, 100))];
+        let mut screen_manager = ScreenManager::new(screen_positions, config, root);
+
+        let (frame_a, client_a) = create_fake_client();
+        let (frame_b, client_b) = create_fake_client();
+        let (frame_c, client_c) = create_fake_client();
+        screen_manager.create_client(frame_a, client_a, None, None, None);
+        screen_manager.create_client(frame_b, client_b, None, None, None);
+        screen_manager.create_client(frame_c, client_c, None, None, None);
+
+        // ┌──────────┐┌──────────┐
+        // │          ││    b     │
+        // │    a     │├──────────┤
+        // │          ││ selected │
+        // └──────────┘└──────────┘
+        let workspace = screen_manager.screen_mut(0).active_workspace_mut();
+        workspace.set_focused_client(Some(frame_c));
+        TallLayout::swap_with_master(&mut screen_manager).unwrap();
+
+        // ┌──────────┐┌──────────┐
+        // │          ││    b     │
+        // │ selected │├──────────┤
+        // │          ││    a     │
+        // └──────────┘└──────────┘
+        let workspace = screen_manager.screen(0).active_workspace();
+        assert_eq!(workspace.clients(), &[frame_c, frame_b, frame_a]);
+        assert!(screen_manager.screen(0).focused_client().eq(&Some(frame_c)));
+
+        // the master is already focused, so nothing changes
+        TallLayout::swap_with_master(&mut screen_manager).unwrap();
+        let workspace = screen_manager.screen(0).active_workspace();
+        assert_eq!(workspace.clients(), &[frame_c, frame_b, frame_a]);
+    }
+
+    #[test]
+    fn test_multiple_master_clients() {
+        let area = Position::new(0, 0, 100, 100);
+        let (total, master_count) = (4, 2);
+        let main_width = TallLayout::main_width(&area, total, master_count, 0.5);
+
+        // ┌────┐┌────┐
+        // │ m0 ││ s0 │
+        // ├────┤├────┤
+        // │ m1 ││ s1 │
+        // └────┘└────┘
+        let cells = (0..total)
+            .map(|i| match i {
+                _ if i < master_count => TallLayout::main_cell(&area, i, &[1.0; 2], main_width),
+                _ => TallLayout::side_cell(
+                    &area,
+                    i - master_count,
+                    &[1.0; 2],
+                    main_width,
+                    &StackDirection::Vertical,
+                ),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            cells,
+            vec![
+                Position::new(0, 0, 50, 50),
+                Position::new(0, 50, 50, 50),
+                Position::new(50, 0, 50, 50),
+                Position::new(50, 50, 50, 50),
+            ]
+        );
+
+        // with as many clients as masters, the master column takes the whole width
+        assert_eq!(TallLayout::main_width(&area, 2, master_count, 0.5), 100);
+    }
+
+    #[test]
+    fn test_layout_orientation() {
+        let area = Position::new(10, 20, 200, 100);
+        let orient = |orientation: LayoutOrientation| {
+            let layout_area = TallLayout::layout_area(&area, &orientation);
+            let main_width = TallLayout::main_width(&layout_area, 2, 1, 0.25);
+            let main = TallLayout::main_cell(&layout_area, 0, &[1.0], main_width);
+            let side = TallLayout::side_cell(
+                &layout_area,
+                0,
+                &[1.0],
+                main_width,
+                &StackDirection::Vertical,
+            );
+            (
+                TallLayout::orient_cell(main, &area, &orientation),
+                TallLayout::orient_cell(side, &area, &orientation),
+            )
+        };
+
+        assert_eq!(
+            orient(LayoutOrientation::Left),
+            (
+                Position::new(10, 20, 50, 100),
+                Position::new(60, 20, 150, 100)
+            )
+        );
+        assert_eq!(
+            orient(LayoutOrientation::Right),
+            (
+                Position::new(160, 20, 50, 100),
+                Position::new(10, 20, 150, 100)
+            )
+        );
+        assert_eq!(
+            orient(LayoutOrientation::Top),
+            (
+                Position::new(10, 20, 200, 25),
+                Position::new(10, 45, 200, 75)
+            )
+        );
+        assert_eq!(
+            orient(LayoutOrientation::Bottom),
+            (
+                Position::new(10, 95, 200, 25),
+                Position::new(10, 20, 200, 75)
+            )
+        );
+    }
+
+    #[test]
+    fn test_stack_direction() {
+        let area = Position::new(0, 0, 120, 100);
+        let cells =
+            |stack_direction: StackDirection| {
+                let main_width = TallLayout::main_width(&area, 3, 1, 0.5);
+                let mut cells = vec![TallLayout::main_cell(&area, 0, &[1.0], main_width)];
+                cells.extend((0..2).map(|i| {
+                    TallLayout::side_cell(&area, i, &[1.0; 2], main_width, &stack_direction)
+                }));
+                cells
+            };
+
+        // ┌────┐┌────┐
+        // │    ││ s0 │
+        // │ m  │├────┤
+        // │    ││ s1 │
+        // └────┘└────┘
+        assert_eq!(
+            cells(StackDirection::Vertical),
+            vec![
+                Position::new(0, 0, 60, 100),
+                Position::new(60, 0, 60, 50),
+                Position::new(60, 50, 60, 50),
+            ]
+        );
+
+        // ┌────┐┌─┐┌─┐
+        // │    ││ ││ │
+        // │ m  ││s││s│
+        // │    ││0││1│
+        // └────┘└─┘└─┘
+        assert_eq!(
+            cells(StackDirection::Horizontal),
+            vec![
+                Position::new(0, 0, 60, 100),
+                Position::new(60, 0, 30, 100),
+                Position::new(90, 0, 30, 100),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_side_cells_tile_the_stack() {
+        let area = Position::new(0, 10, 100, 103);
+        let cells = (0..5)
+            .map(|i| TallLayout::side_cell(&area, i, &[1.0; 5], 50, &StackDirection::Vertical))
+            .collect::<Vec<_>>();
+
+        assert_eq!(cells[0].top(), area.top());
+        for pair in cells.windows(2) {
+            assert_eq!(pair[0].bottom(), pair[1].top());
+        }
+        assert_eq!(cells[4].bottom(), area.bottom());
+        assert_eq!(
+            cells.iter().map(|cell| cell.height).collect::<Vec<_>>(),
+            vec![21, 21, 21, 20, 20]
+        );
+    }
+
+    #[test]
+    fn test_compute_positions() {
+        let config = Rc::new(RefCell::new(Config::default()));
+        let root = unsafe { xcb::x::Window::new(0) };
+        let screen_positions = vec![Screen::new(&config, Position::new(0, 0, 200, 100))];
+        let mut screen_manager = ScreenManager::new(screen_positions, config.clone(), root);
+
+        let (frame_a, client_a) = create_fake_client();
+        let (frame_b, client_b) = create_fake_client();
+        let (frame_c, client_c) = create_fake_client();
+        screen_manager.create_client(frame_a, client_a, None, None, None);
+        screen_manager.create_client(frame_b, client_b, None, None, None);
+        screen_manager.create_client(frame_c, client_c, None, None, None);
+
+        // ┌──────────┐┌──────────┐
+        // │          ││    b     │
+        // │    a     │├──────────┤
+        // │          ││    c     │
+        // └──────────┘└──────────┘
+        let screen = screen_manager.screen(0);
+        let clients = screen_manager.get_visible_screen_clients(screen);
+        let positions = TallLayout::compute_positions(screen, &clients, &config.borrow());
+
+        assert_eq!(
+            positions,
+            vec![
+                (frame_a, Position::new(0, 0, 100, 100)),
+                (frame_b, Position::new(100, 0, 100, 50)),
+                (frame_c, Position::new(100, 50, 100, 50)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_single_window_border() {
+        let config = Rc::new(RefCell::new(Config::default()));
+        let root = unsafe { xcb::x::Window::new(0) };
+        let screen_positions = vec![Screen::new(&config, Position::new(0, 0, 200, 100))];
+        let mut screen_manager = ScreenManager::new(screen_positions, config.clone(), root);
+
+        let (frame_a, client_a) = create_fake_client();
+        screen_manager.create_client(frame_a, client_a, None, None, None);
+
+        // the frame always covers the tiling area, the border is drawn inside of it, so the
+        // client gets whatever the border leaves
+        let client_area = |config: &Config| {
+            let screen = screen_manager.screen(0);
+            let clients = screen_manager.get_visible_screen_clients(screen);
+            let positions = TallLayout::compute_positions(screen, &clients, config);
+            assert_eq!(positions, vec![(frame_a, screen.get_tiling_area(config))]);
+
+            let border_double = TallLayout::border_width(clients[0], config, 1) * 2;
+            let (_, frame) = &positions[0];
+            (frame.width - border_double, frame.height - border_double)
+        };
+
+        let border_double = config.borrow().border_width() as u32 * 2;
+        assert_eq!(
+            client_area(&config.borrow()),
+            (200 - border_double, 100 - border_double)
+        );
+
+        config
+            .borrow_mut()
+            .set("single_window_border", "false")
+            .unwrap();
+        assert_eq!(client_area(&config.borrow()), (200, 100));
+    }
+
+    #[test]
+    fn test_resize_client() {
+        let config = Rc::new(RefCell::new(Config::default()));
+        let root = unsafe { xcb::x::Window::new(0) };
+        let screen_positions = vec![Screen::new(&config, Position::new(0, 0, 200, 100))];
+        let mut screen_manager = ScreenManager::new(screen_positions, config.clone(), root);
+
+        let (frame_a, client_a) = create_fake_client();
+        let (frame_b, client_b) = create_fake_client();
+        let (frame_c, client_c) = create_fake_client();
+        screen_manager.create_client(frame_a, client_a, None, None, None);
+        screen_manager.create_client(frame_b, client_b, None, None, None);
+        screen_manager.create_client(frame_c, client_c, None, None, None);
+        let tiled = [frame_a, frame_b, frame_c];
+
+        // the last client of the stack has no neighbour below, so its top border moves down
+        TallLayout::resize_client(
+            screen_manager.screen_mut(0),
+            &tiled,
+            frame_c,
+            Direction::Down,
+            &config.borrow(),
+        );
+        let workspace = screen_manager.screen(0).active_workspace();
+        assert!(workspace.weight(frame_b) > workspace.weight(frame_c));
+
+        // growing it back up gives it its space back
+        TallLayout::resize_client(
+            screen_manager.screen_mut(0),
+            &tiled,
+            frame_c,
+            Direction::Up,
+            &config.borrow(),
+        );
+        let workspace = screen_manager.screen(0).active_workspace();
+        assert!((workspace.weight(frame_b) - workspace.weight(frame_c)).abs() < f32::EPSILON);
+
+        // the border between the master and the stack moves the master ratio
+        TallLayout::resize_client(
+            screen_manager.screen_mut(0),
+            &tiled,
+            frame_b,
+            Direction::Left,
+            &config.borrow(),
+        );
+        let workspace = screen_manager.screen(0).active_workspace();
+        assert!(workspace.master_ratio(&config.borrow()) < config.borrow().master_ratio());
+
+        // a lone master column has nothing to trade space with
+        TallLayout::resize_client(
+            screen_manager.screen_mut(0),
+            &tiled,
+            frame_a,
+            Direction::Up,
+            &config.borrow(),
+        );
+        let workspace = screen_manager.screen(0).active_workspace();
+        assert_eq!(workspace.weight(frame_a), 1.0);
+    }
 }
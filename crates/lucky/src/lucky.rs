@@ -1,12 +1,24 @@
 use crate::{
-    atoms::Atoms, decorator::Decorator, event::EventContext, ewmh::ewmh_set_wm_hints,
-    handlers::Handlers, keyboard::Keyboard, layout_manager::LayoutManager, position::Position,
-    screen::Screen, screen_manager::ScreenManager,
+    atoms::Atoms,
+    cursor::{cursor_glyph, DEFAULT_CURSOR},
+    decorator::Decorator,
+    event::EventContext,
+    ewmh::ewmh_set_wm_hints,
+    handlers::Handlers,
+    ipc::IpcRequest,
+    keyboard::Keyboard,
+    layout_manager::LayoutManager,
+    position::Position,
+    screen::Screen,
+    screen_manager::ScreenManager,
+    session::Session,
+    xcb_utils::{xcb_destroy_win, xcb_map_win, xcb_unmap_win},
 };
 use anyhow::Context;
 use config::{AutoCommand, AvailableActions, Config};
 use std::{
     cell::RefCell,
+    os::unix::process::CommandExt,
     rc::Rc,
     sync::{
         mpsc::{channel, Sender},
@@ -32,27 +44,34 @@ pub struct Lucky {
 
 impl Lucky {
     #[tracing::instrument(skip_all, err)]
-    pub fn new() -> anyhow::Result<Self> {
+    pub fn new(config: Config) -> anyhow::Result<Self> {
         let (conn, _) = xcb::Connection::connect(None).context("failed to initialize self.conn to the X server. Check the DISPLAY environment variable")?;
         let conn = Arc::new(conn);
-        let config = Rc::new(RefCell::new(config::load_config()));
-        execute_auto_commands(config.borrow().startup_commands())
-            .context("failed to run startup commands")?;
+        let config = Rc::new(RefCell::new(config));
 
-        let root = Self::setup(&conn)?;
+        let root = Self::setup(&conn, config.borrow().cursor())?;
         let atoms = Atoms::new(&conn);
-        let screens = Self::get_monitors(&conn, root, &config)?;
-        let screen_manager = ScreenManager::new(screens, config.clone(), root);
+        let screens = Self::get_monitors(&conn, root, &config);
+        let mut screen_manager = ScreenManager::try_new(screens, config.clone(), root)
+            .context("failed to set up the screens")?;
 
         screen_manager.update_atoms(&atoms, &conn);
         ewmh_set_wm_hints(&conn, root, &atoms).context("failed to setup window manager hints")?;
 
         conn.flush().expect("failed to flush the connection");
 
+        // only spawn after we are the window manager, so bars and compositors started here
+        // find the root window already managed and the hints set. After a restart they are
+        // still running from the previous instance
+        if std::env::var_os(RESTARTED_VAR).is_none() {
+            execute_auto_commands(config.borrow().startup_commands());
+        }
+        std::env::remove_var(RESTARTED_VAR);
+
         Ok(Lucky {
             keyboard: Keyboard::new(&conn, config.clone(), root)?,
             layout_manager: LayoutManager::new(conn.clone(), config.clone()),
-            decorator: Decorator::new(conn.clone(), config.clone()),
+            decorator: Decorator::new(conn.clone(), config.clone(), atoms.clone()),
             atoms,
             handlers: Handlers::default(),
             screen_manager: Rc::new(RefCell::new(screen_manager)),
@@ -75,9 +94,51 @@ impl Lucky {
             }
         });
 
+        // the window manager is still usable without ipc, so failing to set it up is not fatal
+        if let Err(e) = crate::ipc::listen(event_tx) {
+            tracing::error!("failed to start ipc: {e:?}");
+        }
+
+        self.adopt_existing_windows(&action_tx)?;
+
+        if let Some(command) = self.config.borrow().on_ready() {
+            if let Err(e) = std::process::Command::new(command.command())
+                .args(command.args())
+                .spawn()
+            {
+                tracing::error!("failed to spawn on_ready command {:?}: {e:?}", command);
+            }
+        }
+
         loop {
-            if let Ok(AvailableActions::Reload) = action_rx.try_recv() {
+            let action = action_rx.try_recv();
+            if let Ok(AvailableActions::Restart) = action {
+                self.restart()?;
+            }
+
+            if let Ok(AvailableActions::Quit) = action {
+                return Ok(());
+            }
+
+            if let Ok(AvailableActions::Reload) = action {
+                self.save_session();
                 self.config.borrow_mut().update(config::load_config());
+                self.keyboard.regrab(
+                    &self.conn,
+                    &self.config.borrow(),
+                    self.screen_manager.borrow().root(),
+                );
+                self.decorator
+                    .refresh_borders(self.screen_manager.borrow().clients().values());
+                self.screen_manager
+                    .borrow_mut()
+                    .screens_mut()
+                    .iter_mut()
+                    .flat_map(|screen| screen.workspaces_mut())
+                    .for_each(|workspace| workspace.apply_configured_name(&self.config.borrow()));
+                self.screen_manager
+                    .borrow_mut()
+                    .update_atoms(&self.atoms, &self.conn);
                 self.layout_manager
                     .display_screens(&self.screen_manager, &self.decorator)
                     .expect("failed to redraw the screen");
@@ -98,13 +159,17 @@ impl Lucky {
                 if pointer_position.ne(&self.last_pointer_position) {
                     self.last_pointer_position = pointer_position;
                     self.screen_manager.borrow_mut().maybe_switch_screen(
-                        pointer_reply,
+                        (pointer_position.0.into(), pointer_position.1.into()),
                         &self.conn,
                         &self.atoms,
                     );
                 }
             }
 
+            if self.decorator.advance_fades() {
+                self.conn.flush().expect("failed to flush the connection");
+            }
+
             if let Ok(event) = event_rx.try_recv() {
                 match event {
                     XEvent::KeyPress(event) => self.handlers.on_key_press(EventContext {
@@ -118,6 +183,17 @@ impl Lucky {
                         layout_manager: &self.layout_manager,
                         action_tx: action_tx.clone(),
                     })?,
+                    XEvent::KeyRelease(event) => self.handlers.on_key_release(EventContext {
+                        event,
+                        conn: self.conn.clone(),
+                        keyboard: &self.keyboard,
+                        config: self.config.clone(),
+                        screen_manager: self.screen_manager.clone(),
+                        atoms: &self.atoms,
+                        decorator: &self.decorator,
+                        layout_manager: &self.layout_manager,
+                        action_tx: action_tx.clone(),
+                    })?,
                     XEvent::MapRequest(event) => self.handlers.on_map_request(EventContext {
                         event,
                         conn: self.conn.clone(),
@@ -177,7 +253,47 @@ impl Lucky {
                             action_tx: action_tx.clone(),
                         })?
                     }
-                    XEvent::ConfigureRequest(_) => todo!(),
+                    XEvent::ClientMessage(event) => {
+                        self.handlers.on_client_message(EventContext {
+                            event,
+                            conn: self.conn.clone(),
+                            keyboard: &self.keyboard,
+                            config: self.config.clone(),
+                            screen_manager: self.screen_manager.clone(),
+                            atoms: &self.atoms,
+                            decorator: &self.decorator,
+                            layout_manager: &self.layout_manager,
+                            action_tx: action_tx.clone(),
+                        })?
+                    }
+                    XEvent::MappingNotify => {
+                        self.keyboard.reload_keymap(&self.conn);
+                        self.keyboard.regrab(
+                            &self.conn,
+                            &self.config.borrow(),
+                            self.screen_manager.borrow().root(),
+                        );
+                    }
+                    XEvent::KeyboardStateNotify(event) => {
+                        if self.keyboard.update_state(&event) {
+                            self.keyboard.regrab(
+                                &self.conn,
+                                &self.config.borrow(),
+                                self.screen_manager.borrow().root(),
+                            );
+                        }
+                    }
+                    XEvent::Ipc(event) => self.handlers.on_ipc(EventContext {
+                        event,
+                        conn: self.conn.clone(),
+                        keyboard: &self.keyboard,
+                        config: self.config.clone(),
+                        screen_manager: self.screen_manager.clone(),
+                        atoms: &self.atoms,
+                        decorator: &self.decorator,
+                        layout_manager: &self.layout_manager,
+                        action_tx: action_tx.clone(),
+                    })?,
                 };
 
                 self.conn.flush().expect("failed to flush the connection");
@@ -185,8 +301,145 @@ impl Lucky {
         }
     }
 
+    /// manages the windows that were already on screen when we started, like the ones left by
+    /// a previous instance, and puts them back where the saved session says they were
+    fn adopt_existing_windows(
+        &mut self,
+        action_tx: &Sender<AvailableActions>,
+    ) -> anyhow::Result<()> {
+        let root = self.screen_manager.borrow().root();
+        let tree = self
+            .conn
+            .wait_for_reply(self.conn.send_request(&xcb::x::QueryTree { window: root }))
+            .context("failed to query the existing windows")?;
+
+        let windows = tree
+            .children()
+            .iter()
+            .copied()
+            .filter(|&window| {
+                self.conn
+                    .wait_for_reply(
+                        self.conn
+                            .send_request(&xcb::x::GetWindowAttributes { window }),
+                    )
+                    .is_ok_and(|attributes| {
+                        !attributes.override_redirect()
+                            && attributes.map_state() == xcb::x::MapState::Viewable
+                    })
+            })
+            .collect::<Vec<_>>();
+
+        for window in windows {
+            // the window is handled as if it was just asking to be mapped, unmapping it first
+            // keeps reparenting from reporting an unmap that would look like it went away
+            xcb_unmap_win!(self.conn, window);
+            self.handlers.on_map_request(EventContext {
+                event: xcb::x::MapRequestEvent::new(root, window),
+                conn: self.conn.clone(),
+                keyboard: &self.keyboard,
+                config: self.config.clone(),
+                screen_manager: self.screen_manager.clone(),
+                atoms: &self.atoms,
+                decorator: &self.decorator,
+                layout_manager: &self.layout_manager,
+                action_tx: action_tx.clone(),
+            })?;
+        }
+
+        if let Some(session) = Session::load() {
+            session.restore(
+                &mut self.screen_manager.borrow_mut(),
+                &self.conn,
+                &self.atoms,
+            );
+            self.screen_manager
+                .borrow_mut()
+                .update_atoms(&self.atoms, &self.conn);
+            self.layout_manager
+                .display_screens(&self.screen_manager, &self.decorator)?;
+        }
+
+        self.conn
+            .flush()
+            .context("failed to flush the connection")?;
+
+        Ok(())
+    }
+
+    /// replaces this process with whatever binary `lucky` now resolves to. Every client is
+    /// handed back to the root window first, for the new instance to adopt along with the
+    /// saved session. Only returns when the exec fails
+    fn restart(&mut self) -> anyhow::Result<()> {
+        self.save_session();
+        self.shutdown();
+
+        let mut args = std::env::args();
+        let program = args.next().unwrap_or_else(|| String::from("lucky"));
+        let e = std::process::Command::new(program)
+            .args(args)
+            .env(RESTARTED_VAR, "1")
+            .exec();
+        Err(e).context("failed to restart")
+    }
+
+    /// as ICCCM asks, every client is given back to the root window where it was last shown
+    /// and its frame is destroyed, otherwise the clients would go down with their frames and
+    /// whoever manages the display next could not adopt them. Clients are forgotten as they
+    /// are released, so calling this twice is harmless
+    fn shutdown(&mut self) {
+        let root = self.screen_manager.borrow().root();
+        let clients = self
+            .screen_manager
+            .borrow_mut()
+            .clients_mut()
+            .drain()
+            .map(|(_, client)| client)
+            .collect::<Vec<_>>();
+
+        // borders depend on the layout, like smart borders dropping them, so the width is
+        // read back from the server rather than taken from the configuration
+        let geometries = clients
+            .iter()
+            .map(|client| {
+                self.conn.send_request(&xcb::x::GetGeometry {
+                    drawable: xcb::x::Drawable::Window(client.frame),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for (client, geometry) in clients.into_iter().zip(geometries) {
+            let border_width = match self.conn.wait_for_reply(geometry) {
+                Ok(geometry) => geometry.border_width() as i32,
+                Err(_) => client.border_width(&self.config.borrow()) as i32,
+            };
+            // the window sits inside of the frame border, so it keeps its place on screen
+            let (x, y) = client.position.as_ref().map_or((0, 0), |position| {
+                (position.x + border_width, position.y + border_width)
+            });
+            self.conn.send_request(&xcb::x::ReparentWindow {
+                window: client.window,
+                parent: root,
+                x: x as i16,
+                y: y as i16,
+            });
+            xcb_map_win!(self.conn, client.window);
+            xcb_destroy_win!(self.conn, client.frame);
+        }
+
+        self.conn.flush().ok();
+    }
+
+    /// a failure to save only costs the arrangement on the next start, so it is not fatal
+    fn save_session(&self) {
+        let session = Session::capture(&self.screen_manager.borrow(), &self.conn, &self.atoms);
+        if let Err(e) = session.save() {
+            tracing::warn!("failed to save the session: {e:?}");
+        }
+    }
+
     #[tracing::instrument(skip_all, err)]
-    fn setup(conn: &Arc<xcb::Connection>) -> anyhow::Result<xcb::x::Window> {
+    fn setup(conn: &Arc<xcb::Connection>, cursor_name: &str) -> anyhow::Result<xcb::x::Window> {
         let screen = conn
             .get_setup()
             .roots()
@@ -201,13 +454,53 @@ impl Lucky {
         }))
         .context("failed to open cursor font")?;
 
+        let default_glyph = cursor_glyph(DEFAULT_CURSOR).expect("the default cursor must exist");
+
+        let glyph = cursor_glyph(cursor_name).unwrap_or_else(|| {
+            tracing::warn!("unknown cursor {cursor_name:?}, using {DEFAULT_CURSOR:?} instead");
+            default_glyph
+        });
+        let cursor = match Self::create_cursor(conn, font, glyph) {
+            Err(e) if glyph.ne(&default_glyph) => {
+                tracing::warn!(
+                    "failed to load cursor {cursor_name:?}, using {DEFAULT_CURSOR:?}: {e:?}"
+                );
+                Self::create_cursor(conn, font, default_glyph)?
+            }
+            cursor => cursor?,
+        };
+
+        conn.check_request(conn.send_request_checked(&ChangeWindowAttributes {
+            window: root,
+            value_list: &[
+                // entering the root window means the pointer moved onto a part of a monitor
+                // without any window, which may be another monitor
+                x::Cw::EventMask(
+                    x::EventMask::SUBSTRUCTURE_REDIRECT
+                        | x::EventMask::SUBSTRUCTURE_NOTIFY
+                        | x::EventMask::ENTER_WINDOW,
+                ),
+                x::Cw::Cursor(cursor),
+            ],
+        }))
+        .context("failed to subscribe for substructure redirection")?;
+
+        Ok(root)
+    }
+
+    /// creates a cursor from a glyph of the cursor font, masked by the glyph right after it
+    fn create_cursor(
+        conn: &Arc<xcb::Connection>,
+        font: xcb::x::Font,
+        glyph: u16,
+    ) -> anyhow::Result<xcb::x::Cursor> {
         let cursor = conn.generate_id();
         conn.check_request(conn.send_request_checked(&xcb::x::CreateGlyphCursor {
             cid: cursor,
             source_font: font,
             mask_font: font,
-            source_char: 68,
-            mask_char: 69,
+            source_char: glyph,
+            mask_char: glyph + 1,
             fore_red: 0,
             fore_green: 0,
             fore_blue: 0,
@@ -217,41 +510,75 @@ impl Lucky {
         }))
         .context("failed to create a cursor")?;
 
-        conn.check_request(conn.send_request_checked(&ChangeWindowAttributes {
-            window: root,
-            value_list: &[
-                x::Cw::EventMask(
-                    x::EventMask::SUBSTRUCTURE_REDIRECT | x::EventMask::SUBSTRUCTURE_NOTIFY,
-                ),
-                x::Cw::Cursor(cursor),
-            ],
-        }))
-        .context("failed to subscribe for substructure redirection")?;
-
-        Ok(root)
+        Ok(cursor)
     }
 
     fn get_monitors(
         conn: &Arc<xcb::Connection>,
         root: xcb::x::Window,
         config: &Rc<RefCell<Config>>,
-    ) -> anyhow::Result<Vec<Screen>> {
-        let total_screens = conn
+    ) -> Vec<Screen> {
+        Self::get_monitor_positions(conn, root)
+            .into_iter()
+            .map(|position| Screen::new(config, position))
+            .collect::<Vec<_>>()
+    }
+
+    /// asks RandR for the active monitors. Nested servers like Xephyr, or servers without
+    /// RandR at all, may fail to answer, in which case the whole root window is a single monitor
+    fn get_monitor_positions(conn: &Arc<xcb::Connection>, root: xcb::x::Window) -> Vec<Position> {
+        // requests to a missing extension make libxcb shut the connection down, so we have to
+        // ask whether it is there first
+        let has_randr = conn
+            .wait_for_reply(conn.send_request(&xcb::x::QueryExtension {
+                name: randr::XNAME.as_bytes(),
+            }))
+            .is_ok_and(|reply| reply.present());
+        if !has_randr {
+            tracing::warn!("RandR is not available, using the root window as the only monitor");
+            return Self::get_root_position(conn, root);
+        }
+
+        let monitors = conn
             .wait_for_reply(conn.send_request(&randr::GetMonitors {
                 window: root,
                 get_active: true,
             }))
-            .context("failed to get monitors")?;
+            .map(|reply| reply.monitors().map(Into::into).collect::<Vec<Position>>());
+
+        match monitors {
+            Ok(monitors) if !monitors.is_empty() => monitors,
+            Ok(_) => {
+                tracing::warn!("RandR reported no monitors, using the root window instead");
+                Self::get_root_position(conn, root)
+            }
+            Err(e) => {
+                tracing::warn!("failed to get monitors from RandR, using the root window: {e}");
+                Self::get_root_position(conn, root)
+            }
+        }
+    }
 
-        let screens = total_screens
-            .monitors()
-            .map(Into::into)
-            .collect::<Vec<Position>>()
+    fn get_root_position(conn: &Arc<xcb::Connection>, root: xcb::x::Window) -> Vec<Position> {
+        conn.get_setup()
+            .roots()
+            .find(|screen| screen.root().eq(&root))
+            .map(|screen| {
+                Position::new(
+                    0,
+                    0,
+                    screen.width_in_pixels().into(),
+                    screen.height_in_pixels().into(),
+                )
+            })
             .into_iter()
-            .map(|position| Screen::new(config, position))
-            .collect::<Vec<_>>();
+            .collect()
+    }
+}
 
-        Ok(screens)
+impl Drop for Lucky {
+    fn drop(&mut self) {
+        self.shutdown();
     }
 }
 
@@ -263,6 +590,9 @@ fn poll_events(conn: Arc<xcb::Connection>, event_tx: Sender<XEvent>) -> anyhow::
                 xcb::Event::X(xcb::x::Event::KeyPress(e)) => event_tx
                     .send(XEvent::KeyPress(e))
                     .context("failed to send event through channel")?,
+                xcb::Event::X(xcb::x::Event::KeyRelease(e)) => event_tx
+                    .send(XEvent::KeyRelease(e))
+                    .context("failed to send event through channel")?,
                 xcb::Event::X(xcb::x::Event::MapRequest(e)) => event_tx
                     .send(XEvent::MapRequest(e))
                     .context("failed to send event through channel")?,
@@ -278,6 +608,24 @@ fn poll_events(conn: Arc<xcb::Connection>, event_tx: Sender<XEvent>) -> anyhow::
                 xcb::Event::X(xcb::x::Event::PropertyNotify(e)) => event_tx
                     .send(XEvent::PropertyNotify(e))
                     .context("failed to send event through channel")?,
+                xcb::Event::X(xcb::x::Event::ClientMessage(e)) => event_tx
+                    .send(XEvent::ClientMessage(e))
+                    .context("failed to send event through channel")?,
+                xcb::Event::X(xcb::x::Event::MappingNotify(e))
+                    if e.request() == xcb::x::Mapping::Keyboard =>
+                {
+                    event_tx
+                        .send(XEvent::MappingNotify)
+                        .context("failed to send event through channel")?
+                }
+                xcb::Event::Xkb(
+                    xcb::xkb::Event::NewKeyboardNotify(_) | xcb::xkb::Event::MapNotify(_),
+                ) => event_tx
+                    .send(XEvent::MappingNotify)
+                    .context("failed to send event through channel")?,
+                xcb::Event::Xkb(xcb::xkb::Event::StateNotify(e)) => event_tx
+                    .send(XEvent::KeyboardStateNotify(e))
+                    .context("failed to send event through channel")?,
                 xcb::Event::X(xcb::x::Event::ConfigureRequest(_)) => {}
                 xcb::Event::RandR(xcb::randr::Event::Notify(e)) => {
                     tracing::trace!("from notify randr {e:?}")
@@ -292,26 +640,37 @@ fn poll_events(conn: Arc<xcb::Connection>, event_tx: Sender<XEvent>) -> anyhow::
     }
 }
 
-#[tracing::instrument(skip_all, err)]
-pub fn execute_auto_commands(auto_commands: &[AutoCommand]) -> anyhow::Result<()> {
+/// set on the environment of the instance that replaces us on a restart
+const RESTARTED_VAR: &str = "LUCKY_RESTARTED";
+
+/// spawns every autostart command, a command that fails to spawn is logged and skipped so it
+/// cannot prevent the window manager from starting
+#[tracing::instrument(skip_all)]
+pub fn execute_auto_commands(auto_commands: &[AutoCommand]) {
     for command in auto_commands {
         // TODO: we should store what failed to maybe display a notification
-        std::process::Command::new(command.command())
+        if let Err(e) = std::process::Command::new(command.command())
             .args(command.args())
             .spawn()
-            .context(format!("failed to spawn command {:?}", command))?;
+        {
+            tracing::error!("failed to spawn autostart command {:?}: {e:?}", command);
+        }
     }
-
-    Ok(())
 }
 
 #[derive(Debug)]
 pub enum XEvent {
     KeyPress(xcb::x::KeyPressEvent),
+    KeyRelease(xcb::x::KeyReleaseEvent),
     MapRequest(xcb::x::MapRequestEvent),
     DestroyNotify(xcb::x::DestroyNotifyEvent),
     EnterNotify(xcb::x::EnterNotifyEvent),
     UnmapNotify(xcb::x::UnmapNotifyEvent),
     PropertyNotify(xcb::x::PropertyNotifyEvent),
-    ConfigureRequest(xcb::x::ConfigureRequestEvent),
+    ClientMessage(xcb::x::ClientMessageEvent),
+    /// the keyboard, or its mapping, changed, either through the core protocol or through xkb
+    MappingNotify,
+    KeyboardStateNotify(xcb::xkb::StateNotifyEvent),
+    /// a command received through the ipc socket
+    Ipc(IpcRequest),
 }
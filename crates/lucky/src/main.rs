@@ -1,33 +1,104 @@
 mod atoms;
+mod cursor;
 mod decorator;
 mod event;
 mod ewmh;
 mod handlers;
+mod ipc;
 mod keyboard;
 mod layout_manager;
 mod lucky;
 mod position;
+mod query;
 mod screen;
 mod screen_manager;
+mod session;
 
 mod xcb_utils;
 
+use anyhow::Context;
+use config::Config;
 use lucky::Lucky;
+use std::path::{Path, PathBuf};
+use tracing_appender::non_blocking::WorkerGuard;
 use tracing_subscriber::FmtSubscriber;
 
 fn main() -> anyhow::Result<()> {
-    let (data_dir, log_file) = config::log_file()?;
-    let log_writer = tracing_appender::rolling::daily(data_dir, log_file);
-    let (non_blocking, _guard) = tracing_appender::non_blocking(log_writer);
-    let subscriber = FmtSubscriber::builder()
-        .with_max_level(tracing::Level::TRACE)
+    // `lucky msg <command>` talks to an already running instance instead of starting one,
+    // `lucky query` only reads what it advertises on the root window
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    match args.first().map(String::as_str) {
+        Some("msg") => std::process::exit(ipc::send_message(&args[1..])),
+        Some("query") => std::process::exit(query::print_state()),
+        Some("--check-config") => std::process::exit(check_config(args.get(1))),
+        _ => {}
+    }
+
+    // the logging setup depends on the config, so whatever is logged while loading it goes
+    // to stderr, warnings about the config included
+    let stderr = FmtSubscriber::builder()
         .with_ansi(false)
-        .with_writer(non_blocking)
+        .with_writer(std::io::stderr)
         .finish();
-    tracing::subscriber::set_global_default(subscriber).expect("Setting default subscriber failed");
+    let config = tracing::subscriber::with_default(stderr, config::load_config);
+    let _guard = setup_logging(&config)?;
 
-    let lucky = Lucky::new()?;
+    let lucky = Lucky::new(config)?;
     lucky.run()?;
 
     Ok(())
 }
+
+/// logs go to the file set on the `[log]` section of the config, or to stderr, which is also
+/// where they go when the file cannot be opened. The returned guard flushes whatever is still
+/// buffered when dropped, so it must live as long as we do
+fn setup_logging(config: &Config) -> anyhow::Result<WorkerGuard> {
+    let mut failure = None;
+    let (writer, guard) = match config.log_file().map(open_log_file) {
+        Some(Ok(file)) => tracing_appender::non_blocking(file),
+        Some(Err(e)) => {
+            failure = Some(e);
+            tracing_appender::non_blocking(std::io::stderr())
+        }
+        None => tracing_appender::non_blocking(std::io::stderr()),
+    };
+
+    let subscriber = FmtSubscriber::builder()
+        .with_max_level(config.log_level())
+        .with_ansi(false)
+        .with_writer(writer)
+        .finish();
+    tracing::subscriber::set_global_default(subscriber).context("failed to set up logging")?;
+
+    if let Some(e) = failure {
+        tracing::warn!("{e:#}, logging to stderr instead");
+    }
+
+    Ok(guard)
+}
+
+fn open_log_file(path: &Path) -> anyhow::Result<std::fs::File> {
+    if let Some(directory) = path.parent() {
+        std::fs::create_dir_all(directory)
+            .with_context(|| format!("failed to create {}", directory.display()))?;
+    }
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("failed to open the log file {}", path.display()))
+}
+
+/// validates the config without connecting to X, so it can be used over ssh or in scripts
+fn check_config(path: Option<&String>) -> i32 {
+    match config::check_config(path.map(PathBuf::from)) {
+        Ok(path) => {
+            println!("config OK: {}", path.display());
+            0
+        }
+        Err(e) => {
+            eprintln!("lucky: {e:#}");
+            1
+        }
+    }
+}
@@ -1,4 +1,6 @@
-#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Serialize, Deserialize)]
 pub struct Position {
     pub x: i32,
     pub y: i32,
@@ -46,6 +48,39 @@ impl Position {
     pub fn top(&self) -> i32 {
         self.y
     }
+
+    /// moves this position so that its center matches the center of `area`, keeping its size
+    pub fn centered_in(&self, area: &Position) -> Position {
+        let x = area.x + (area.width as i32 - self.width as i32) / 2;
+        let y = area.y + (area.height as i32 - self.height as i32) / 2;
+        Position::new(x, y, self.width, self.height)
+    }
+
+    /// shrinks this position to be at most as big as `area`, and then moves it so that it
+    /// fits entirely inside of `area`
+    pub fn clamp_within(&self, area: &Position) -> Position {
+        let width = self.width.min(area.width);
+        let height = self.height.min(area.height);
+        let x = self.x.clamp(area.left(), area.right() - width as i32);
+        let y = self.y.clamp(area.top(), area.bottom() - height as i32);
+        Position::new(x, y, width, height)
+    }
+
+    /// whether both positions share any area, touching edges don't count
+    pub fn overlaps(&self, other: &Position) -> bool {
+        self.left() < other.right()
+            && other.left() < self.right()
+            && self.top() < other.bottom()
+            && other.top() < self.bottom()
+    }
+
+    /// whether this position lies entirely inside of `area`
+    pub fn fits_within(&self, area: &Position) -> bool {
+        self.left() >= area.left()
+            && self.top() >= area.top()
+            && self.right() <= area.right()
+            && self.bottom() <= area.bottom()
+    }
 }
 
 impl std::fmt::Display for Position {
@@ -0,0 +1,46 @@
+use crate::atoms::Atoms;
+use crate::ewmh::{ewmh_get_active_window, ewmh_get_client_list, ewmh_get_current_desktop};
+use anyhow::Context;
+use std::sync::Arc;
+use xcb::Xid;
+
+/// prints the state the window manager advertises on the root window as a single line of
+/// json, which is enough for bars that poll instead of subscribing through `lucky msg`
+pub fn print_state() -> i32 {
+    match read_state() {
+        Ok(state) => {
+            println!("{state}");
+            0
+        }
+        Err(e) => {
+            eprintln!("lucky: {e:#}");
+            1
+        }
+    }
+}
+
+fn read_state() -> anyhow::Result<serde_json::Value> {
+    let (conn, screen_num) = xcb::Connection::connect(None)
+        .context("failed to connect to the X server. Check the DISPLAY environment variable")?;
+    let conn = Arc::new(conn);
+    let root = conn
+        .get_setup()
+        .roots()
+        .nth(screen_num as usize)
+        .context("failed to find the root window")?
+        .root();
+    let atoms = Atoms::new(&conn);
+
+    let active_window = ewmh_get_active_window(&conn, &atoms, root);
+    let current_desktop = ewmh_get_current_desktop(&conn, &atoms, root);
+    let clients = ewmh_get_client_list(&conn, &atoms, root)
+        .iter()
+        .map(Xid::resource_id)
+        .collect::<Vec<_>>();
+
+    Ok(serde_json::json!({
+        "active_window": active_window.as_ref().map(Xid::resource_id),
+        "current_desktop": current_desktop,
+        "clients": clients,
+    }))
+}
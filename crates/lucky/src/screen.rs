@@ -1,6 +1,7 @@
-use config::Config;
-use std::{cell::RefCell, rc::Rc};
+use config::{AvailableLayouts, Config, WorkspaceSettings};
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
+use crate::ewmh::SizeHints;
 use crate::position::Position;
 
 pub trait IntoClient {
@@ -14,6 +15,42 @@ pub struct Client {
     pub window: xcb::x::Window,
     pub workspace: u8,
     pub visible: bool,
+    /// whether the frame of this client should be drawn with a border
+    pub border: bool,
+    /// outer geometry of the frame, border included, when the client is floating. Floating
+    /// clients are not tiled by the workspace layout
+    pub floating: Option<Position>,
+    /// fullscreen clients cover their whole screen, reserved areas included, and are drawn
+    /// above every other client. Their tiled or floating placement is kept to be restored
+    pub fullscreen: bool,
+    /// size constraints from `WM_NORMAL_HINTS`, applied whenever the client is resized
+    pub size_hints: SizeHints,
+    /// clients demanding attention are drawn with the `urgent_border_color` until focused
+    pub urgent: bool,
+    /// `_NET_WM_PID` of the client, used to find the terminal it was launched from
+    pub pid: Option<u32>,
+    /// outer geometry of the frame, border included, as last placed by the layout. Unset until
+    /// the client is displayed for the first time
+    pub position: Option<Position>,
+    /// sticky clients are carried along to every workspace their screen switches to
+    pub sticky: bool,
+    /// clients of a class on `no_focus_classes` are displayed but never focused
+    pub focusable: bool,
+    /// increases with every client created, so clients can be listed in the order they were
+    /// managed in
+    pub sequence: u64,
+}
+
+impl Client {
+    /// clients can have their border toggled off, in which case the frame is drawn without any
+    /// border regardless of the configured `border_width`
+    pub fn border_width(&self, config: &Config) -> u32 {
+        if self.border {
+            config.border_width() as u32
+        } else {
+            0
+        }
+    }
 }
 
 impl IntoClient for Client {
@@ -52,6 +89,55 @@ impl IntoClient for ReservedClient {
 pub enum WorkspaceLayout {
     #[default]
     Tall,
+    /// every client takes the whole screen, only the focused one is visible
+    Monocle,
+}
+
+impl WorkspaceLayout {
+    /// the layout that comes after this one when cycling through layouts
+    pub fn next(&self) -> WorkspaceLayout {
+        match self {
+            WorkspaceLayout::Tall => WorkspaceLayout::Monocle,
+            WorkspaceLayout::Monocle => WorkspaceLayout::Tall,
+        }
+    }
+
+    /// whether clients on this layout leave the space reserved by docks uncovered
+    pub fn respects_struts(&self, config: &Config) -> bool {
+        match self {
+            WorkspaceLayout::Tall => config.tall_respect_struts(),
+            WorkspaceLayout::Monocle => config.monocle_respect_struts(),
+        }
+    }
+}
+
+/// how the stack area of the tall layout is split among its clients. Directions are given for
+/// the left and right orientations, the top and bottom ones transpose them
+#[derive(Default, Debug, Clone, PartialEq)]
+pub enum StackDirection {
+    /// stacked clients are placed one above the other
+    #[default]
+    Vertical,
+    /// stacked clients are placed side by side
+    Horizontal,
+}
+
+impl StackDirection {
+    pub fn toggled(&self) -> StackDirection {
+        match self {
+            StackDirection::Vertical => StackDirection::Horizontal,
+            StackDirection::Horizontal => StackDirection::Vertical,
+        }
+    }
+}
+
+impl From<AvailableLayouts> for WorkspaceLayout {
+    fn from(value: AvailableLayouts) -> Self {
+        match value {
+            AvailableLayouts::Tall => WorkspaceLayout::Tall,
+            AvailableLayouts::Monocle => WorkspaceLayout::Monocle,
+        }
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -61,16 +147,33 @@ pub struct Workspace {
     name: String,
     clients: Vec<xcb::x::Window>,
     focused_client: Option<xcb::x::Window>,
+    /// clients that were focused on this workspace, the most recently focused one last. Used
+    /// to give the focus back to the previous client when the focused one goes away
+    focus_history: Vec<xcb::x::Window>,
+    /// ratio of the master area set through the master actions, when unset the configured
+    /// `master_ratio` is used
+    master_ratio: Option<f32>,
+    /// how many clients share the master area of the layout
+    master_count: usize,
+    stack_direction: StackDirection,
+    /// how much of its column each tiled client takes relative to its neighbours, set through
+    /// the resize actions. Clients without a weight have a weight of `1.0`
+    weights: HashMap<xcb::x::Window, f32>,
 }
 
 impl Workspace {
-    pub fn new(id: u8) -> Self {
+    pub fn new(id: u8, name: Option<String>) -> Self {
         Workspace {
             id,
             layout: Default::default(),
-            name: format!("Workspace {}", id + 1),
+            name: name.unwrap_or_else(|| Self::default_name(id)),
             clients: vec![],
             focused_client: None,
+            focus_history: vec![],
+            master_ratio: None,
+            master_count: 1,
+            stack_direction: StackDirection::default(),
+            weights: HashMap::default(),
         }
     }
 
@@ -78,10 +181,27 @@ impl Workspace {
         &self.name
     }
 
+    fn default_name(id: u8) -> String {
+        format!("Workspace {}", id + 1)
+    }
+
+    /// renames the workspace to the name given to its index on `workspace_names`
+    pub fn apply_configured_name(&mut self, config: &Config) {
+        self.name = config
+            .workspace_names()
+            .get(self.id as usize)
+            .cloned()
+            .unwrap_or_else(|| Self::default_name(self.id));
+    }
+
     pub fn layout(&self) -> &WorkspaceLayout {
         &self.layout
     }
 
+    pub fn set_layout(&mut self, layout: WorkspaceLayout) {
+        self.layout = layout;
+    }
+
     pub fn id(&self) -> u8 {
         self.id
     }
@@ -98,15 +218,131 @@ impl Workspace {
         &mut self.clients
     }
 
+    /// puts `new` in the place of `old`, on the client list, the focus and the focus history
+    pub fn replace_client(&mut self, old: xcb::x::Window, new: xcb::x::Window) {
+        let replace = |client: &mut xcb::x::Window| {
+            if old.eq(client) {
+                *client = new;
+            }
+        };
+        self.clients.iter_mut().for_each(replace);
+        self.focus_history.iter_mut().for_each(replace);
+        self.focused_client.iter_mut().for_each(replace);
+        if let Some(weight) = self.weights.remove(&old) {
+            self.weights.insert(new, weight);
+        }
+    }
+
+    pub fn focused_client(&self) -> Option<xcb::x::Window> {
+        self.focused_client
+    }
+
     pub fn set_focused_client(&mut self, client: Option<xcb::x::Window>) {
+        if let Some(client) = client {
+            self.focus_history.retain(|other| other.ne(&client));
+            self.focus_history.push(client);
+        }
         self.focused_client = client
     }
 
+    fn settings<'a>(&self, config: &'a Config) -> Option<&'a WorkspaceSettings> {
+        config
+            .workspace_settings()
+            .iter()
+            .find(|settings| settings.index().eq(&self.id))
+    }
+
+    /// the gap between tiled clients, a `[[workspace]]` override wins over the global one
+    pub fn inner_gap(&self, config: &Config) -> u32 {
+        self.settings(config)
+            .and_then(WorkspaceSettings::inner_gap)
+            .unwrap_or(config.inner_gap())
+    }
+
+    /// the gap around tiled clients, a `[[workspace]]` override wins over the global one
+    pub fn outer_gap(&self, config: &Config) -> u32 {
+        self.settings(config)
+            .and_then(WorkspaceSettings::outer_gap)
+            .unwrap_or(config.outer_gap())
+    }
+
+    /// whether monocle clients cover the monitor without borders or gaps, a `[[workspace]]`
+    /// override wins over the global one
+    pub fn gapless_monocle(&self, config: &Config) -> bool {
+        self.settings(config)
+            .and_then(WorkspaceSettings::gapless_monocle)
+            .unwrap_or(config.gapless_monocle())
+    }
+
+    /// whether the clients leave the space reserved by docks uncovered, a `[[workspace]]`
+    /// override wins over the one of the layout
+    pub fn respects_struts(&self, config: &Config) -> bool {
+        self.settings(config)
+            .and_then(WorkspaceSettings::respect_struts)
+            .unwrap_or(self.layout.respects_struts(config))
+    }
+
+    pub fn master_ratio(&self, config: &Config) -> f32 {
+        self.master_ratio.unwrap_or(config.master_ratio())
+    }
+
+    /// grows or shrinks the master area by `step`, keeping it within the same bounds as the
+    /// configured `master_ratio`
+    pub fn adjust_master_ratio(&mut self, config: &Config, step: f32) {
+        self.master_ratio = Some((self.master_ratio(config) + step).clamp(0.1, 0.9));
+    }
+
+    pub fn master_count(&self) -> usize {
+        self.master_count
+    }
+
+    /// changes how many clients share the master area, there is always at least one and never
+    /// more than there are clients, so growing it past them is not undone by extra shrinks
+    pub fn adjust_master_count(&mut self, delta: isize) {
+        self.master_count = self
+            .master_count
+            .saturating_add_signed(delta)
+            .min(self.clients.len())
+            .max(1);
+    }
+
+    /// splits the clients evenly between the master and the stack, the master area takes the
+    /// extra client when there is an odd number of them
+    pub fn distribute_even(&mut self) {
+        self.master_count = self.clients.len().div_ceil(2).max(1);
+    }
+
+    pub fn weight(&self, client: xcb::x::Window) -> f32 {
+        self.weights.get(&client).copied().unwrap_or(1.0)
+    }
+
+    /// moves `step` of the space shared by `grow` and `shrink` from the latter to the former,
+    /// neither of them ever goes below a tenth of it
+    pub fn resize_pair(&mut self, grow: xcb::x::Window, shrink: xcb::x::Window, step: f32) {
+        let (grow_weight, shrink_weight) = (self.weight(grow), self.weight(shrink));
+        let total = grow_weight + shrink_weight;
+        let share = (grow_weight / total + step).clamp(0.1, 0.9);
+        self.weights.insert(grow, total * share);
+        self.weights.insert(shrink, total * (1.0 - share));
+    }
+
+    pub fn stack_direction(&self) -> &StackDirection {
+        &self.stack_direction
+    }
+
+    pub fn toggle_stack_direction(&mut self) {
+        self.stack_direction = self.stack_direction.toggled();
+    }
+
+    /// when the focused client is removed, the focus goes back to the client focused before
+    /// it, or to the first client when none of the remaining clients was ever focused
     pub fn remove_client(&mut self, client: xcb::x::Window) {
         self.clients.retain(|i| i.ne(&client));
-        self.focused_client
-            .is_some_and(|other| client.eq(&other))
-            .then(|| self.focused_client = None);
+        self.focus_history.retain(|other| other.ne(&client));
+        self.weights.remove(&client);
+        if self.focused_client.is_some_and(|other| client.eq(&other)) {
+            self.focused_client = self.focus_history.last().or(self.clients.first()).copied();
+        }
     }
 }
 
@@ -116,6 +352,8 @@ pub struct Screen {
     active_workspace: u8,
     workspaces: Vec<Workspace>,
     reserved_clients: Vec<ReservedClient>,
+    /// frames of the sticky clients on this screen
+    sticky_clients: Vec<xcb::x::Window>,
     reserved_left_area: u32,
     reserved_bottom_area: u32,
     reserved_top_area: u32,
@@ -132,8 +370,12 @@ impl Screen {
             reserved_top_area: 0,
             reserved_right_area: 0,
             reserved_clients: Vec::default(),
+            sticky_clients: Vec::default(),
             workspaces: (0..config.borrow().workspaces())
-                .map(Workspace::new)
+                .map(|id| {
+                    let name = config.borrow().workspace_names().get(id as usize).cloned();
+                    Workspace::new(id, name)
+                })
                 .collect(),
         }
     }
@@ -158,6 +400,20 @@ impl Screen {
         &mut self.workspaces
     }
 
+    /// appends the workspaces missing for the screen to have `count` of them
+    pub fn grow_workspaces(&mut self, config: &Config, count: usize) {
+        for id in self.workspaces.len()..count {
+            let name = config.workspace_names().get(id).cloned();
+            self.workspaces.push(Workspace::new(id as u8, name));
+        }
+    }
+
+    /// drops the workspaces past the first `count`, the active workspace is always kept
+    pub fn truncate_workspaces(&mut self, count: usize) {
+        self.workspaces
+            .truncate(count.max(self.active_workspace as usize + 1));
+    }
+
     pub fn active_workspace(&self) -> &Workspace {
         &self.workspaces[self.active_workspace as usize]
     }
@@ -170,10 +426,44 @@ impl Screen {
         self.active_workspace as usize
     }
 
+    /// sticky clients on the workspace being left are moved to the new one, keeping the focus
+    /// when the new workspace has no focused client of its own
     pub fn set_active_workspace(&mut self, workspace: u8) {
+        let previous = self.active_workspace as usize;
+        let next = workspace as usize;
+
+        if previous.ne(&next) {
+            for frame in self.sticky_clients.iter().copied() {
+                if !self.workspaces[previous].clients.contains(&frame) {
+                    continue;
+                }
+
+                let was_focused = self.workspaces[previous].focused_client.eq(&Some(frame));
+                self.workspaces[previous].remove_client(frame);
+                self.workspaces[next].new_client(frame);
+                if was_focused && self.workspaces[next].focused_client.is_none() {
+                    self.workspaces[next].set_focused_client(Some(frame));
+                }
+            }
+        }
+
         self.active_workspace = workspace;
     }
 
+    pub fn sticky_clients(&self) -> &[xcb::x::Window] {
+        &self.sticky_clients
+    }
+
+    pub fn add_sticky_client(&mut self, frame: xcb::x::Window) {
+        if !self.sticky_clients.contains(&frame) {
+            self.sticky_clients.push(frame);
+        }
+    }
+
+    pub fn remove_sticky_client(&mut self, frame: xcb::x::Window) {
+        self.sticky_clients.retain(|other| other.ne(&frame));
+    }
+
     pub fn position(&self) -> &Position {
         &self.position
     }
@@ -225,4 +515,108 @@ impl Screen {
         let height = self.position.height - self.reserved_top_area - self.reserved_bottom_area;
         Position::new(x, y, width, height)
     }
+
+    /// the area the layout of the active workspace places clients on, the available area when
+    /// it respects the struts of docks, or the whole screen otherwise
+    pub fn get_layout_area(&self, config: &Config) -> Position {
+        match self.active_workspace().respects_struts(config) {
+            true => self.get_available_area(),
+            false => self.position.clone(),
+        }
+    }
+
+    /// the area where clients can be tiled, this is the layout area shrunk by the outer gap
+    /// of the active workspace.
+    ///
+    /// when `outer_gap_on_reserved_edges` is disabled, edges that already have space reserved by
+    /// a bar skip the outer gap, so the gap between the bar and the clients isn't doubled
+    pub fn get_tiling_area(&self, config: &Config) -> Position {
+        let area = self.get_layout_area(config);
+        let respects_struts = self.active_workspace().respects_struts(config);
+        let gap = self.active_workspace().outer_gap(config);
+        let edge_gap = |reserved: u32| {
+            if respects_struts && reserved > 0 && !config.outer_gap_on_reserved_edges() {
+                0
+            } else {
+                gap
+            }
+        };
+
+        let left = edge_gap(self.reserved_left_area);
+        let right = edge_gap(self.reserved_right_area);
+        let top = edge_gap(self.reserved_top_area);
+        let bottom = edge_gap(self.reserved_bottom_area);
+
+        Position::new(
+            area.x + left as i32,
+            area.y + top as i32,
+            area.width.saturating_sub(left + right),
+            area.height.saturating_sub(top + bottom),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use xcb::XidNew;
+
+    #[test]
+    fn outer_gaps_are_measured_from_the_reserved_area() {
+        let config = Rc::new(RefCell::new(Config::default()));
+        let mut screen = Screen::new(&config, Position::new(0, 0, 1000, 1000));
+        screen.add_top_reserved_area(30);
+        config.borrow_mut().set("outer_gap", "10").unwrap();
+
+        let area = screen.get_tiling_area(&config.borrow());
+        assert_eq!(area, Position::new(10, 40, 980, 950));
+
+        config
+            .borrow_mut()
+            .set("outer_gap_on_reserved_edges", "false")
+            .unwrap();
+        let area = screen.get_tiling_area(&config.borrow());
+        assert_eq!(area, Position::new(10, 30, 980, 960));
+
+        // without struts the bar is covered, so the gap goes back to the edge of the screen
+        config
+            .borrow_mut()
+            .set("tall_respect_struts", "false")
+            .unwrap();
+        let area = screen.get_tiling_area(&config.borrow());
+        assert_eq!(area, Position::new(10, 10, 980, 980));
+    }
+
+    #[test]
+    fn distributing_evenly_gives_the_master_the_odd_client() {
+        let mut workspace = Workspace::new(0, None);
+        workspace.distribute_even();
+        assert_eq!(workspace.master_count(), 1);
+
+        for (clients, master_count) in [(1, 1), (4, 2), (5, 3)] {
+            let mut workspace = Workspace::new(0, None);
+            for id in 1..=clients {
+                workspace.new_client(unsafe { xcb::x::Window::new(id) });
+            }
+            workspace.distribute_even();
+            assert_eq!(workspace.master_count(), master_count, "{clients} clients");
+        }
+    }
+
+    #[test]
+    fn master_count_stays_within_the_clients() {
+        let mut workspace = Workspace::new(0, None);
+        workspace.adjust_master_count(1);
+        assert_eq!(workspace.master_count(), 1);
+
+        for id in 1..=3 {
+            workspace.new_client(unsafe { xcb::x::Window::new(id) });
+        }
+        workspace.adjust_master_count(5);
+        assert_eq!(workspace.master_count(), 3);
+        workspace.adjust_master_count(-1);
+        assert_eq!(workspace.master_count(), 2);
+        workspace.adjust_master_count(-5);
+        assert_eq!(workspace.master_count(), 1);
+    }
 }
@@ -1,12 +1,19 @@
 use crate::ewmh::*;
+use crate::ipc::Subscribers;
 use crate::position::Position;
 use crate::screen::{Client, Screen};
-use config::Config;
+use config::{
+    AvailableDirections, Config, FloatPlacement, NewClientMonitorFocus, WorkspaceScope,
+    WorkspaceSwitchFocus,
+};
+use std::os::unix::net::UnixStream;
 use std::{cell::RefCell, collections::HashMap, ops::Add, rc::Rc};
+use xcb::Xid;
 
 use std::sync::Arc;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Direction {
     Left,
     Down,
@@ -14,6 +21,17 @@ pub enum Direction {
     Right,
 }
 
+impl From<AvailableDirections> for Direction {
+    fn from(value: AvailableDirections) -> Self {
+        match value {
+            AvailableDirections::Left => Direction::Left,
+            AvailableDirections::Down => Direction::Down,
+            AvailableDirections::Up => Direction::Up,
+            AvailableDirections::Right => Direction::Right,
+        }
+    }
+}
+
 pub struct ScreenManager {
     screens: Vec<Screen>,
     clients: HashMap<xcb::x::Window, Client>,
@@ -21,18 +39,69 @@ pub struct ScreenManager {
     active_screen: usize,
     config: Rc<RefCell<Config>>,
     showing_desktop_status: EwmhShowingDesktop,
+    subscribers: Subscribers,
+    /// frame of the scratchpad client. While hidden, it is kept on the client list without
+    /// belonging to any workspace
+    scratchpad: Option<xcb::x::Window>,
+    /// frames of the clients that swallowed a terminal, paired with the frame of that terminal.
+    /// Swallowed terminals are kept on the client list without belonging to any workspace
+    swallowed: Vec<(xcb::x::Window, xcb::x::Window)>,
+    /// sequence given to the next client created
+    next_sequence: u64,
+    /// frames in the order they were last raised, from the bottom up
+    stacking: Vec<xcb::x::Window>,
+    /// frames in the order they were last focused across every screen, the most recent last
+    focus_history: Vec<xcb::x::Window>,
 }
 
 impl ScreenManager {
+    /// like `try_new`, panicking when there are no screens
+    #[cfg(test)]
     pub fn new(screens: Vec<Screen>, config: Rc<RefCell<Config>>, root: xcb::x::Window) -> Self {
-        ScreenManager {
+        Self::try_new(screens, config, root).expect("at least one screen is needed")
+    }
+
+    /// fails when there are no screens, as every client has to be placed on one of them.
+    ///
+    /// with global workspaces every monitor starts on a workspace of its own, as a workspace is
+    /// never shown on two monitors at once
+    pub fn try_new(
+        mut screens: Vec<Screen>,
+        config: Rc<RefCell<Config>>,
+        root: xcb::x::Window,
+    ) -> anyhow::Result<Self> {
+        if screens.is_empty() {
+            anyhow::bail!("there are no screens to manage");
+        }
+
+        // every monitor shows its own global workspace, so there are at least as many
+        // workspaces as there are monitors
+        if config
+            .borrow()
+            .workspace_scope()
+            .eq(&WorkspaceScope::Global)
+        {
+            let count = screens.len();
+            for (idx, screen) in screens.iter_mut().enumerate() {
+                screen.grow_workspaces(&config.borrow(), count);
+                screen.set_active_workspace(idx as u8);
+            }
+        }
+
+        Ok(ScreenManager {
             active_screen: 0,
             root,
             clients: HashMap::new(),
             showing_desktop_status: EwmhShowingDesktop::Hide,
+            subscribers: Subscribers::default(),
+            scratchpad: None,
+            swallowed: vec![],
+            next_sequence: 0,
+            stacking: vec![],
+            focus_history: vec![],
             screens,
             config,
-        }
+        })
     }
 
     pub fn root(&self) -> xcb::x::Window {
@@ -114,31 +183,316 @@ impl ScreenManager {
         self.active_screen = active_screen_idx
     }
 
-    /// Creates a new client on the active screen and active workspace on given screen
+    /// the client that comes after, or before, the focused one when walking through every
+    /// client in order of screen, workspace and position on the workspace, wrapping around at
+    /// the ends. Returns the screen and workspace the client is on along with its frame
+    pub fn cycle_target(&self, forward: bool) -> Option<(usize, u8, xcb::x::Window)> {
+        let clients = self
+            .screens
+            .iter()
+            .enumerate()
+            .flat_map(|(screen_idx, screen)| {
+                screen.workspaces().iter().flat_map(move |workspace| {
+                    workspace
+                        .clients()
+                        .iter()
+                        .map(move |frame| (screen_idx, workspace.id(), *frame))
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let total = clients.len();
+        let current = self.screens[self.active_screen]
+            .focused_client()
+            .and_then(|focused| clients.iter().position(|(_, _, frame)| frame.eq(&focused)));
+        let next = match (current, forward) {
+            (Some(idx), true) => (idx + 1) % total,
+            (Some(idx), false) => (idx + total - 1) % total,
+            (None, true) => 0,
+            (None, false) => total.checked_sub(1)?,
+        };
+
+        clients.get(next).copied()
+    }
+
+    /// creates `workspace` and the ones before it on `screen` when dynamic workspaces are
+    /// enabled, global workspaces being created on every monitor. Returns whether `workspace`
+    /// exists on `screen`
+    pub fn ensure_workspace(&mut self, screen: usize, workspace: usize) -> bool {
+        let config = self.config.borrow();
+        if config.dynamic_workspaces() && workspace < config.workspace_limit() as usize {
+            let global = config.workspace_scope().eq(&WorkspaceScope::Global);
+            for (idx, other) in self.screens.iter_mut().enumerate() {
+                if global || idx.eq(&screen) {
+                    other.grow_workspaces(&config, workspace + 1);
+                }
+            }
+        }
+
+        workspace < self.screens[screen].workspaces().len()
+    }
+
+    /// drops the trailing dynamic workspaces that hold no clients and are not shown, never
+    /// going below the configured amount. Global workspaces are only dropped once they are
+    /// unused on every monitor, so all monitors keep the same workspaces
+    fn collect_workspaces(&mut self) {
+        let config = self.config.borrow();
+        if !config.dynamic_workspaces() {
+            return;
+        }
+        let keep = config.workspaces() as usize;
+        let global = config.workspace_scope().eq(&WorkspaceScope::Global);
+        drop(config);
+
+        let used = |screen: &Screen| {
+            screen
+                .workspaces()
+                .iter()
+                .enumerate()
+                .rposition(|(id, workspace)| {
+                    id < keep
+                        || id.eq(&screen.active_workspace_id())
+                        || !workspace.clients().is_empty()
+                })
+                .map_or(0, |id| id + 1)
+        };
+        let used_anywhere = self.screens.iter().map(used).max().unwrap_or_default();
+
+        for screen in self.screens.iter_mut() {
+            match global {
+                true => screen.truncate_workspaces(used_anywhere),
+                false => screen.truncate_workspaces(used(screen)),
+            }
+        }
+    }
+
+    /// keeps track of the focused client across every screen and workspace. Called whenever the
+    /// state is published, which happens after anything that can move the focus
+    fn record_focus(&mut self) {
+        let clients = &self.clients;
+        self.focus_history
+            .retain(|frame| clients.contains_key(frame));
+
+        let Some(frame) = self.get_focused_client().map(|client| client.frame) else {
+            return;
+        };
+        self.focus_history.retain(|other| other.ne(&frame));
+        self.focus_history.push(frame);
+    }
+
+    /// the client focused before the current one, wherever it is, along with the screen and
+    /// workspace it is on
+    pub fn last_focused_target(&self) -> Option<(usize, u8, xcb::x::Window)> {
+        let focused = self.get_focused_client().map(|client| client.frame);
+
+        self.focus_history
+            .iter()
+            .rev()
+            .filter(|frame| focused.ne(&Some(**frame)))
+            .filter(|frame| {
+                self.clients
+                    .get(frame)
+                    .is_some_and(|client| client.focusable)
+            })
+            .find_map(|frame| {
+                self.screens
+                    .iter()
+                    .enumerate()
+                    .find_map(|(screen_idx, screen)| {
+                        screen
+                            .workspaces()
+                            .iter()
+                            .find(|workspace| workspace.clients().contains(frame))
+                            .map(|workspace| (screen_idx, workspace.id(), *frame))
+                    })
+            })
+    }
+
+    /// moves every client of the active workspace to the workspace shown on the screen at
+    /// `target`, which becomes the active screen. Floating clients keep their place relative
+    /// to the screen they are on
+    pub fn move_workspace_to_screen(&mut self, target: usize) {
+        let source = self.active_screen;
+        if source.eq(&target) || target >= self.screens.len() {
+            return;
+        }
+
+        let focused_client = self.screens[source].focused_client();
+        let workspace = self.screens[source].active_workspace_mut();
+        let frames = workspace.clients().to_vec();
+        for frame in frames.iter() {
+            workspace.remove_client(*frame);
+        }
+
+        let source_position = self.screens[source].position().clone();
+        let target_position = self.screens[target].position().clone();
+        let target_workspace = self.screens[target].active_workspace_mut();
+        let target_workspace_id = target_workspace.id();
+        for frame in frames.iter() {
+            target_workspace.new_client(*frame);
+        }
+        if focused_client.is_some() {
+            target_workspace.set_focused_client(focused_client);
+        }
+        for frame in frames.iter() {
+            if self.screens[source].sticky_clients().contains(frame) {
+                self.screens[source].remove_sticky_client(*frame);
+                self.screens[target].add_sticky_client(*frame);
+            }
+        }
+
+        self.active_screen = target;
+
+        for frame in frames {
+            let Some(client) = self.clients.get_mut(&frame) else {
+                continue;
+            };
+            client.workspace = target_workspace_id;
+
+            if let Some(geometry) = client.floating.clone() {
+                let geometry = Position::new(
+                    geometry.x - source_position.x + target_position.x,
+                    geometry.y - source_position.y + target_position.y,
+                    geometry.width,
+                    geometry.height,
+                );
+                self.float_client(frame, geometry);
+            }
+        }
+    }
+
+    /// moves a client to the end of a workspace on any screen, wherever it was before
+    pub fn place_client(&mut self, frame: xcb::x::Window, screen: usize, workspace: u8) {
+        let Some(client) = self.clients.get_mut(&frame) else {
+            return;
+        };
+        client.workspace = workspace;
+
+        self.screens
+            .iter_mut()
+            .flat_map(|screen| screen.workspaces_mut())
+            .for_each(|workspace| workspace.remove_client(frame));
+        self.screens[screen].workspaces_mut()[workspace as usize].new_client(frame);
+    }
+
+    /// Creates a new client on `screen` when given, or on the active screen otherwise, placed
+    /// on `workspace` when given, or on the active workspace of that screen otherwise
     ///
     /// When `focus_new_clients` is true on configuration, we also set the focus to the newly
-    /// created client
+    /// created client, `focus` overrides it when given, as set by the rule matching the client
     ///
     /// even when `focus_new_clients` is false, if the client is the only client on the workspace
     /// we focus it
-    pub fn create_client(&mut self, frame: xcb::x::Window, window: xcb::x::Window) {
+    ///
+    /// clients created on a screen other than the active one follow `new_client_monitor_focus`,
+    /// which can also make their screen the active one
+    pub fn create_client(
+        &mut self,
+        frame: xcb::x::Window,
+        window: xcb::x::Window,
+        screen: Option<usize>,
+        workspace: Option<u8>,
+        focus: Option<bool>,
+    ) {
+        let screen_idx = screen
+            .filter(|idx| *idx < self.screens.len())
+            .unwrap_or(self.active_screen);
+        let workspace_id = workspace
+            .filter(|id| self.ensure_workspace(screen_idx, *id as usize))
+            .unwrap_or(self.screens[screen_idx].active_workspace().id());
+
         self.clients.insert(
             frame,
             Client {
                 frame,
                 window,
                 visible: true,
-                workspace: self.screens[self.active_screen].active_workspace().id(),
+                border: true,
+                floating: None,
+                fullscreen: false,
+                size_hints: Default::default(),
+                urgent: false,
+                pid: None,
+                position: None,
+                sticky: false,
+                focusable: true,
+                workspace: workspace_id,
+                sequence: self.next_sequence,
             },
         );
+        self.next_sequence += 1;
+
+        let config = self.config.borrow();
+        let other_screen = screen_idx.ne(&self.active_screen);
+        let policy = config.new_client_monitor_focus();
+        let focus = focus.unwrap_or(config.focus_new_clients())
+            && !(other_screen && policy.eq(&NewClientMonitorFocus::Keep));
+        drop(config);
 
-        let screen = &mut self.screens[self.active_screen];
-        let workspace = screen.active_workspace_mut();
+        let screen = &mut self.screens[screen_idx];
+        let workspace = &mut screen.workspaces_mut()[workspace_id as usize];
         workspace.new_client(frame);
 
-        if self.config.borrow().focus_new_clients() || workspace.clients().len().eq(&1) {
+        if focus || workspace.clients().len().eq(&1) {
             workspace.set_focused_client(Some(frame));
         }
+
+        if focus && other_screen && policy.eq(&NewClientMonitorFocus::Switch) {
+            self.active_screen = screen_idx;
+        }
+    }
+
+    /// picks the client focused on the active workspace of `screen` after switching to it,
+    /// following `workspace_switch_focus`. The client under `cursor` is the topmost one,
+    /// floating clients being above the tiled ones
+    pub fn focus_after_switch(&mut self, screen: usize, cursor: Option<(i32, i32)>) {
+        let workspace = self.screens[screen].active_workspace();
+        let focusable = workspace
+            .clients()
+            .iter()
+            .filter_map(|frame| self.clients.get(frame))
+            .filter(|client| client.focusable)
+            .collect::<Vec<_>>();
+        let first = focusable.first().map(|client| client.frame);
+        let last = workspace.focused_client();
+
+        let target = match self.config.borrow().workspace_switch_focus() {
+            WorkspaceSwitchFocus::Last => last.or(first),
+            WorkspaceSwitchFocus::First => first,
+            WorkspaceSwitchFocus::Cursor => cursor
+                .and_then(|(x, y)| {
+                    let (floating, tiled): (Vec<&Client>, Vec<&Client>) = focusable
+                        .iter()
+                        .partition(|client| client.floating.is_some());
+                    floating
+                        .iter()
+                        .rev()
+                        .chain(tiled.iter())
+                        .find(|client| {
+                            client
+                                .position
+                                .as_ref()
+                                .is_some_and(|position| is_cursor_inside(x, y, position))
+                        })
+                        .map(|client| client.frame)
+                })
+                .or(last)
+                .or(first),
+        };
+
+        self.screens[screen]
+            .active_workspace_mut()
+            .set_focused_client(target);
+    }
+
+    /// the screen holding `frame` on any of its workspaces
+    pub fn client_screen(&self, frame: xcb::x::Window) -> Option<usize> {
+        self.screens.iter().position(|screen| {
+            screen
+                .workspaces()
+                .iter()
+                .any(|workspace| workspace.clients().contains(&frame))
+        })
     }
 
     /// Directly focus a client on any of the screens;
@@ -154,6 +508,7 @@ impl ScreenManager {
             .values()
             .find(|client| client.window.eq(&window) || client.frame.eq(&window))
         {
+            Some(client) if !client.focusable => {}
             Some(client) => {
                 self.screens.iter_mut().for_each(|screen| {
                     let workspace = screen.active_workspace_mut();
@@ -174,15 +529,304 @@ impl ScreenManager {
         None
     }
 
-    pub fn close_focused_client(&mut self) -> anyhow::Result<Option<Client>> {
-        let active_screen = &mut self.screens[self.active_screen];
-        if let Some(frame) = active_screen.focused_client() {
-            let workspace = active_screen.active_workspace_mut();
+    /// flips the border of the focused client, returning whether a client was focused at all
+    pub fn toggle_focused_client_border(&mut self) -> bool {
+        let Some(frame) = self.screens[self.active_screen].focused_client() else {
+            return false;
+        };
+
+        match self.clients.get_mut(&frame) {
+            Some(client) => {
+                client.border = !client.border;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// clients that are not focusable are still displayed, but the focus never lands on them.
+    /// When such a client was just focused, the focus moves to the last focusable client of
+    /// its workspace instead
+    pub fn set_focusable(&mut self, frame: xcb::x::Window, focusable: bool) {
+        let Some(client) = self.clients.get_mut(&frame) else {
+            return;
+        };
+        client.focusable = focusable;
+        if focusable {
+            return;
+        }
+
+        let clients = &self.clients;
+        for workspace in self
+            .screens
+            .iter_mut()
+            .flat_map(|screen| screen.workspaces_mut())
+            .filter(|workspace| workspace.focused_client().eq(&Some(frame)))
+        {
+            let fallback = workspace
+                .clients()
+                .iter()
+                .rev()
+                .find(|other| {
+                    clients
+                        .get(other)
+                        .is_some_and(|other| other.frame.ne(&frame) && other.focusable)
+                })
+                .copied();
+            workspace.set_focused_client(fallback);
+        }
+    }
+
+    pub fn set_border(&mut self, frame: xcb::x::Window, border: bool) {
+        if let Some(client) = self.clients.get_mut(&frame) {
+            client.border = border;
+        }
+    }
+
+    /// makes a client float with the given outer geometry. The geometry is clamped to the
+    /// available area of the screen holding the client, so oversized or offset windows are
+    /// shrunk and moved instead of spilling past its edges
+    pub fn float_client(&mut self, frame: xcb::x::Window, geometry: Position) {
+        let screen = self.client_screen(frame).unwrap_or(self.active_screen);
+        let area = self.screens[screen].get_available_area();
+        if let Some(client) = self.clients.get_mut(&frame) {
+            client.floating = Some(geometry.clamp_within(&area));
+        }
+    }
+
+    /// picks where a new floating client with the given outer size shows up on `screen`,
+    /// following `float_placement`. The cursor is only used by the `cursor` placement
+    pub fn place_floating(
+        &self,
+        screen: usize,
+        width: u32,
+        height: u32,
+        cursor: Option<(i32, i32)>,
+    ) -> Position {
+        let area = self.screens[screen].get_available_area();
+        let centered = Position::new(0, 0, width, height).centered_in(&area);
+        let placement = self.config.borrow().float_placement();
+
+        match placement {
+            FloatPlacement::Center => centered,
+            FloatPlacement::Cursor => cursor
+                .map(|(x, y)| {
+                    let (x, y) = (x - width as i32 / 2, y - height as i32 / 2);
+                    Position::new(x, y, width, height).clamp_within(&area)
+                })
+                .unwrap_or(centered),
+            FloatPlacement::Smart => self.smart_placement(screen, centered, &area),
+        }
+    }
+
+    /// tries the center of the screen, its top left corner and then the spots beside and
+    /// below every floating client, taking the first one that covers no other floating client
+    fn smart_placement(&self, screen: usize, centered: Position, area: &Position) -> Position {
+        let floating = self.screens[screen]
+            .active_workspace()
+            .clients()
+            .iter()
+            .filter_map(|frame| self.clients.get(frame)?.floating.clone())
+            .collect::<Vec<_>>();
+        let (width, height) = (centered.width, centered.height);
+
+        let beside_floating = floating.iter().flat_map(|other| {
+            [
+                Position::new(other.right(), other.top(), width, height),
+                Position::new(other.left(), other.bottom(), width, height),
+            ]
+        });
+
+        [
+            centered.clone(),
+            Position::new(area.x, area.y, width, height),
+        ]
+        .into_iter()
+        .chain(beside_floating)
+        .find(|candidate| {
+            candidate.fits_within(area) && !floating.iter().any(|other| candidate.overlaps(other))
+        })
+        .unwrap_or(centered)
+    }
+
+    pub fn scratchpad(&self) -> Option<xcb::x::Window> {
+        self.scratchpad
+    }
+
+    pub fn set_scratchpad(&mut self, frame: Option<xcb::x::Window>) {
+        self.scratchpad = frame;
+    }
+
+    /// the scratchpad is visible whenever it belongs to a workspace
+    pub fn is_scratchpad_visible(&self) -> bool {
+        self.scratchpad.is_some_and(|frame| {
+            self.screens
+                .iter()
+                .flat_map(|screen| screen.workspaces())
+                .any(|workspace| workspace.clients().contains(&frame))
+        })
+    }
+
+    /// moves the scratchpad to the active workspace, floating at the center of the active
+    /// screen with the given outer size, and focuses it
+    pub fn show_scratchpad(&mut self, width: u32, height: u32) {
+        let Some(frame) = self.scratchpad else {
+            return;
+        };
+
+        self.hide_scratchpad();
+
+        let area = self.screens[self.active_screen].get_available_area();
+        self.float_client(frame, Position::new(0, 0, width, height).centered_in(&area));
+
+        let workspace = self.screens[self.active_screen].active_workspace_mut();
+        workspace.new_client(frame);
+        workspace.set_focused_client(Some(frame));
+    }
+
+    /// takes the scratchpad out of every workspace, focus goes back to the client focused
+    /// before it
+    pub fn hide_scratchpad(&mut self) {
+        let Some(frame) = self.scratchpad else {
+            return;
+        };
+
+        for workspace in self
+            .screens
+            .iter_mut()
+            .flat_map(|screen| screen.workspaces_mut())
+            .filter(|workspace| workspace.clients().contains(&frame))
+        {
+            workspace.remove_client(frame);
+        }
+    }
+
+    /// the client owning `window`, which can be either its frame or the window it manages
+    pub fn find_client(&self, window: xcb::x::Window) -> Option<&Client> {
+        self.clients.get(&window).or_else(|| {
+            self.clients
+                .values()
+                .find(|client| client.window.eq(&window))
+        })
+    }
+
+    /// puts a floating client back under the control of the workspace layout
+    pub fn tile_client(&mut self, frame: xcb::x::Window) {
+        if let Some(client) = self.clients.get_mut(&frame) {
+            client.floating = None;
+        }
+    }
+
+    pub fn set_size_hints(&mut self, frame: xcb::x::Window, size_hints: SizeHints) {
+        if let Some(client) = self.clients.get_mut(&frame) {
+            client.size_hints = size_hints;
+        }
+    }
+
+    pub fn set_urgent(&mut self, frame: xcb::x::Window, urgent: bool) {
+        if let Some(client) = self.clients.get_mut(&frame) {
+            client.urgent = urgent;
+        }
+    }
+
+    pub fn set_position(&mut self, frame: xcb::x::Window, position: Position) {
+        if let Some(client) = self.clients.get_mut(&frame) {
+            client.position = Some(position);
+        }
+    }
+
+    pub fn set_pid(&mut self, frame: xcb::x::Window, pid: u32) {
+        if let Some(client) = self.clients.get_mut(&frame) {
+            client.pid = Some(pid);
+        }
+    }
+
+    pub fn is_swallowed(&self, frame: xcb::x::Window) -> bool {
+        self.swallowed
+            .iter()
+            .any(|(_, terminal)| terminal.eq(&frame))
+    }
+
+    /// makes the client take the place of the terminal it was launched from, the terminal
+    /// leaves its workspace until the client is gone
+    pub fn swallow(&mut self, terminal: xcb::x::Window, frame: xcb::x::Window) {
+        let Some(terminal_client) = self.clients.get(&terminal).cloned() else {
+            return;
+        };
+
+        for workspace in self
+            .screens
+            .iter_mut()
+            .flat_map(|screen| screen.workspaces_mut())
+        {
             workspace.remove_client(frame);
-            workspace.set_focused_client(workspace.clients().first().copied());
-            return Ok(self.clients.remove(&frame));
+            workspace.replace_client(terminal, frame);
+        }
+
+        if let Some(client) = self.clients.get_mut(&frame) {
+            client.workspace = terminal_client.workspace;
+            client.floating = terminal_client.floating;
+        }
+
+        self.swallowed.push((frame, terminal));
+    }
+
+    /// gives the place of a client that is going away back to the terminal it swallowed,
+    /// returning the frame of that terminal
+    pub fn unswallow(&mut self, frame: xcb::x::Window) -> Option<xcb::x::Window> {
+        let idx = self
+            .swallowed
+            .iter()
+            .position(|(swallower, _)| swallower.eq(&frame))?;
+        let (_, terminal) = self.swallowed.remove(idx);
+
+        for workspace in self
+            .screens
+            .iter_mut()
+            .flat_map(|screen| screen.workspaces_mut())
+        {
+            workspace.replace_client(frame, terminal);
+        }
+
+        let workspace = self.clients.get(&frame).map(|client| client.workspace);
+        if let Some((client, workspace)) = self.clients.get_mut(&terminal).zip(workspace) {
+            client.workspace = workspace;
+        }
+
+        Some(terminal)
+    }
+
+    /// drops every swallow involving a client that is gone
+    pub fn forget_swallowed(&mut self, frame: xcb::x::Window) {
+        self.swallowed
+            .retain(|(swallower, terminal)| swallower.ne(&frame) && terminal.ne(&frame));
+    }
+
+    /// sticky clients are tracked by the screen holding them
+    pub fn set_sticky(&mut self, frame: xcb::x::Window, sticky: bool) {
+        let Some(client) = self.clients.get_mut(&frame) else {
+            return;
+        };
+        client.sticky = sticky;
+
+        for screen in self.screens.iter_mut() {
+            let holds_client = screen
+                .workspaces()
+                .iter()
+                .any(|workspace| workspace.clients().contains(&frame));
+
+            if sticky && holds_client {
+                screen.add_sticky_client(frame);
+            } else {
+                screen.remove_sticky_client(frame);
+            }
+        }
+    }
+
+    pub fn set_fullscreen(&mut self, frame: xcb::x::Window, fullscreen: bool) {
+        if let Some(client) = self.clients.get_mut(&frame) {
+            client.fullscreen = fullscreen;
         }
-        Ok(None)
     }
 
     pub fn get_visible_screen_clients(&self, screen: &Screen) -> Vec<&Client> {
@@ -198,44 +842,161 @@ impl ScreenManager {
             .collect::<Vec<&Client>>()
     }
 
+    /// the screen containing the given point of the root window. When screens overlap, like
+    /// mirrored monitors, the first one wins
+    pub fn screen_at(&self, x: i32, y: i32) -> Option<usize> {
+        self.screens
+            .iter()
+            .position(|screen| is_cursor_inside(x, y, screen.position()))
+    }
+
     /// when the user cursor mover from a monitor to another we need to
     /// switch focus to that monitor,
     pub fn maybe_switch_screen(
         &mut self,
-        pointer: xcb::x::QueryPointerReply,
+        (cursor_x, cursor_y): (i32, i32),
         conn: &Arc<xcb::Connection>,
         atoms: &crate::atoms::Atoms,
     ) {
-        let (cursor_x, cursor_y) = (pointer.root_x(), pointer.root_y());
-
-        for (idx, screen) in self.screens.iter().enumerate() {
-            if is_cursor_inside(cursor_x.into(), cursor_y.into(), screen.position()) {
-                self.active_screen = idx;
-                self.update_atoms(atoms, conn);
-                if let Some(client) = self.get_focused_client() {
-                    ewmh_set_active_window(conn, self.root, atoms, client.window).ok();
-                    ewmh_set_focus(conn, atoms, client.window, EwmhFocusAction::Focus).ok();
-                }
+        if let Some(idx) = self.screen_at(cursor_x, cursor_y) {
+            self.active_screen = idx;
+            self.update_atoms(atoms, conn);
+            if let Some(client) = self.get_focused_client() {
+                ewmh_set_active_window(conn, self.root, atoms, client.window).ok();
+                ewmh_set_focus(conn, atoms, client.window, EwmhFocusAction::Focus).ok();
             }
         }
     }
 
+    pub fn add_subscriber(&mut self, connection: Arc<UnixStream>) {
+        self.subscribers.add(connection);
+    }
+
+    /// the monitor a global workspace lives on, the one showing it or else the one holding its
+    /// clients
+    pub fn workspace_screen(&self, workspace: u8) -> Option<usize> {
+        let workspace = workspace as usize;
+        self.screens
+            .iter()
+            .position(|screen| screen.active_workspace_id().eq(&workspace))
+            .or_else(|| {
+                self.screens.iter().position(|screen| {
+                    screen
+                        .workspaces()
+                        .get(workspace)
+                        .is_some_and(|workspace| !workspace.clients().is_empty())
+                })
+            })
+    }
+
+    /// brings a global workspace, along with its clients, over to `screen` from the monitor it
+    /// lives on, which must not be showing it
+    pub fn take_workspace(&mut self, screen: usize, workspace: u8) {
+        let Some(source) = self
+            .workspace_screen(workspace)
+            .filter(|source| source.ne(&screen))
+        else {
+            return;
+        };
+        if self.screens[source]
+            .active_workspace_id()
+            .eq(&(workspace as usize))
+        {
+            return;
+        }
+
+        let (low, high) = (screen.min(source), screen.max(source));
+        let (left, right) = self.screens.split_at_mut(high);
+        std::mem::swap(
+            &mut left[low].workspaces_mut()[workspace as usize],
+            &mut right[0].workspaces_mut()[workspace as usize],
+        );
+    }
+
+    /// windows of every managed client in the order they were created, so taskbars listing
+    /// them don't reorder them on every update
+    pub fn client_list(&self) -> Vec<xcb::x::Window> {
+        let mut clients = self.clients.values().collect::<Vec<_>>();
+        clients.sort_by_key(|client| client.sequence);
+        clients.into_iter().map(|client| client.window).collect()
+    }
+
+    /// windows of every managed client from the bottom of the stack up. Clients that were never
+    /// raised sit below the others, in the order they were created
+    pub fn client_list_stacking(&self) -> Vec<xcb::x::Window> {
+        let mut clients = self.clients.values().collect::<Vec<_>>();
+        clients.sort_by_key(|client| {
+            let position = self
+                .stacking
+                .iter()
+                .position(|frame| client.frame.eq(frame));
+            (position, client.sequence)
+        });
+        clients.into_iter().map(|client| client.window).collect()
+    }
+
+    /// records that `frames` were raised, in order, above every other client
+    pub fn raise_clients(&mut self, frames: &[xcb::x::Window]) {
+        let clients = &self.clients;
+        self.stacking
+            .retain(|frame| clients.contains_key(frame) && !frames.contains(frame));
+        self.stacking.extend_from_slice(frames);
+    }
+
     /// update every EWMH necessary atoms regarding the current context
     /// of the active screen.
     ///
     /// although techinically some of those atoms could be updated only
     /// when changed, this is a fair tradeoff as the performance impact of
     /// this is negligible
-    pub fn update_atoms(&self, atoms: &crate::atoms::Atoms, conn: &Arc<xcb::Connection>) {
+    ///
+    /// atoms are updated after every change to the state, which makes this the place where
+    /// ipc subscribers are notified of those changes as well, and where the client that got
+    /// the focus stops demanding attention
+    pub fn update_atoms(&mut self, atoms: &crate::atoms::Atoms, conn: &Arc<xcb::Connection>) {
+        self.record_focus();
+        self.collect_workspaces();
+
+        if let Some(client) = self.get_focused_client().filter(|client| client.urgent) {
+            let (frame, window) = (client.frame, client.window);
+            ewmh_set_wm_state(
+                conn,
+                atoms,
+                window,
+                atoms.net_wm_state_demands_attention,
+                false,
+            )
+            .ok();
+            self.set_urgent(frame, false);
+        }
+
         let screen = &self.screens[self.active_screen];
         ewmh_set_desktop_viewport(conn, self.root, &self.screens, atoms).ok();
         ewmh_set_number_of_desktops(conn, self.root, screen, atoms).ok();
         ewmh_set_current_desktop(conn, self.root, screen, atoms).ok();
         ewmh_set_desktop_names(conn, self.root, screen, atoms).ok();
-        ewmh_set_wm_desktop(conn, screen, &self.clients, atoms).ok();
-        ewmh_set_client_list(conn, self.root, self.clients.keys(), atoms).ok();
-        ewmh_set_client_list_stacking(conn, self.root, self.clients.keys(), atoms).ok();
+        ewmh_set_wm_desktop(conn, &self.screens, &self.clients, atoms).ok();
+        ewmh_set_client_list(conn, self.root, &self.client_list(), atoms).ok();
+        ewmh_set_client_list_stacking(conn, self.root, &self.client_list_stacking(), atoms).ok();
         ewmh_set_showing_desktop(conn, self.root, atoms, self.showing_desktop_status).ok();
+
+        self.subscribers.update(
+            self.screens
+                .iter()
+                .map(|screen| screen.active_workspace_id() + 1)
+                .collect(),
+            self.get_focused_client()
+                .map(|client| client.window.resource_id()),
+            self.clients
+                .values()
+                .map(|client| client.window.resource_id())
+                .collect(),
+            self.clients
+                .values()
+                .filter(|client| client.urgent)
+                .map(|client| client.window.resource_id())
+                .collect(),
+        );
     }
 }
 
@@ -316,4 +1077,333 @@ mod tests {
         let idx = sm.get_relative_screen_idx(Direction::Up);
         assert!(idx.is_none());
     }
+
+    #[test]
+    fn screen_at_picks_a_single_screen() {
+        let config = Rc::new(RefCell::new(Config::default()));
+        let root = unsafe { xcb::x::Window::new(0) };
+        let screens = vec![
+            Position::new(0, 0, 1920, 1080),
+            Position::new(1920, 0, 1920, 1080),
+            // mirrors the first screen
+            Position::new(0, 0, 1920, 1080),
+        ]
+        .into_iter()
+        .map(|position| Screen::new(&config, position))
+        .collect();
+        let sm = ScreenManager::new(screens, config, root);
+
+        assert_eq!(sm.screen_at(100, 100), Some(0));
+        // the edge shared by adjacent screens belongs to the one it starts
+        assert_eq!(sm.screen_at(1919, 500), Some(0));
+        assert_eq!(sm.screen_at(1920, 500), Some(1));
+        assert_eq!(sm.screen_at(3840, 500), None);
+        assert_eq!(sm.screen_at(-1, 0), None);
+    }
+
+    #[test]
+    fn moving_a_workspace_takes_its_clients_and_focus_along() {
+        let config = Rc::new(RefCell::new(Config::default()));
+        let screens = vec![
+            Screen::new(&config, Position::new(0, 0, 1920, 1080)),
+            Screen::new(&config, Position::new(1920, 0, 1920, 1080)),
+        ];
+        let root = unsafe { xcb::x::Window::new(0) };
+        let mut sm = ScreenManager::new(screens, config, root);
+        let (first, second) = unsafe { (xcb::x::Window::new(1), xcb::x::Window::new(2)) };
+        sm.create_client(first, first, None, None, None);
+        sm.create_client(second, second, None, None, None);
+        sm.float_client(second, Position::new(100, 100, 200, 200));
+
+        sm.move_workspace_to_screen(1);
+
+        assert_eq!(sm.active_screen_idx(), 1);
+        assert!(sm.screen(0).active_workspace().clients().is_empty());
+        assert_eq!(sm.screen(1).active_workspace().clients(), &[first, second]);
+        assert_eq!(
+            sm.get_focused_client().map(|client| client.frame),
+            Some(second)
+        );
+        assert_eq!(
+            sm.clients()[&second].floating,
+            Some(Position::new(2020, 100, 200, 200))
+        );
+    }
+
+    #[test]
+    fn smart_placement_avoids_other_floating_clients() {
+        let config = Rc::new(RefCell::new(Config::default()));
+        let area = Position::new(0, 0, 1000, 1000);
+        let screens = vec![Screen::new(&config, area.clone())];
+        let root = unsafe { xcb::x::Window::new(0) };
+        let mut sm = ScreenManager::new(screens, config, root);
+        let first = unsafe { xcb::x::Window::new(1) };
+        sm.create_client(first, first, None, None, None);
+
+        let centered = Position::new(0, 0, 400, 400).centered_in(&area);
+        let position = sm.smart_placement(0, centered, &area);
+        assert_eq!(position, Position::new(300, 300, 400, 400));
+        sm.float_client(first, position);
+
+        let centered = Position::new(0, 0, 300, 300).centered_in(&area);
+        let position = sm.smart_placement(0, centered, &area);
+        assert_eq!(position, Position::new(0, 0, 300, 300));
+        assert!(!position.overlaps(&sm.clients()[&first].floating.clone().unwrap()));
+    }
+
+    #[test]
+    fn cycling_focus_walks_every_workspace_and_wraps() {
+        let config = Rc::new(RefCell::new(Config::default()));
+        let screens = vec![Screen::new(&config, Position::new(0, 0, 1920, 1080))];
+        let root = unsafe { xcb::x::Window::new(0) };
+        let mut sm = ScreenManager::new(screens, config, root);
+        let (first, second, third) = unsafe {
+            (
+                xcb::x::Window::new(1),
+                xcb::x::Window::new(2),
+                xcb::x::Window::new(3),
+            )
+        };
+        sm.create_client(first, first, None, None, None);
+        sm.create_client(second, second, None, Some(2), None);
+        sm.create_client(third, third, None, Some(1), None);
+
+        assert_eq!(sm.cycle_target(true), Some((0, 1, third)));
+        assert_eq!(sm.cycle_target(false), Some((0, 2, second)));
+
+        sm.screen_mut(0).set_active_workspace(2);
+        assert_eq!(sm.cycle_target(true), Some((0, 0, first)));
+    }
+
+    #[test]
+    fn rules_can_keep_new_clients_from_taking_the_focus() {
+        let config = Rc::new(RefCell::new(Config::default()));
+        let screens = vec![Screen::new(&config, Position::new(0, 0, 1920, 1080))];
+        let root = unsafe { xcb::x::Window::new(0) };
+        let mut sm = ScreenManager::new(screens, config, root);
+        let (first, second, third) = unsafe {
+            (
+                xcb::x::Window::new(1),
+                xcb::x::Window::new(2),
+                xcb::x::Window::new(3),
+            )
+        };
+
+        sm.create_client(first, first, None, None, Some(false));
+        sm.create_client(second, second, None, None, Some(false));
+        assert_eq!(
+            sm.get_focused_client().map(|client| client.frame),
+            Some(first)
+        );
+
+        sm.create_client(third, third, None, None, None);
+        assert_eq!(
+            sm.get_focused_client().map(|client| client.frame),
+            Some(third)
+        );
+    }
+
+    #[test]
+    fn client_lists_keep_a_stable_order() {
+        let config = Rc::new(RefCell::new(Config::default()));
+        let screens = vec![Screen::new(&config, Position::new(0, 0, 1920, 1080))];
+        let root = unsafe { xcb::x::Window::new(0) };
+        let mut sm = ScreenManager::new(screens, config.clone(), root);
+
+        let windows = (1..=8)
+            .map(|id| unsafe { xcb::x::Window::new(id) })
+            .collect::<Vec<_>>();
+        for pair in windows.chunks(2).rev() {
+            sm.create_client(pair[0], pair[1], None, None, None);
+        }
+        let created = [windows[7], windows[5], windows[3], windows[1]];
+        assert_eq!(sm.client_list(), created);
+
+        sm.raise_clients(&[windows[4], windows[0]]);
+        assert_eq!(
+            sm.client_list_stacking(),
+            [windows[7], windows[3], windows[5], windows[1]]
+        );
+    }
+
+    #[test]
+    fn global_workspaces_are_taken_with_their_clients() {
+        let config = Rc::new(RefCell::new(Config::default()));
+        config
+            .borrow_mut()
+            .set("workspace_scope", "global")
+            .unwrap();
+        let screens = vec![
+            Screen::new(&config, Position::new(0, 0, 1920, 1080)),
+            Screen::new(&config, Position::new(1920, 0, 1920, 1080)),
+        ];
+        let root = unsafe { xcb::x::Window::new(0) };
+        let mut sm = ScreenManager::new(screens, config.clone(), root);
+
+        let (frame, window) = unsafe { (xcb::x::Window::new(1), xcb::x::Window::new(2)) };
+        sm.create_client(frame, window, None, Some(2), None);
+        assert_eq!(sm.workspace_screen(2), Some(0));
+
+        // a workspace is never taken away from the monitor showing it
+        sm.take_workspace(1, 0);
+        assert_eq!(sm.workspace_screen(0), Some(0));
+
+        sm.take_workspace(1, 2);
+        assert!(sm.screen(0).workspaces()[2].clients().is_empty());
+        assert_eq!(sm.screen(1).workspaces()[2].clients(), [frame]);
+        assert_eq!(sm.workspace_screen(2), Some(1));
+    }
+
+    #[test]
+    fn global_workspaces_grow_to_cover_every_monitor() {
+        let config = Rc::new(RefCell::new(Config::default()));
+        config
+            .borrow_mut()
+            .set("workspace_scope", "global")
+            .unwrap();
+        let count = config.borrow().workspaces() as usize + 1;
+        let screens = (0..count)
+            .map(|idx| Screen::new(&config, Position::new(idx as i32 * 1920, 0, 1920, 1080)))
+            .collect();
+        let root = unsafe { xcb::x::Window::new(0) };
+        let sm = ScreenManager::new(screens, config.clone(), root);
+
+        for idx in 0..count {
+            assert_eq!(sm.screen(idx).workspaces().len(), count);
+            assert_eq!(sm.workspace_screen(idx as u8), Some(idx));
+        }
+    }
+
+    #[test]
+    fn no_screens_is_an_error() {
+        let config = Rc::new(RefCell::new(Config::default()));
+        let root = unsafe { xcb::x::Window::new(0) };
+
+        assert!(ScreenManager::try_new(vec![], config, root).is_err());
+    }
+
+    #[test]
+    fn clients_on_other_monitors_follow_the_focus_policy() {
+        let config = Rc::new(RefCell::new(Config::default()));
+        let screens = vec![
+            Screen::new(&config, Position::new(0, 0, 1920, 1080)),
+            Screen::new(&config, Position::new(1920, 0, 1920, 1080)),
+        ];
+        let root = unsafe { xcb::x::Window::new(0) };
+        let mut sm = ScreenManager::new(screens, config.clone(), root);
+        let windows = (1..=3)
+            .map(|id| unsafe { xcb::x::Window::new(id) })
+            .collect::<Vec<_>>();
+
+        sm.create_client(windows[0], windows[0], Some(1), None, None);
+        sm.create_client(windows[1], windows[1], Some(1), None, None);
+        assert_eq!(sm.active_screen_idx(), 0);
+        assert_eq!(sm.screen(1).focused_client(), Some(windows[1]));
+
+        config
+            .borrow_mut()
+            .set("new_client_monitor_focus", "switch")
+            .unwrap();
+        sm.create_client(windows[2], windows[2], Some(1), None, None);
+        assert_eq!(sm.active_screen_idx(), 1);
+        assert_eq!(sm.screen(1).focused_client(), Some(windows[2]));
+    }
+
+    #[test]
+    fn workspace_switch_focus_falls_back_to_the_first_client() {
+        let config = Rc::new(RefCell::new(Config::default()));
+        let screens = vec![Screen::new(&config, Position::new(0, 0, 1000, 1000))];
+        let root = unsafe { xcb::x::Window::new(0) };
+        let mut sm = ScreenManager::new(screens, config.clone(), root);
+        let (first, second) = unsafe { (xcb::x::Window::new(1), xcb::x::Window::new(2)) };
+        sm.create_client(first, first, None, None, None);
+        sm.create_client(second, second, None, None, None);
+        sm.set_position(first, Position::new(0, 0, 500, 1000));
+        sm.set_position(second, Position::new(500, 0, 500, 1000));
+
+        sm.focus_after_switch(0, None);
+        assert_eq!(sm.screen(0).focused_client(), Some(second));
+
+        config
+            .borrow_mut()
+            .set("workspace_switch_focus", "cursor")
+            .unwrap();
+        sm.focus_after_switch(0, Some((100, 100)));
+        assert_eq!(sm.screen(0).focused_client(), Some(first));
+
+        sm.screen_mut(0)
+            .active_workspace_mut()
+            .set_focused_client(None);
+        sm.focus_after_switch(0, Some((2000, 2000)));
+        assert_eq!(sm.screen(0).focused_client(), Some(first));
+    }
+
+    #[test]
+    fn last_focused_client_is_found_on_any_screen() {
+        let config = Rc::new(RefCell::new(Config::default()));
+        let screens = vec![
+            Screen::new(&config, Position::new(0, 0, 1920, 1080)),
+            Screen::new(&config, Position::new(1920, 0, 1920, 1080)),
+        ];
+        let root = unsafe { xcb::x::Window::new(0) };
+        let mut sm = ScreenManager::new(screens, config.clone(), root);
+        let (first, second) = unsafe { (xcb::x::Window::new(1), xcb::x::Window::new(2)) };
+        assert!(sm.last_focused_target().is_none());
+
+        sm.create_client(first, first, None, Some(3), None);
+        sm.screen_mut(0).set_active_workspace(3);
+        sm.record_focus();
+        sm.create_client(second, second, Some(1), None, None);
+        sm.set_active_screen(1);
+        sm.record_focus();
+
+        assert_eq!(sm.last_focused_target(), Some((0, 3, first)));
+    }
+
+    #[test]
+    fn dynamic_workspaces_grow_on_demand_and_are_collected_when_empty() {
+        let config = Rc::new(RefCell::new(Config::default()));
+        let screens = vec![Screen::new(&config, Position::new(0, 0, 1000, 1000))];
+        let root = unsafe { xcb::x::Window::new(0) };
+        let mut sm = ScreenManager::new(screens, config.clone(), root);
+        let frame = unsafe { xcb::x::Window::new(1) };
+
+        assert!(!sm.ensure_workspace(0, 11));
+        assert_eq!(sm.screen(0).workspaces().len(), 9);
+
+        config
+            .borrow_mut()
+            .set("dynamic_workspaces", "true")
+            .unwrap();
+        assert!(sm.ensure_workspace(0, 11));
+        sm.create_client(frame, frame, None, Some(11), None);
+        sm.ensure_workspace(0, 14);
+        sm.collect_workspaces();
+        assert_eq!(sm.screen(0).workspaces().len(), 12);
+        assert_eq!(sm.screen(0).workspaces()[11].clients(), [frame]);
+
+        sm.screen_mut(0).workspaces_mut()[11].remove_client(frame);
+        sm.collect_workspaces();
+        assert_eq!(sm.screen(0).workspaces().len(), 9);
+    }
+
+    #[test]
+    fn moving_the_focused_client_away_focuses_the_previous_one() {
+        let config = Rc::new(RefCell::new(Config::default()));
+        let screens = vec![Screen::new(&config, Position::new(0, 0, 1000, 1000))];
+        let root = unsafe { xcb::x::Window::new(0) };
+        let mut sm = ScreenManager::new(screens, config, root);
+        let [a, b, c] = [1, 2, 3].map(|id| unsafe { xcb::x::Window::new(id) });
+        for frame in [a, b, c] {
+            sm.create_client(frame, frame, None, None, None);
+        }
+        let workspace = sm.screen_mut(0).active_workspace_mut();
+        workspace.set_focused_client(Some(b));
+        workspace.set_focused_client(Some(c));
+
+        sm.place_client(c, 0, 1);
+
+        assert_eq!(sm.screen(0).focused_client(), Some(b));
+        assert_eq!(sm.screen(0).workspaces()[1].clients(), [c]);
+    }
 }
@@ -0,0 +1,233 @@
+use crate::atoms::Atoms;
+use crate::ewmh::{ewmh_get_pid, WmClass};
+use crate::ipc::{display_name, runtime_dir};
+use crate::position::Position;
+use crate::screen::Client;
+use crate::screen_manager::ScreenManager;
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// the arrangement of every client, written before the window manager goes away so that the
+/// next instance can put the windows it adopts back where they were
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Session {
+    screens: Vec<SessionScreen>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SessionScreen {
+    active_workspace: u8,
+    /// clients of every workspace, in the order they are laid out
+    workspaces: Vec<Vec<SessionClient>>,
+}
+
+/// windows are identified across restarts by their process and `WM_CLASS`, as the window ids
+/// of the frames we created die with us
+#[derive(Debug, Serialize, Deserialize)]
+struct SessionClient {
+    pid: Option<u32>,
+    class: Option<String>,
+    floating: Option<Position>,
+}
+
+/// the session lives next to the ipc socket, on a directory only the user can reach, and is
+/// named after `$DISPLAY` so instances on different displays keep sessions of their own
+pub fn session_path() -> anyhow::Result<PathBuf> {
+    let name = match display_name() {
+        Some(display) => format!("lucky-session-{display}.json"),
+        None => String::from("lucky-session.json"),
+    };
+    Ok(runtime_dir()?.join(name))
+}
+
+/// what identifies a client across restarts, its process and the class of its window
+type Identity = (Option<u32>, Option<String>);
+
+fn identify(conn: &Arc<xcb::Connection>, atoms: &Atoms, client: &Client) -> Identity {
+    let pid = client
+        .pid
+        .or_else(|| ewmh_get_pid(conn, atoms, client.window));
+    let class = WmClass::get(conn, client.window).map(|name| name.class);
+    (pid, class)
+}
+
+impl Session {
+    pub fn capture(
+        screen_manager: &ScreenManager,
+        conn: &Arc<xcb::Connection>,
+        atoms: &Atoms,
+    ) -> Session {
+        Self::capture_with(screen_manager, |client| identify(conn, atoms, client))
+    }
+
+    fn capture_with(
+        screen_manager: &ScreenManager,
+        identify: impl Fn(&Client) -> Identity,
+    ) -> Session {
+        let screens = screen_manager
+            .screens()
+            .iter()
+            .map(|screen| SessionScreen {
+                active_workspace: screen.active_workspace_id() as u8,
+                workspaces: screen
+                    .workspaces()
+                    .iter()
+                    .map(|workspace| {
+                        workspace
+                            .clients()
+                            .iter()
+                            .filter_map(|frame| screen_manager.clients().get(frame))
+                            .map(|client| {
+                                let (pid, class) = identify(client);
+                                SessionClient {
+                                    pid,
+                                    class,
+                                    floating: client.floating.clone(),
+                                }
+                            })
+                            .collect()
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        Session { screens }
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        let path = session_path()?;
+        let file = std::fs::File::create(&path)
+            .with_context(|| format!("failed to create {}", path.display()))?;
+        serde_json::to_writer(file, self).context("failed to write the session")?;
+
+        Ok(())
+    }
+
+    /// reads the session left by the previous instance, the file is removed so that a session
+    /// is restored at most once
+    pub fn load() -> Option<Session> {
+        let path = session_path()
+            .inspect_err(|e| tracing::warn!("not restoring the session: {e:?}"))
+            .ok()?;
+        let source = std::fs::read_to_string(&path).ok()?;
+        std::fs::remove_file(&path).ok();
+
+        serde_json::from_str(&source)
+            .inspect_err(|e| tracing::warn!("ignoring invalid session at {}: {e}", path.display()))
+            .ok()
+    }
+
+    /// moves every client that matches a saved one back to its workspace, in the saved order.
+    /// Clients that match nothing stay where they were placed when adopted
+    pub fn restore(
+        self,
+        screen_manager: &mut ScreenManager,
+        conn: &Arc<xcb::Connection>,
+        atoms: &Atoms,
+    ) {
+        self.restore_with(screen_manager, |client| identify(conn, atoms, client));
+    }
+
+    fn restore_with(
+        self,
+        screen_manager: &mut ScreenManager,
+        identify: impl Fn(&Client) -> Identity,
+    ) {
+        let mut candidates = screen_manager
+            .clients()
+            .values()
+            .map(|client| {
+                let (pid, class) = identify(client);
+                (client.frame, pid, class)
+            })
+            .collect::<Vec<_>>();
+
+        let total_screens = screen_manager.screens().len();
+        for (screen_idx, screen) in self.screens.into_iter().enumerate() {
+            if screen_idx >= total_screens {
+                break;
+            }
+
+            for (workspace_id, clients) in screen.workspaces.into_iter().enumerate() {
+                if !screen_manager.ensure_workspace(screen_idx, workspace_id) {
+                    break;
+                }
+
+                for saved in clients {
+                    let Some(idx) = candidates
+                        .iter()
+                        .position(|(_, pid, class)| pid.eq(&saved.pid) && class.eq(&saved.class))
+                    else {
+                        continue;
+                    };
+
+                    let (frame, _, _) = candidates.remove(idx);
+                    screen_manager.place_client(frame, screen_idx, workspace_id as u8);
+                    if let Some(client) = screen_manager.clients_mut().get_mut(&frame) {
+                        client.floating = saved.floating;
+                    }
+                }
+            }
+
+            if screen_manager.ensure_workspace(screen_idx, screen.active_workspace as usize) {
+                screen_manager
+                    .screen_mut(screen_idx)
+                    .set_active_workspace(screen.active_workspace);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::screen::Screen;
+    use config::Config;
+    use std::{cell::RefCell, rc::Rc};
+    use xcb::{Xid, XidNew};
+
+    #[test]
+    fn sessions_put_clients_back_on_their_workspaces() {
+        let config = Rc::new(RefCell::new(Config::default()));
+        let new_manager = || {
+            let screens = vec![Screen::new(&config, Position::new(0, 0, 1000, 1000))];
+            ScreenManager::new(screens, config.clone(), unsafe { xcb::x::Window::new(0) })
+        };
+        // the frames change between instances, the process behind each window does not
+        let identify = |client: &Client| (Some(client.window.resource_id()), None);
+        let [a, b, c] = [1, 2, 3].map(|id| unsafe { xcb::x::Window::new(id) });
+
+        let mut before = new_manager();
+        before.create_client(a, a, None, Some(2), None);
+        before.create_client(b, b, None, Some(0), None);
+        before.create_client(c, c, None, Some(2), None);
+        before.screen_mut(0).set_active_workspace(2);
+        let session = Session::capture_with(&before, identify);
+
+        let mut after = new_manager();
+        for frame in [c, b, a] {
+            let window = unsafe { xcb::x::Window::new(frame.resource_id()) };
+            after.create_client(
+                unsafe { xcb::x::Window::new(frame.resource_id() + 10) },
+                window,
+                None,
+                None,
+                None,
+            );
+        }
+        session.restore_with(&mut after, identify);
+
+        let frames = |workspace: usize| {
+            after.screen(0).workspaces()[workspace]
+                .clients()
+                .iter()
+                .map(|frame| frame.resource_id() - 10)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(frames(0), [2]);
+        assert_eq!(frames(2), [1, 3]);
+        assert_eq!(after.screen(0).active_workspace_id(), 2);
+    }
+}